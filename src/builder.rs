@@ -0,0 +1,106 @@
+use crate::{Bicycle, EscapeFn, HelperDef, JsonMap};
+use std::fmt::{self, Debug};
+
+/// Configures a [`Bicycle`] piece by piece, as an alternative to passing
+/// everything to [`Bicycle::new`] at once. Created by [`Bicycle::builder`].
+///
+/// # Examples
+/// ```
+/// use bicycle::{handlebars::handlebars_helper, Bicycle, EscapeFn};
+///
+/// handlebars_helper!(shout: |s: str| s.to_uppercase());
+/// handlebars_helper!(whisper: |s: str| s.to_lowercase());
+///
+/// let bike = Bicycle::builder()
+///     .escape_fn(EscapeFn::Html)
+///     .helper("shout", Box::new(shout))
+///     .helper("whisper", Box::new(whisper))
+///     .strict_mode(false)
+///     .build();
+/// let rendered = bike.render("{{shout name}}{{missing}}", |map| {
+///     map.insert("name", "<rei>");
+/// }).unwrap();
+/// assert_eq!(rendered, "&lt;REI&gt;");
+/// ```
+pub struct BicycleBuilder {
+    escape_fn: EscapeFn,
+    helpers: Vec<(String, Box<dyn HelperDef + Send + Sync + 'static>)>,
+    base_data: JsonMap,
+    strict_mode: bool,
+}
+
+impl Debug for BicycleBuilder {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.debug_struct("BicycleBuilder")
+            .field("escape_fn", &self.escape_fn)
+            .field(
+                "helpers",
+                &self
+                    .helpers
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .field("base_data", &self.base_data)
+            .field("strict_mode", &self.strict_mode)
+            .finish()
+    }
+}
+
+impl Default for BicycleBuilder {
+    fn default() -> Self {
+        Self {
+            escape_fn: Default::default(),
+            helpers: Vec::new(),
+            base_data: Default::default(),
+            strict_mode: true,
+        }
+    }
+}
+
+impl BicycleBuilder {
+    /// Sets how template variables are escaped. The default is
+    /// [`EscapeFn::None`].
+    pub fn escape_fn(mut self, escape_fn: EscapeFn) -> Self {
+        self.escape_fn = escape_fn;
+        self
+    }
+
+    /// Registers a helper, replacing any helper that was already registered
+    /// under the same name.
+    pub fn helper(
+        mut self,
+        name: &str,
+        helper: Box<dyn HelperDef + Send + Sync + 'static>,
+    ) -> Self {
+        self.helpers.retain(|(existing, _)| existing != name);
+        self.helpers.push((name.to_owned(), helper));
+        self
+    }
+
+    /// Sets the data that's available to every render, replacing any base data
+    /// set previously.
+    pub fn base_data(mut self, base_data: JsonMap) -> Self {
+        self.base_data = base_data;
+        self
+    }
+
+    /// Sets whether or not a missing value is an error when rendering. See
+    /// [`Bicycle::set_strict_mode`].
+    pub fn strict_mode(mut self, strict: bool) -> Self {
+        self.strict_mode = strict;
+        self
+    }
+
+    /// Creates the configured [`Bicycle`].
+    pub fn build(self) -> Bicycle {
+        let (names, helpers): (Vec<_>, Vec<_>) = self.helpers.into_iter().unzip();
+        let mut bike = Bicycle::new(
+            self.escape_fn,
+            names.iter().map(String::as_str).zip(helpers),
+            self.base_data,
+        );
+        bike.set_strict_mode(self.strict_mode);
+        bike
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{Bicycle, JsonMap, RenderingError};
+use handlebars::Template;
+
+/// A template that's already been parsed, produced by [`Bicycle::compile`].
+///
+/// Rendering a `CompiledTemplate` skips the parsing step that
+/// [`Bicycle::render`] performs on every call, while still using the helpers
+/// and base data of the [`Bicycle`] it was compiled by.
+#[derive(Debug)]
+pub struct CompiledTemplate<'bike> {
+    bicycle: &'bike Bicycle,
+    template: Template,
+}
+
+impl<'bike> CompiledTemplate<'bike> {
+    pub(crate) fn new(bicycle: &'bike Bicycle, template: Template) -> Self {
+        Self { bicycle, template }
+    }
+
+    /// Renders the template.
+    ///
+    /// Use `insert_data` to define any variables needed for the template.
+    pub fn render(&self, insert_data: impl FnOnce(&mut JsonMap)) -> Result<String, RenderingError> {
+        self.bicycle.render_compiled(&self.template, insert_data)
+    }
+}
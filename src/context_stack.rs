@@ -0,0 +1,151 @@
+use crate::{Action, JsonMap, ProcessingError};
+use serde_json::value::{Map, Value as Json};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The reserved key that [`ContextStack::flatten`] puts the enclosing context
+/// under.
+pub const PARENT_KEY: &str = "parent";
+
+/// A stack of data contexts, where each context is layered over the ones
+/// beneath it. This is useful for hierarchical generators, where something
+/// nested (like a module's items) needs its own data as well as that of its
+/// parent.
+///
+/// # Examples
+/// ```
+/// use bicycle::{ContextStack, JsonMap};
+///
+/// let mut outer = JsonMap::default();
+/// outer.insert("name", "crate");
+/// outer.insert("edition", 2021);
+/// let mut inner = JsonMap::default();
+/// inner.insert("name", "module");
+///
+/// let mut stack = ContextStack::new();
+/// stack.push(outer);
+/// stack.push(inner);
+/// let mut expected = JsonMap::default();
+/// expected.insert("name", "module");
+/// expected.insert("edition", 2021);
+/// expected.insert("parent", serde_json::json!({
+///     "name": "crate",
+///     "edition": 2021,
+/// }));
+/// assert_eq!(stack.flatten(), expected);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ContextStack {
+    frames: Vec<JsonMap>,
+}
+
+impl ContextStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pushes a context, which takes precedence over all of the others.
+    pub fn push(&mut self, frame: JsonMap) {
+        self.frames.push(frame);
+    }
+
+    /// Pops the most recently pushed context.
+    pub fn pop(&mut self) -> Option<JsonMap> {
+        self.frames.pop()
+    }
+
+    /// The number of contexts on the stack.
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Merges the stack into a single context. Top-level keys from later
+    /// contexts override those from earlier ones, and if there's more than one
+    /// context, the result of flattening all but the last one is available
+    /// under [`PARENT_KEY`] (which itself has a `parent`, and so on).
+    pub fn flatten(&self) -> JsonMap {
+        let mut flat = Map::new();
+        for (index, frame) in self.frames.iter().enumerate() {
+            let parent = (index > 0).then(|| flat.clone());
+            flat.extend(frame.0.clone());
+            if let Some(parent) = parent {
+                flat.insert(PARENT_KEY.to_owned(), Json::Object(parent));
+            }
+        }
+        JsonMap(flat)
+    }
+}
+
+/// Tracks the data contributed by each directory between the root of a
+/// traversal and the template being rendered. The stack is only popped and
+/// pushed as needed, so traversing in pre-order reads each data file once.
+#[derive(Debug)]
+pub(crate) struct DirectoryContext<'a> {
+    root: &'a Path,
+    name: Option<&'a str>,
+    dirs: Vec<PathBuf>,
+    stack: ContextStack,
+}
+
+impl<'a> DirectoryContext<'a> {
+    pub(crate) fn new(root: &'a Path, name: Option<&'a str>) -> Self {
+        Self {
+            root,
+            name,
+            dirs: Vec::new(),
+            stack: ContextStack::new(),
+        }
+    }
+
+    /// The flattened context for an action that renders a template, or `None`
+    /// if directory data is disabled or doesn't apply.
+    pub(crate) fn data_for(&mut self, action: &Action) -> Result<Option<JsonMap>, ProcessingError> {
+        let name = match self.name {
+            Some(name) if action.tag().renders() => name,
+            _ => return Ok(None),
+        };
+        let dir = match action.src().parent() {
+            Some(dir) if dir.starts_with(self.root) => dir,
+            _ => return Ok(None),
+        };
+        while self.dirs.last().is_some_and(|last| !dir.starts_with(last)) {
+            self.dirs.pop();
+            self.stack.pop();
+        }
+        let last = self.dirs.last().cloned();
+        let mut missing = dir
+            .ancestors()
+            .take_while(|ancestor| {
+                Some(*ancestor) != last.as_deref() && ancestor.starts_with(self.root)
+            })
+            .collect::<Vec<_>>();
+        missing.reverse();
+        for dir in missing {
+            self.stack.push(read_frame(&dir.join(name))?);
+            self.dirs.push(dir.to_owned());
+        }
+        Ok(Some(self.stack.flatten()))
+    }
+}
+
+fn read_frame(path: &Path) -> Result<JsonMap, ProcessingError> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(JsonMap::default()),
+        Err(cause) => {
+            return Err(ProcessingError::DirectoryDataReadFailed {
+                path: path.to_owned(),
+                cause,
+            })
+        }
+    };
+    log::info!("using directory data from {:?}", path);
+    serde_json::from_slice(&bytes)
+        .map(JsonMap)
+        .map_err(|cause| ProcessingError::DirectoryDataParseFailed {
+            path: path.to_owned(),
+            cause,
+        })
+}
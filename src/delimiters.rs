@@ -0,0 +1,63 @@
+/// An alternative pair of delimiters, which get translated into `{{` and `}}`
+/// prior to rendering. See [`Bicycle::set_delimiters`](crate::Bicycle::set_delimiters).
+#[derive(Clone, Debug)]
+pub(crate) struct Delimiters {
+    open: String,
+    close: String,
+}
+
+impl Delimiters {
+    pub(crate) fn new(open: &str, close: &str) -> Self {
+        assert!(
+            !open.is_empty() && !close.is_empty(),
+            "delimiters can't be empty"
+        );
+        Self {
+            open: open.to_owned(),
+            close: close.to_owned(),
+        }
+    }
+
+    pub(crate) fn open(&self) -> &str {
+        &self.open
+    }
+
+    /// Rewrites `template` so that the configured delimiters are replaced by
+    /// `{{`/`}}`, and any literal `{{` is escaped so that handlebars leaves
+    /// it alone.
+    pub(crate) fn translate(&self, template: &str) -> String {
+        let mut translated = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(c) = rest.chars().next() {
+            let escaped = rest.strip_prefix('\\');
+            if let Some(after) = escaped.and_then(|rest| rest.strip_prefix(self.open.as_str())) {
+                translated.push_str(&self.open);
+                rest = after;
+            } else if let Some(after) =
+                escaped.and_then(|rest| rest.strip_prefix(self.close.as_str()))
+            {
+                translated.push_str(&self.close);
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix(self.open.as_str()) {
+                translated.push_str("{{");
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix(self.close.as_str()) {
+                translated.push_str("}}");
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("\\{{") {
+                // Handlebars reads `\\{{` as a backslash followed by a live
+                // mustache, so the backslash is kept apart from the escaped
+                // `{{` with an empty comment.
+                translated.push_str("\\\\{{!}}\\{{");
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("{{") {
+                translated.push_str("\\{{");
+                rest = after;
+            } else {
+                translated.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        translated
+    }
+}
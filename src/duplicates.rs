@@ -0,0 +1,200 @@
+use crate::Action;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs,
+    hash::Hasher as _,
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+
+/// An error encountered when checking source files for duplicates.
+#[derive(Debug, Error)]
+pub enum DuplicateDetectionError {
+    #[error("Failed to get metadata for {path:?}: {cause}")]
+    MetadataFailed {
+        path: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    #[error("Failed to read {path:?}: {cause}")]
+    ReadFailed {
+        path: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+}
+
+/// Groups [`Tag::CopyFile`](crate::Tag::CopyFile) actions whose sources have
+/// identical contents, so that callers can decide whether to hard link them,
+/// copy once, or otherwise avoid redundant work.
+///
+/// Only groups with more than one action are returned, in the order their
+/// first member appears in `actions`. Templates are never considered, since
+/// their output depends on the data they're rendered with. On Unix, hard links
+/// to the same file are detected by inode without reading their contents.
+pub fn find_duplicate_sources<'a>(
+    actions: impl IntoIterator<Item = &'a Action>,
+) -> Result<Vec<Vec<&'a Action>>, DuplicateDetectionError> {
+    // Group hard links first, since they're known to be identical.
+    let mut links = Buckets::default();
+    let copies = actions
+        .into_iter()
+        .filter(|action| action.tag().copy_file());
+    for (index, action) in copies.enumerate() {
+        let metadata = fs::metadata(action.src()).map_err(|cause| {
+            DuplicateDetectionError::MetadataFailed {
+                path: action.src().to_owned(),
+                cause,
+            }
+        })?;
+        // Without a file ID, every file has to be treated as distinct.
+        let id = file_id(&metadata).ok_or(index);
+        links.insert((metadata.len(), id), (index, action));
+    }
+    // Same size is a cheap prerequisite for same contents.
+    let mut by_size = Buckets::default();
+    for ((size, _), hard_links) in links.into_groups() {
+        by_size.insert(size, hard_links);
+    }
+    let mut duplicates = Vec::new();
+    for (_, candidates) in by_size.into_groups() {
+        if candidates.len() == 1 {
+            let hard_links = candidates.into_iter().next().unwrap();
+            if hard_links.len() > 1 {
+                duplicates.push(hard_links);
+            }
+            continue;
+        }
+        let mut by_hash = Buckets::default();
+        for hard_links in candidates {
+            by_hash.insert(hash_contents(hard_links[0].1.src())?, hard_links);
+        }
+        for (_, candidates) in by_hash.into_groups() {
+            // Don't trust the hash alone, since collisions are possible.
+            let mut confirmed: Vec<Vec<(usize, &'a Action)>> = Vec::new();
+            for hard_links in candidates {
+                let mut matched = None;
+                for (index, group) in confirmed.iter().enumerate() {
+                    if same_contents(group[0].1.src(), hard_links[0].1.src())? {
+                        matched = Some(index);
+                        break;
+                    }
+                }
+                match matched {
+                    Some(index) => confirmed[index].extend(hard_links),
+                    None => confirmed.push(hard_links),
+                }
+            }
+            duplicates.extend(confirmed.into_iter().filter(|group| group.len() > 1));
+        }
+    }
+    for group in &mut duplicates {
+        group.sort_unstable_by_key(|(index, _)| *index);
+    }
+    duplicates.sort_unstable_by_key(|group| group[0].0);
+    Ok(duplicates
+        .into_iter()
+        .map(|group| group.into_iter().map(|(_, action)| action).collect())
+        .collect())
+}
+
+/// Groups values by key, preserving the order in which keys were first seen.
+struct Buckets<K, V> {
+    indices: HashMap<K, usize>,
+    groups: Vec<(K, Vec<V>)>,
+}
+
+impl<K, V> Default for Buckets<K, V> {
+    fn default() -> Self {
+        Self {
+            indices: Default::default(),
+            groups: Default::default(),
+        }
+    }
+}
+
+impl<K: Clone + Eq + std::hash::Hash, V> Buckets<K, V> {
+    fn insert(&mut self, key: K, value: V) {
+        let groups = &mut self.groups;
+        let index = *self.indices.entry(key.clone()).or_insert_with(|| {
+            groups.push((key, Vec::new()));
+            groups.len() - 1
+        });
+        self.groups[index].1.push(value);
+    }
+
+    fn into_groups(self) -> Vec<(K, Vec<V>)> {
+        self.groups
+    }
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt as _;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+fn open(path: &Path) -> Result<BufReader<fs::File>, DuplicateDetectionError> {
+    fs::File::open(path)
+        .map(BufReader::new)
+        .map_err(|cause| DuplicateDetectionError::ReadFailed {
+            path: path.to_owned(),
+            cause,
+        })
+}
+
+fn read_chunk(
+    path: &Path,
+    reader: &mut impl Read,
+    buf: &mut [u8],
+) -> Result<usize, DuplicateDetectionError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(cause) => {
+                return Err(DuplicateDetectionError::ReadFailed {
+                    path: path.to_owned(),
+                    cause,
+                })
+            }
+        }
+    }
+    Ok(filled)
+}
+
+fn hash_contents(path: &Path) -> Result<u64, DuplicateDetectionError> {
+    let mut reader = open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buf = [0; 8192];
+    loop {
+        let n = read_chunk(path, &mut reader, &mut buf)?;
+        if n == 0 {
+            break Ok(hasher.finish());
+        }
+        hasher.write(&buf[..n]);
+    }
+}
+
+fn same_contents(a: &Path, b: &Path) -> Result<bool, DuplicateDetectionError> {
+    let (mut reader_a, mut reader_b) = (open(a)?, open(b)?);
+    let (mut buf_a, mut buf_b) = ([0; 8192], [0; 8192]);
+    loop {
+        let n_a = read_chunk(a, &mut reader_a, &mut buf_a)?;
+        let n_b = read_chunk(b, &mut reader_b, &mut buf_b)?;
+        if buf_a[..n_a] != buf_b[..n_b] {
+            break Ok(false);
+        }
+        if n_a == 0 {
+            break Ok(true);
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    any::Any,
+    cell::RefCell,
+    panic::{self, AssertUnwindSafe},
+};
+
+thread_local! {
+    // Escape functions are called on the thread doing the rendering, so this
+    // can't be clobbered by renders happening on other threads.
+    static PANIC: RefCell<Option<EscapeFnPanic>> = const { RefCell::new(None) };
+}
+
+/// Details of a panic caught by [`catching`].
+#[derive(Debug)]
+pub(crate) struct EscapeFnPanic {
+    pub(crate) input: String,
+    pub(crate) message: String,
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    payload
+        .downcast::<String>()
+        .map(|message| *message)
+        .or_else(|payload| {
+            payload
+                .downcast::<&str>()
+                .map(|message| message.to_string())
+        })
+        .unwrap_or_else(|_| "Box<dyn Any>".to_owned())
+}
+
+/// Wraps `escape_fn` so that panics are caught rather than unwinding through
+/// `handlebars`. The first panic is recorded for [`take_panic`] to retrieve,
+/// and the offending input is replaced with an empty string.
+pub(crate) fn catching(
+    escape_fn: impl Fn(&str) -> String + Send + Sync,
+) -> impl Fn(&str) -> String + Send + Sync {
+    move |input: &str| {
+        panic::catch_unwind(AssertUnwindSafe(|| escape_fn(input))).unwrap_or_else(|payload| {
+            let message = panic_message(payload);
+            log::error!(
+                "custom escape function panicked on input {:?}: {}",
+                input,
+                message
+            );
+            PANIC.with(|panic| {
+                panic.borrow_mut().get_or_insert_with(|| EscapeFnPanic {
+                    input: input.to_owned(),
+                    message,
+                });
+            });
+            String::new()
+        })
+    }
+}
+
+/// Retrieves the panic caught since the last call, if any.
+pub(crate) fn take_panic() -> Option<EscapeFnPanic> {
+    PANIC.with(|panic| panic.borrow_mut().take())
+}
@@ -0,0 +1,154 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Where [`Bicycle::process_with_fs`](crate::Bicycle::process_with_fs) writes
+/// its output, along with reading back what's already there for things like
+/// [`Bicycle::set_overwrite_policy`](crate::Bicycle::set_overwrite_policy).
+///
+/// Only the destination goes through this; templates and other source files
+/// are always read from disk.
+pub trait FileSystem {
+    /// Creates a directory, along with any missing parents. A directory that
+    /// already exists is fine.
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Copies the source file at `src` to `dst`, replacing whatever's there.
+    fn copy(&self, src: &Path, dst: &Path) -> io::Result<()>;
+
+    /// Reads the file at `path`, which fails with [`io::ErrorKind::NotFound`]
+    /// if there isn't one.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Writes `contents` to the file at `path`, replacing whatever's there.
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+}
+
+impl<F: FileSystem + ?Sized> FileSystem for &F {
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        (**self).create_dir_all(path)
+    }
+
+    fn copy(&self, src: &Path, dst: &Path) -> io::Result<()> {
+        (**self).copy(src, dst)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        (**self).read(path)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        (**self).write(path, contents)
+    }
+}
+
+/// The real filesystem, using [`std::fs`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealFs;
+
+impl FileSystem for RealFs {
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn copy(&self, src: &Path, dst: &Path) -> io::Result<()> {
+        fs::copy(src, dst).map(drop)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+}
+
+/// Keeps files in a map from their path to their contents, which is handy for
+/// testing scaffolding logic without touching the disk. Unlike a
+/// [`MemorySink`](crate::MemorySink), what's already there is visible to
+/// processing, so overwrite policies and appending work the same way they do
+/// on disk.
+///
+/// Directories are tracked separately, and don't need to exist for files to
+/// be written inside of them.
+///
+/// # Examples
+/// ```no_run
+/// use bicycle::{Bicycle, MemFs};
+///
+/// let fs = MemFs::default();
+/// Bicycle::default()
+///     .process_with_fs("templates", "out", &fs, |map| map.insert("name", "shinji"))
+///     .unwrap();
+/// for (path, contents) in fs.into_files() {
+///     println!("{:?}: {} bytes", path, contents.len());
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct MemFs {
+    dirs: Mutex<HashSet<PathBuf>>,
+    files: Mutex<HashMap<PathBuf, Vec<u8>>>,
+}
+
+impl MemFs {
+    /// Starts out with `files` already in place.
+    pub fn with_files(files: HashMap<PathBuf, Vec<u8>>) -> Self {
+        Self {
+            dirs: Default::default(),
+            files: Mutex::new(files),
+        }
+    }
+
+    /// The directories that were created, including their parents.
+    pub fn dirs(&self) -> HashSet<PathBuf> {
+        self.dirs.lock().unwrap().clone()
+    }
+
+    /// The contents of the file at `path`, if there is one.
+    pub fn file(&self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+        self.files.lock().unwrap().get(path.as_ref()).cloned()
+    }
+
+    /// Returns the contents of each file.
+    pub fn into_files(self) -> HashMap<PathBuf, Vec<u8>> {
+        self.files.into_inner().unwrap()
+    }
+}
+
+impl FileSystem for MemFs {
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut dirs = self.dirs.lock().unwrap();
+        for ancestor in path.ancestors() {
+            if ancestor.as_os_str().is_empty() || !dirs.insert(ancestor.to_owned()) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn copy(&self, src: &Path, dst: &Path) -> io::Result<()> {
+        let contents = fs::read(src)?;
+        self.write(dst, &contents)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.file(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{:?} isn't in the `MemFs`", path),
+            )
+        })
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(path.to_owned(), contents.to_vec());
+        Ok(())
+    }
+}
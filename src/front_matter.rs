@@ -0,0 +1,69 @@
+use crate::ProcessingError;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Per-template settings, read from a YAML block between `---` lines at the
+/// very start of a template. See
+/// [`Bicycle::set_front_matter`](crate::Bicycle::set_front_matter).
+///
+/// ```text
+/// ---
+/// filename: "{{name}}.rs"
+/// data:
+///   greeting: Hello
+/// ---
+/// // {{greeting}} from {{name}}!
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct FrontMatter {
+    /// Replaces the file name of the template's destination, keeping it in
+    /// the same directory. This is rendered like any other destination path.
+    pub filename: Option<String>,
+    /// Skips the template entirely.
+    pub skip: bool,
+    /// Data that's only available to this template, which overrides values of
+    /// the same name from anywhere else.
+    pub data: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Splits the front matter (if any) off of `template`, returning it alongside
+/// the rest of the template. `src` is only used for error reporting.
+///
+/// A template without front matter, including one whose opening `---` is
+/// never closed, gets the default front matter and is returned as-is.
+pub(crate) fn split<'a>(
+    src: &Path,
+    template: &'a str,
+) -> Result<(FrontMatter, &'a str), ProcessingError> {
+    let (yaml, body) = match find(template) {
+        Some(found) => found,
+        None => return Ok((FrontMatter::default(), template)),
+    };
+    // An empty block would otherwise parse as null.
+    if yaml.trim().is_empty() {
+        return Ok((FrontMatter::default(), body));
+    }
+    serde_yaml::from_str(yaml)
+        .map(|front_matter| (front_matter, body))
+        .map_err(|cause| ProcessingError::FrontMatterParseFailed {
+            path: src.to_owned(),
+            cause,
+        })
+}
+
+/// Finds the YAML between the opening and closing `---` lines, and the body
+/// after them.
+fn find(template: &str) -> Option<(&str, &str)> {
+    let rest = template
+        .strip_prefix("---\n")
+        .or_else(|| template.strip_prefix("---\r\n"))?;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end_matches(['\r', '\n']) == "---" {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Component, Path},
+};
+
+/// Specifies how [`Bicycle::set_mark_generated`](crate::Bicycle::set_mark_generated)
+/// writes the `.gitattributes` file at the root of the destination.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GitAttributesMode {
+    /// Adds lines to the existing file, creating it if needed. Lines that are
+    /// already present aren't added again.
+    Append,
+    /// Replaces the existing file, if any, with one containing only the lines
+    /// for this run.
+    Overwrite,
+}
+
+pub(crate) const FILE_NAME: &str = ".gitattributes";
+
+/// Formats `path` (relative to the root) as an anchored gitattributes pattern.
+fn pattern(path: &Path) -> Option<String> {
+    let mut pattern = String::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => {
+                pattern.push('/');
+                for c in name.to_str()?.chars() {
+                    if matches!(c, '*' | '?' | '[' | '\\' | '!' | '#') {
+                        pattern.push('\\');
+                    }
+                    pattern.push(c);
+                }
+            }
+            Component::CurDir => (),
+            _ => return None,
+        }
+    }
+    // Unquoted patterns end at whitespace.
+    if pattern.contains(char::is_whitespace) {
+        pattern = format!("\"{}\"", pattern.replace('\\', "\\\\").replace('"', "\\\""));
+    }
+    Some(pattern)
+}
+
+/// Marks each of `paths` (relative to `root`) as `linguist-generated` in
+/// `root/.gitattributes`.
+pub(crate) fn mark_generated<'a>(
+    root: &Path,
+    mode: GitAttributesMode,
+    paths: impl Iterator<Item = &'a Path>,
+) -> io::Result<()> {
+    let file = root.join(FILE_NAME);
+    let mut contents = match mode {
+        GitAttributesMode::Append => match fs::read_to_string(&file) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        },
+        GitAttributesMode::Overwrite => String::new(),
+    };
+    let mut lines = contents
+        .lines()
+        .map(ToOwned::to_owned)
+        .collect::<std::collections::HashSet<_>>();
+    for path in paths {
+        match pattern(path) {
+            Some(pattern) => {
+                let line = format!("{} linguist-generated=true", pattern);
+                if lines.insert(line.clone()) {
+                    if !contents.is_empty() && !contents.ends_with('\n') {
+                        contents.push('\n');
+                    }
+                    contents.push_str(&line);
+                    contents.push('\n');
+                }
+            }
+            None => log::warn!("couldn't express {:?} in {}", path, FILE_NAME),
+        }
+    }
+    log::info!("marking generated files in {:?}", file);
+    fs::write(file, contents)
+}
@@ -0,0 +1,65 @@
+use handlebars::{Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, ScopedJson};
+use serde_json::Value as Json;
+use std::path::Path;
+
+/// The key that [`Bicycle::set_destination_metadata`](crate::Bicycle::set_destination_metadata)
+/// puts the destination under.
+pub(crate) const DESTINATION_KEY: &str = "destination";
+
+/// The name of the [`DestExists`] helper.
+pub(crate) const DEST_EXISTS: &str = "dest_exists";
+
+/// Checks whether the destination of the template being rendered exists, or
+/// with a parameter, whether a path relative to the destination's directory
+/// exists.
+pub(crate) struct DestExists;
+
+impl HelperDef for DestExists {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        helper: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let dst = ctx
+            .data()
+            .get(DESTINATION_KEY)
+            .and_then(|destination| destination.get("path"))
+            .and_then(Json::as_str)
+            .map(Path::new)
+            .ok_or_else(|| {
+                RenderError::new(
+                    "`dest_exists` is only available when processing templates into a directory",
+                )
+            })?;
+        let path = match helper.param(0) {
+            Some(param) => {
+                let relative = param.value().as_str().ok_or_else(|| {
+                    RenderError::new("`dest_exists` expects its parameter to be a path string")
+                })?;
+                dst.parent().unwrap_or_else(|| Path::new("")).join(relative)
+            }
+            None => dst.to_owned(),
+        };
+        Ok(ScopedJson::Derived(Json::Bool(path.exists())))
+    }
+}
+
+/// Takes the place of [`DestExists`] once destination metadata is disabled
+/// again, since Handlebars can't unregister helpers.
+pub(crate) struct DestExistsDisabled;
+
+impl HelperDef for DestExistsDisabled {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        _: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        Err(RenderError::new(
+            "`dest_exists` isn't available, since destination metadata is disabled",
+        ))
+    }
+}
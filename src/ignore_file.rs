@@ -0,0 +1,85 @@
+use crate::{traverse_filtered, Action, TraversalError};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::{
+    collections::VecDeque,
+    error::Error as StdError,
+    fmt::{Debug, Display},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The name of the ignore file read by [`traverse_with_ignore`].
+pub const IGNORE_FILE_NAME: &str = ".bicycleignore";
+
+/// The same as [`traverse`](crate::traverse), but skips everything in `src`
+/// matched by a [`IGNORE_FILE_NAME`] file in the root of `src` or by
+/// `ignore_patterns`, the same way `git` skips what's matched by a
+/// `.gitignore`. Requires the `ignore` feature.
+///
+/// Patterns use gitignore syntax, so `*.log` matches at any depth, `/notes.md`
+/// only matches in the root, `target/` only matches directories, and `!`
+/// negates an earlier pattern. `ignore_patterns` are applied after the ignore
+/// file, so they can override it. Ignored directories are skipped along with
+/// everything in them, and the ignore file itself is never part of the output.
+///
+/// # Examples
+/// ```no_run
+/// let actions = bicycle::traverse_with_ignore(
+///     "templates",
+///     "out",
+///     bicycle::no_transform,
+///     bicycle::DEFAULT_TEMPLATE_EXT,
+///     &[".git/".to_owned(), ".DS_Store".to_owned()],
+/// )
+/// .unwrap();
+/// ```
+pub fn traverse_with_ignore<E: Debug + Display + StdError + 'static>(
+    src: impl AsRef<Path>,
+    dst: impl AsRef<Path>,
+    transform_dst: impl Fn(&Path) -> Result<PathBuf, E>,
+    template_ext: Option<&str>,
+    ignore_patterns: &[String],
+) -> Result<VecDeque<Action>, TraversalError<E>> {
+    let src = src.as_ref();
+    let ignore_file = src.join(IGNORE_FILE_NAME);
+    let ignore = build(src, &ignore_file, ignore_patterns)?;
+    traverse_filtered(src, dst, transform_dst, template_ext, |path| {
+        let ignored = path == ignore_file || ignore.matched(path, path.is_dir()).is_ignore();
+        if ignored {
+            log::info!("ignoring {:?}", path);
+        }
+        !ignored
+    })
+}
+
+fn build<E: Debug + Display + StdError + 'static>(
+    src: &Path,
+    ignore_file: &Path,
+    ignore_patterns: &[String],
+) -> Result<Gitignore, TraversalError<E>> {
+    let mut builder = GitignoreBuilder::new(src);
+    match fs::read_to_string(ignore_file) {
+        Ok(contents) => {
+            for line in contents.lines() {
+                builder
+                    .add_line(Some(ignore_file.to_owned()), line)
+                    .map_err(|cause| TraversalError::IgnoreParseFailed { cause })?;
+            }
+        }
+        Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+        Err(cause) => {
+            return Err(TraversalError::IgnoreFileReadFailed {
+                path: ignore_file.to_owned(),
+                cause,
+            })
+        }
+    }
+    for pattern in ignore_patterns {
+        builder
+            .add_line(None, pattern)
+            .map_err(|cause| TraversalError::IgnoreParseFailed { cause })?;
+    }
+    builder
+        .build()
+        .map_err(|cause| TraversalError::IgnoreParseFailed { cause })
+}
@@ -1,9 +1,14 @@
 use handlebars::to_json;
 use serde::Serialize;
 use serde_json::value::{Map, Value as Json};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
 
 /// Map of template variable names and values.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[repr(transparent)]
 pub struct JsonMap(pub(crate) Map<String, Json>);
 
@@ -13,8 +18,406 @@ impl Default for JsonMap {
     }
 }
 
+/// An error encountered when calling [`JsonMap::nest`] or [`JsonMap::insert_at`].
+#[derive(Debug, Error)]
+pub enum NestingError {
+    /// A key has a value of its own, but is also the prefix of another key.
+    #[error("Key {key:?} has a value, but is also used as a prefix of {other:?}")]
+    KeyConflict { key: String, other: String },
+}
+
+/// An error encountered when calling [`JsonMap::from_dir`].
+#[derive(Debug, Error)]
+pub enum FromDirError {
+    #[error("Failed to read directory at {path:?}: {cause}")]
+    DirectoryReadFailed {
+        path: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    #[error("Failed to read entry in directory {dir:?}: {cause}")]
+    EntryReadFailed {
+        dir: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    #[error("Failed to read data file at {path:?}: {cause}")]
+    FileReadFailed {
+        path: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    #[error("Failed to parse JSON at {path:?}: {cause}")]
+    JsonParseFailed {
+        path: PathBuf,
+        #[source]
+        cause: serde_json::Error,
+    },
+    #[cfg(feature = "serde_yaml")]
+    #[error("Failed to parse YAML at {path:?}: {cause}")]
+    YamlParseFailed {
+        path: PathBuf,
+        #[source]
+        cause: serde_yaml::Error,
+    },
+    /// A data file contained something other than an object at the top level.
+    #[error("Data file at {path:?} doesn't contain an object")]
+    NotAnObject { path: PathBuf },
+}
+
 impl JsonMap {
     pub fn insert(&mut self, name: &str, value: impl Serialize) {
         self.0.insert(name.to_owned(), to_json(value));
     }
+
+    /// Inserts `value` at a dotted path, so that `project.author.name` is
+    /// inserted as `name` within an `author` object within a `project` object.
+    /// Any missing objects along the way are created, and a value already at
+    /// the end of the path is replaced.
+    ///
+    /// It's an error for anything along the way to already have a value that
+    /// isn't an object, which is left untouched.
+    ///
+    /// # Examples
+    /// ```
+    /// use bicycle::JsonMap;
+    ///
+    /// let mut map = JsonMap::default();
+    /// map.insert_at("project.author.name", "Shinji").unwrap();
+    /// map.insert_at("project.author.email", "shinji@nerv.jp").unwrap();
+    /// let mut expected = JsonMap::default();
+    /// expected.insert("project", serde_json::json!({
+    ///     "author": { "name": "Shinji", "email": "shinji@nerv.jp" },
+    /// }));
+    /// assert_eq!(map, expected);
+    /// assert!(map.insert_at("project.author.name.first", "Shinji").is_err());
+    /// ```
+    pub fn insert_at(
+        &mut self,
+        dotted_path: &str,
+        value: impl Serialize,
+    ) -> Result<(), NestingError> {
+        let (parents, name) = match dotted_path.rsplit_once('.') {
+            Some((parents, name)) => (Some(parents), name),
+            None => (None, dotted_path),
+        };
+        let mut cursor = &mut self.0;
+        let mut prefix = String::new();
+        for segment in parents.into_iter().flat_map(|parents| parents.split('.')) {
+            if !prefix.is_empty() {
+                prefix.push('.');
+            }
+            prefix.push_str(segment);
+            cursor = match cursor
+                .entry(segment)
+                .or_insert_with(|| Json::Object(Map::new()))
+            {
+                Json::Object(map) => map,
+                _ => {
+                    return Err(NestingError::KeyConflict {
+                        key: prefix,
+                        other: dotted_path.to_owned(),
+                    })
+                }
+            };
+        }
+        cursor.insert(name.to_owned(), to_json(value));
+        Ok(())
+    }
+
+    /// Deep merges `other` into this map, which is handy for layering
+    /// overrides on top of defaults. Nested objects present in both are
+    /// merged recursively, while any other value from `other` (including
+    /// arrays) replaces what was there before.
+    ///
+    /// # Examples
+    /// ```
+    /// use bicycle::JsonMap;
+    ///
+    /// let mut data = JsonMap::default();
+    /// data.insert("app", serde_json::json!({ "name": "bicycle", "version": 1 }));
+    /// let mut overrides = JsonMap::default();
+    /// overrides.insert("app", serde_json::json!({ "version": 2 }));
+    /// data.merge(&overrides);
+    /// let mut expected = JsonMap::default();
+    /// expected.insert("app", serde_json::json!({ "name": "bicycle", "version": 2 }));
+    /// assert_eq!(data, expected);
+    /// ```
+    pub fn merge(&mut self, other: &JsonMap) {
+        deep_merge(&mut self.0, other.0.clone());
+    }
+
+    /// Serializes `value` into a map, which is handy for passing a strongly
+    /// typed config struct to templates. `value` must serialize to a JSON
+    /// object, so a struct or a map with string keys, and anything else is
+    /// an error.
+    ///
+    /// # Examples
+    /// ```
+    /// use bicycle::JsonMap;
+    ///
+    /// #[derive(serde::Serialize)]
+    /// struct Config {
+    ///     name: &'static str,
+    ///     unit: u32,
+    /// }
+    ///
+    /// let map = JsonMap::from_serialize(Config { name: "shinji", unit: 1 }).unwrap();
+    /// let mut expected = JsonMap::default();
+    /// expected.insert("name", "shinji");
+    /// expected.insert("unit", 1);
+    /// assert_eq!(map, expected);
+    /// assert!(JsonMap::from_serialize("shinji").is_err());
+    /// ```
+    pub fn from_serialize(value: impl Serialize) -> Result<Self, serde_json::Error> {
+        match serde_json::to_value(value)? {
+            Json::Object(map) => Ok(Self(map)),
+            other => Err(serde::ser::Error::custom(format!(
+                "expected a value that serializes to an object, but got {}",
+                other
+            ))),
+        }
+    }
+
+    /// Collects environment variables whose names start with `prefix`, with
+    /// the prefix stripped and the rest of the name lowercased. Double
+    /// underscores become dots, so that [`JsonMap::nest`] can turn
+    /// `MYAPP_DB__HOST` into `{"db": {"host": ...}}`. All values are strings.
+    ///
+    /// Variables with names or values that aren't valid Unicode are skipped.
+    pub fn from_env(prefix: &str) -> Self {
+        Self(
+            std::env::vars_os()
+                .filter_map(|(name, value)| {
+                    let key = name.to_str()?.strip_prefix(prefix)?;
+                    if key.is_empty() {
+                        return None;
+                    }
+                    let value = value.into_string().ok()?;
+                    Some((key.to_lowercase().replace("__", "."), Json::String(value)))
+                })
+                .collect(),
+        )
+    }
+
+    /// Reads and deep merges every JSON file in `dir`, which is handy for
+    /// configuration that's split up by concern. YAML files (ending in `.yaml`
+    /// or `.yml`) are read too if the `serde_yaml` feature is enabled, and are
+    /// skipped with a warning otherwise. Files with other extensions are
+    /// ignored.
+    ///
+    /// Entries are merged in order of their names, so later files override
+    /// earlier ones. Nested objects are merged recursively, while any other
+    /// value replaces what was there before. Subdirectories are read the same
+    /// way, with their data nested under the name of the subdirectory.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self, FromDirError> {
+        let dir = dir.as_ref();
+        let mut entries = fs::read_dir(dir)
+            .map_err(|cause| FromDirError::DirectoryReadFailed {
+                path: dir.to_owned(),
+                cause,
+            })?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|cause| FromDirError::EntryReadFailed {
+                dir: dir.to_owned(),
+                cause,
+            })?;
+        entries.sort();
+        let mut map = Map::new();
+        for path in entries {
+            if path.is_dir() {
+                let name = match path.file_name().and_then(|name| name.to_str()) {
+                    Some(name) => name.to_owned(),
+                    None => {
+                        log::warn!("skipping directory with non-Unicode name {:?}", path);
+                        continue;
+                    }
+                };
+                let nested = Self::from_dir(&path)?;
+                let nested = std::iter::once((name, Json::Object(nested.0))).collect();
+                deep_merge(&mut map, nested);
+            } else if let Some(data) = read_data_file(&path)? {
+                deep_merge(&mut map, data);
+            }
+        }
+        Ok(Self(map))
+    }
+
+    /// Expands dotted keys into nested objects, so that `user.name` and
+    /// `user.email` become a `user` object containing `name` and `email`.
+    /// This is the inverse of [`JsonMap::flatten`].
+    ///
+    /// Keys without dots are left as-is, and objects that are already nested
+    /// are merged with any dotted keys that share their prefix. It's an error
+    /// for a key to have a non-object value while also being a prefix of
+    /// another key.
+    ///
+    /// # Examples
+    /// ```
+    /// use bicycle::JsonMap;
+    ///
+    /// let mut map = JsonMap::default();
+    /// map.insert("user.name", "Shinji");
+    /// map.insert("user.email", "shinji@nerv.jp");
+    /// let map = map.nest().unwrap();
+    /// let mut expected = JsonMap::default();
+    /// expected.insert("user", serde_json::json!({
+    ///     "name": "Shinji",
+    ///     "email": "shinji@nerv.jp",
+    /// }));
+    /// assert_eq!(map, expected);
+    /// ```
+    pub fn nest(self) -> Result<Self, NestingError> {
+        let mut nested = Map::new();
+        for (key, value) in self.0 {
+            let mut path = key.split('.').peekable();
+            let mut cursor = &mut nested;
+            let mut prefix = String::new();
+            while let Some(segment) = path.next() {
+                if !prefix.is_empty() {
+                    prefix.push('.');
+                }
+                prefix.push_str(segment);
+                if path.peek().is_none() {
+                    match cursor.get_mut(segment) {
+                        Some(existing) => merge(existing, value, &prefix)?,
+                        None => {
+                            cursor.insert(segment.to_owned(), value);
+                        }
+                    }
+                    break;
+                }
+                cursor = match cursor
+                    .entry(segment)
+                    .or_insert_with(|| Json::Object(Map::new()))
+                {
+                    Json::Object(map) => map,
+                    _ => {
+                        return Err(NestingError::KeyConflict {
+                            key: prefix,
+                            other: key,
+                        })
+                    }
+                };
+            }
+        }
+        Ok(Self(nested))
+    }
+
+    /// Collapses nested objects into dotted keys, so that a `user` object
+    /// containing `name` and `email` becomes `user.name` and `user.email`.
+    /// This is the inverse of [`JsonMap::nest`].
+    ///
+    /// Arrays and empty objects are kept as values.
+    pub fn flatten(self) -> Self {
+        fn flatten_into(flat: &mut Map<String, Json>, prefix: String, value: Json) {
+            match value {
+                Json::Object(map) if !map.is_empty() => {
+                    for (key, value) in map {
+                        flatten_into(flat, format!("{}.{}", prefix, key), value);
+                    }
+                }
+                value => {
+                    flat.insert(prefix, value);
+                }
+            }
+        }
+        let mut flat = Map::new();
+        for (key, value) in self.0 {
+            flatten_into(&mut flat, key, value);
+        }
+        Self(flat)
+    }
+}
+
+/// Reads the object in a JSON or YAML file, or returns `None` if `path` isn't
+/// a data file.
+fn read_data_file(path: &Path) -> Result<Option<Map<String, Json>>, FromDirError> {
+    let ext = path.extension().and_then(|ext| ext.to_str());
+    let is_yaml = matches!(ext, Some("yaml" | "yml"));
+    if ext != Some("json") && !is_yaml {
+        return Ok(None);
+    }
+    if is_yaml && cfg!(not(feature = "serde_yaml")) {
+        log::warn!(
+            "skipping {:?}, since the `serde_yaml` feature isn't enabled",
+            path
+        );
+        return Ok(None);
+    }
+    let bytes = fs::read(path).map_err(|cause| FromDirError::FileReadFailed {
+        path: path.to_owned(),
+        cause,
+    })?;
+    let value = if is_yaml {
+        parse_yaml(path, &bytes)?
+    } else {
+        serde_json::from_slice(&bytes).map_err(|cause| FromDirError::JsonParseFailed {
+            path: path.to_owned(),
+            cause,
+        })?
+    };
+    match value {
+        Json::Object(map) => Ok(Some(map)),
+        _ => Err(FromDirError::NotAnObject {
+            path: path.to_owned(),
+        }),
+    }
+}
+
+#[cfg(feature = "serde_yaml")]
+fn parse_yaml(path: &Path, bytes: &[u8]) -> Result<Json, FromDirError> {
+    serde_yaml::from_slice(bytes).map_err(|cause| FromDirError::YamlParseFailed {
+        path: path.to_owned(),
+        cause,
+    })
+}
+
+#[cfg(not(feature = "serde_yaml"))]
+fn parse_yaml(_path: &Path, _bytes: &[u8]) -> Result<Json, FromDirError> {
+    unreachable!("YAML files are skipped without the `serde_yaml` feature")
+}
+
+/// Merges `other` into `existing`, recursing into objects present in both and
+/// otherwise letting values from `other` win.
+fn deep_merge(existing: &mut Map<String, Json>, other: Map<String, Json>) {
+    for (key, value) in other {
+        match (existing.get_mut(&key), value) {
+            (Some(Json::Object(existing)), Json::Object(value)) => deep_merge(existing, value),
+            (_, value) => {
+                existing.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Merges `value` into `existing`, which are both found at `key`.
+fn merge(existing: &mut Json, value: Json, key: &str) -> Result<(), NestingError> {
+    match (existing, value) {
+        (Json::Object(existing), Json::Object(map)) => {
+            for (sub_key, value) in map {
+                let full_key = format!("{}.{}", key, sub_key);
+                match existing.get_mut(&sub_key) {
+                    Some(existing) => merge(existing, value, &full_key)?,
+                    None => {
+                        existing.insert(sub_key, value);
+                    }
+                }
+            }
+            Ok(())
+        }
+        (Json::Object(existing), _) => Err(NestingError::KeyConflict {
+            key: key.to_owned(),
+            other: existing
+                .keys()
+                .next()
+                .map(|sub_key| format!("{}.{}", key, sub_key))
+                .unwrap_or_else(|| key.to_owned()),
+        }),
+        _ => Err(NestingError::KeyConflict {
+            key: key.to_owned(),
+            other: key.to_owned(),
+        }),
+    }
 }
@@ -1,33 +1,170 @@
 //! `bicycle` is [`handlebars`] with wheels. 🚴🏽‍♀️
 
 #![forbid(unsafe_code)]
+#![allow(clippy::result_large_err)]
 
+mod builder;
+mod compiled;
+mod context_stack;
+mod delimiters;
+mod duplicates;
+mod escape;
+mod file_system;
+#[cfg(feature = "serde_yaml")]
+mod front_matter;
+mod gitattributes;
+mod helpers;
+#[cfg(feature = "ignore")]
+mod ignore_file;
 mod json_map;
+mod merge;
+mod options;
+mod output_manifest;
+mod parallel;
+mod sanitize;
+#[cfg(feature = "script_helper")]
+mod script;
+mod sink;
+mod summary;
+mod transaction;
 mod traverse;
+mod variables;
 
-pub use self::{json_map::*, traverse::*};
+#[cfg(feature = "serde_yaml")]
+pub use self::front_matter::FrontMatter;
+#[cfg(feature = "ignore")]
+pub use self::ignore_file::{traverse_with_ignore, IGNORE_FILE_NAME};
+#[cfg(feature = "script_helper")]
+pub use self::script::SCRIPT_MAX_OPERATIONS;
+pub use self::{
+    builder::BicycleBuilder,
+    compiled::*,
+    context_stack::{ContextStack, PARENT_KEY},
+    duplicates::*,
+    file_system::*,
+    gitattributes::GitAttributesMode,
+    json_map::*,
+    merge::MANIFEST_FILE_NAME,
+    options::ProcessOptions,
+    output_manifest::OutputManifest,
+    sanitize::sanitize_path_segment,
+    sink::*,
+    summary::ActionSummary,
+    traverse::*,
+};
 pub use handlebars::{self, HelperDef};
 
-use handlebars::Handlebars;
+use self::{delimiters::Delimiters, merge::Manifest, transaction::Transaction};
+use handlebars::{Context, Handlebars, RenderContext, Renderable as _, StringOutput, Template};
+use serde::{Deserialize, Serialize};
 use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
     fmt::{self, Debug},
     fs,
-    io::{self, Read, Write},
+    io::{self, Write},
     iter,
-    path::{Path, PathBuf},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
 };
 use thiserror::Error;
 
 pub type CustomEscapeFn = &'static (dyn Fn(&str) -> String + 'static + Send + Sync);
 
+/// An escape function that owns whatever it captures, for use with
+/// [`EscapeFn::BoxedCustom`].
+pub type BoxedEscapeFn = Box<dyn Fn(&str) -> String + Send + Sync>;
+
+/// The custom escape function in use, if any, which is kept around so it can
+/// be registered again by [`Bicycle::set_catch_escape_fn_panics`].
+struct CustomEscape(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl Debug for CustomEscape {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.pad("CustomEscape(..)")
+    }
+}
+
+impl CustomEscape {
+    fn escape_fn(&self) -> impl Fn(&str) -> String + Send + Sync {
+        let escape_fn = Arc::clone(&self.0);
+        move |raw: &str| escape_fn(raw)
+    }
+}
+
+/// A hook for rewriting the contents of a template file before it's rendered,
+/// which receives the path of the template alongside its contents. See
+/// [`Bicycle::set_pre_render`].
+pub type PreRenderFn = Box<dyn Fn(&Path, String) -> String + Send + Sync>;
+
+/// Wraps a [`PreRenderFn`], which doesn't implement `Debug` on its own.
+struct PreRender(PreRenderFn);
+
+impl Debug for PreRender {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.pad("PreRender(..)")
+    }
+}
+
+/// A hook for rewriting the output of a template before it's written, which
+/// receives the destination path alongside the rendered contents. See
+/// [`Bicycle::set_post_render`].
+pub type PostRenderFn = Box<dyn Fn(&Path, String) -> Result<String, ProcessingError> + Send + Sync>;
+
+/// Wraps a [`PostRenderFn`], which doesn't implement `Debug` on its own.
+struct PostRender(PostRenderFn);
+
+impl Debug for PostRender {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.pad("PostRender(..)")
+    }
+}
+
+/// Wraps the [`CustomEscapeFn`] set by [`Bicycle::set_path_sanitizer`], which
+/// doesn't implement `Debug` on its own.
+struct PathSanitizer(CustomEscapeFn);
+
+impl Debug for PathSanitizer {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.pad("PathSanitizer(..)")
+    }
+}
+
 /// Specifies how to escape template variables prior to rendering.
+#[derive(Default)]
 pub enum EscapeFn {
     /// The default setting. Doesn't change the variables at all.
+    #[default]
     None,
     /// Escape anything that looks like HTML. This is recommended when rendering HTML templates with user-provided data.
     Html,
     /// Escape using a custom function.
     Custom(CustomEscapeFn),
+    /// Escape using a custom closure, which can capture configuration that's
+    /// only known at runtime.
+    ///
+    /// `handlebars` requires escape functions to live as long as the
+    /// [`Bicycle`], so the closure must own what it captures rather than
+    /// borrowing it. In exchange, nothing needs to be leaked to get a
+    /// `'static` reference like [`EscapeFn::Custom`] takes: the box is owned by
+    /// the [`Bicycle`] and dropped along with it.
+    ///
+    /// # Examples
+    /// ```
+    /// use bicycle::{Bicycle, EscapeFn, JsonMap};
+    ///
+    /// let replacement = '_';
+    /// let bike = Bicycle::new(
+    ///     EscapeFn::BoxedCustom(Box::new(move |raw| raw.replace(' ', &replacement.to_string()))),
+    ///     std::iter::empty(),
+    ///     JsonMap::default(),
+    /// );
+    /// let rendered = bike.render("{{name}}", |map| {
+    ///     map.insert("name", "Shinji Ikari");
+    /// }).unwrap();
+    /// assert_eq!(rendered, "Shinji_Ikari");
+    /// ```
+    BoxedCustom(BoxedEscapeFn),
 }
 
 impl Debug for EscapeFn {
@@ -36,27 +173,164 @@ impl Debug for EscapeFn {
             Self::None => "None",
             Self::Html => "Html",
             Self::Custom(_) => "Custom(..)",
+            Self::BoxedCustom(_) => "BoxedCustom(..)",
         })
     }
 }
 
-impl Default for EscapeFn {
-    fn default() -> Self {
-        Self::None
-    }
-}
-
 impl From<CustomEscapeFn> for EscapeFn {
     fn from(custom: CustomEscapeFn) -> Self {
         Self::Custom(custom)
     }
 }
 
+/// Specifies what to do with templates that exceed the limit set by
+/// [`Bicycle::set_max_template_bytes`].
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OversizedTemplatePolicy {
+    /// Copies the file verbatim instead of rendering it, and logs a warning.
+    Copy,
+    /// Fails with [`ProcessingError::TemplateTooLarge`].
+    Error,
+}
+
+/// Specifies what to do with templates that turn out to be binary files. See
+/// [`Bicycle::set_binary_template_policy`].
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BinaryTemplatePolicy {
+    /// Fails with [`ProcessingError::TemplateIsBinary`].
+    #[default]
+    Error,
+    /// Copies the file verbatim instead of rendering it, and logs a warning.
+    /// For [`Tag::AppendTemplate`], the copy replaces the destination.
+    Copy,
+}
+
+/// Specifies what to do when a file that would be copied or rendered already
+/// exists at its destination. See [`Bicycle::set_overwrite_policy`].
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverwritePolicy {
+    /// Overwrites the existing file.
+    #[default]
+    Always,
+    /// Leaves the existing file alone, skipping the action.
+    Never,
+    /// Only overwrites the existing file if its contents would change, which
+    /// also leaves the modification time of unchanged files alone.
+    IfChanged,
+}
+
+/// Specifies which line endings rendered templates are written with. See
+/// [`Bicycle::set_line_endings`].
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LineEndings {
+    /// Leaves line endings as the template produced them.
+    #[default]
+    Preserve,
+    /// Uses the platform's native line endings: CRLF on Windows, and LF
+    /// everywhere else.
+    Auto,
+    /// Uses LF (`\n`).
+    Lf,
+    /// Uses CRLF (`\r\n`).
+    #[serde(rename = "crlf")]
+    CrLf,
+}
+
+impl LineEndings {
+    fn normalize(self, text: String) -> String {
+        let crlf = match self {
+            Self::Preserve => return text,
+            Self::Auto => cfg!(windows),
+            Self::Lf => false,
+            Self::CrLf => true,
+        };
+        let lf = if text.contains("\r\n") {
+            text.replace("\r\n", "\n")
+        } else {
+            text
+        };
+        if crlf {
+            lf.replace('\n', "\r\n")
+        } else {
+            lf
+        }
+    }
+}
+
 /// An error encountered when rendering a template.
 #[derive(Debug, Error)]
 pub enum RenderingError {
     #[error("Failed to render template: {0}")]
-    RenderingFailed(#[from] handlebars::TemplateRenderError),
+    RenderingFailed(#[from] handlebars::RenderError),
+    /// A custom escape function panicked. Only produced when enabled via
+    /// [`Bicycle::set_catch_escape_fn_panics`].
+    #[error("Custom escape function panicked on input {input:?}: {message}")]
+    EscapeFnPanicked { input: String, message: String },
+    /// A partial couldn't be registered, either because it isn't a valid
+    /// template or because its file couldn't be read.
+    #[error("Failed to register partial {name:?}: {cause}")]
+    PartialRegistrationFailed {
+        name: String,
+        #[source]
+        cause: Box<handlebars::TemplateError>,
+    },
+    /// A script helper's file or directory couldn't be read. Requires the
+    /// `script_helper` feature.
+    #[cfg(feature = "script_helper")]
+    #[error("Failed to read script helpers from {path:?}: {cause}")]
+    ScriptHelperReadFailed {
+        path: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    /// A template passed to [`Bicycle::register_template`] isn't valid.
+    #[error("Failed to register template {name:?}: {cause}")]
+    TemplateRegistrationFailed {
+        name: String,
+        #[source]
+        cause: Box<handlebars::TemplateError>,
+    },
+}
+
+impl RenderingError {
+    /// Whether or not this error was caused by a missing value while rendering
+    /// in strict mode. This is useful for falling back to a lenient render.
+    pub fn is_strict_missing(&self) -> bool {
+        self.strict_missing().is_some()
+    }
+
+    /// If this error was caused by a missing value while rendering in strict
+    /// mode, returns the path of the missing variable, if handlebars knows it.
+    pub fn strict_missing_path(&self) -> Option<String> {
+        self.strict_missing().flatten()
+    }
+
+    fn strict_missing(&self) -> Option<Option<String>> {
+        // handlebars doesn't expose the kind of error, so we have to match on
+        // the descriptions used by `RenderError::strict_error`.
+        let desc = match self {
+            Self::RenderingFailed(err) => err.desc.as_str(),
+            Self::EscapeFnPanicked { .. }
+            | Self::PartialRegistrationFailed { .. }
+            | Self::TemplateRegistrationFailed { .. } => return None,
+            #[cfg(feature = "script_helper")]
+            Self::ScriptHelperReadFailed { .. } => return None,
+        };
+        if desc == "Value is missing in strict mode" {
+            return Some(None);
+        }
+        let quoted = desc
+            .strip_prefix("Variable ")?
+            .strip_suffix(" not found in strict mode.")?;
+        Some(Some(
+            serde_json::from_str(quoted).unwrap_or_else(|_| quoted.trim_matches('"').to_owned()),
+        ))
+    }
 }
 
 /// An error encountered when processing an [`Action`].
@@ -69,7 +343,9 @@ pub enum ProcessingError {
         #[source]
         cause: TraversalError<RenderingError>,
     },
-    /// Failed to create directory.
+    /// Failed to create directory. This is only reported if the directory
+    /// doesn't already exist, since an existing directory is usable even if
+    /// creating it would've failed.
     #[error("Failed to create directory at {dst:?}: {cause}")]
     DirectoryCreationFailed {
         dst: PathBuf,
@@ -91,13 +367,54 @@ pub enum ProcessingError {
         #[source]
         cause: io::Error,
     },
-    /// Failed to render template.
-    #[error("Failed to render template at {src:?}: {cause}")]
+    /// A template exceeded the limit set by [`Bicycle::set_max_template_bytes`].
+    #[error("Template at {path:?} is too large to render ({size} bytes)")]
+    TemplateTooLarge { path: PathBuf, size: u64 },
+    /// A template isn't text, so it can't be rendered. This usually means a
+    /// binary file was given the template extension by mistake.
+    #[error("Template at {path:?} is a binary file, so it can't be rendered")]
+    TemplateIsBinary { path: PathBuf },
+    /// Failed to render template. `dst` is where the output would've been
+    /// written, which is `None` for [`Bicycle::render_file`], since its output
+    /// isn't written anywhere.
+    #[error(
+        "Failed to render template from {src:?}{}: {cause}",
+        .dst.as_ref().map(|dst| format!(" to {:?}", dst)).unwrap_or_default()
+    )]
     TemplateRenderFailed {
         src: PathBuf,
+        dst: Option<PathBuf>,
         #[source]
         cause: RenderingError,
     },
+    /// Failed to read a template's sidecar data file.
+    #[error("Failed to read sidecar data at {path:?}: {cause}")]
+    SidecarReadFailed {
+        path: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    /// Failed to parse a template's sidecar data file as a JSON object.
+    #[error("Failed to parse sidecar data at {path:?}: {cause}")]
+    SidecarParseFailed {
+        path: PathBuf,
+        #[source]
+        cause: serde_json::Error,
+    },
+    /// Failed to read a directory's data file.
+    #[error("Failed to read directory data at {path:?}: {cause}")]
+    DirectoryDataReadFailed {
+        path: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    /// Failed to parse a directory's data file as a JSON object.
+    #[error("Failed to parse directory data at {path:?}: {cause}")]
+    DirectoryDataParseFailed {
+        path: PathBuf,
+        #[source]
+        cause: serde_json::Error,
+    },
     /// Failed to create or write output file.
     #[error("Failed to write template from {src:?} to {dst:?}: {cause}")]
     TemplateWriteFailed {
@@ -106,12 +423,222 @@ pub enum ProcessingError {
         #[source]
         cause: io::Error,
     },
+    /// A [`Tag::AppendTemplate`] was encountered by [`Bicycle::process_into`],
+    /// which can't append to entries that were already written to the sink.
+    #[error(
+        "Failed to append template from {src:?} to {dst:?}, since sinks don't support appending"
+    )]
+    AppendUnsupported { src: PathBuf, dst: PathBuf },
+    /// The subtree passed to [`Bicycle::process_subtree`] wasn't a relative
+    /// path within the source.
+    #[error("Subtree {subtree:?} isn't a relative path without `..` components")]
+    SubtreeNotRelative { subtree: PathBuf },
+    /// A directory couldn't be created because a file already exists at
+    /// `path`, which is either the directory's path or one of its parents.
+    /// See [`Bicycle::set_replace_conflicting_files`].
+    #[error("Failed to create directory at {dst:?}, since {path:?} is a file")]
+    DirectoryPathIsFile { dst: PathBuf, path: PathBuf },
+    /// Failed to mark generated files in `.gitattributes`.
+    #[error("Failed to write generated file markers to {path:?}: {cause}")]
+    GitAttributesWriteFailed {
+        path: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    /// Failed to delete file or directory.
+    #[error("Failed to delete {dst:?}: {cause}")]
+    DeletionFailed {
+        dst: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    /// Failed to read a symlink or create a copy of it.
+    #[error("Failed to recreate symlink {src:?} at {dst:?}: {cause}")]
+    SymlinkCreationFailed {
+        src: PathBuf,
+        dst: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    /// Failed to inspect or back up what's at a destination before changing
+    /// it. Only produced by [`Bicycle::process_actions_transactional`].
+    #[error("Failed to back up {path:?}: {cause}")]
+    BackupFailed {
+        path: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    /// [`Bicycle::process_clean`] was asked to clean a directory containing
+    /// `path`, which is either the current working directory or the source.
+    #[error("Refusing to clean {dst:?}, since it contains {path:?}")]
+    CleanRefused { dst: PathBuf, path: PathBuf },
+    /// Failed to read what's already at a destination, in order to compare
+    /// it against new output.
+    #[error("Failed to read existing output at {path:?}: {cause}")]
+    DestinationReadFailed {
+        path: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    /// Failed to read either the [manifest](MANIFEST_FILE_NAME) used by
+    /// [`Bicycle::process_merge`] or an [`OutputManifest`].
+    #[error("Failed to read manifest at {path:?}: {cause}")]
+    ManifestReadFailed {
+        path: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    /// Failed to parse either the [manifest](MANIFEST_FILE_NAME) used by
+    /// [`Bicycle::process_merge`] or an [`OutputManifest`].
+    #[error("Failed to parse manifest at {path:?}: {cause}")]
+    ManifestParseFailed {
+        path: PathBuf,
+        #[source]
+        cause: serde_json::Error,
+    },
+    /// Failed to write either the [manifest](MANIFEST_FILE_NAME) used by
+    /// [`Bicycle::process_merge`] or an [`OutputManifest`].
+    #[error("Failed to write manifest to {path:?}: {cause}")]
+    ManifestWriteFailed {
+        path: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    /// A template's front matter isn't valid. See
+    /// [`Bicycle::set_front_matter`].
+    #[cfg(feature = "serde_yaml")]
+    #[error("Failed to parse front matter in template at {path:?}: {cause}")]
+    FrontMatterParseFailed {
+        path: PathBuf,
+        #[source]
+        cause: serde_yaml::Error,
+    },
+    /// Front matter is enabled (see [`ProcessOptions::front_matter`]), but
+    /// the `serde_yaml` feature that's needed to parse it isn't.
+    #[error("Can't process template at {path:?}, since front matter is enabled without the `serde_yaml` feature")]
+    FrontMatterUnsupported { path: PathBuf },
+    /// A glob passed to [`Bicycle::process_matching`] isn't valid.
+    #[cfg(feature = "globset")]
+    #[error("Failed to parse glob {glob:?}: {cause}")]
+    GlobParseFailed {
+        glob: String,
+        #[source]
+        cause: globset::Error,
+    },
+    /// A [post-render hook](Bicycle::set_post_render) failed. Hooks can
+    /// report any other variant too, but this one fits errors of their own.
+    #[error("Failed to post-process output for {dst:?}: {cause}")]
+    PostRenderFailed {
+        dst: PathBuf,
+        #[source]
+        cause: Box<dyn std::error::Error + Send + Sync>,
+    },
+}
+
+/// A [`ProcessingError`] encountered partway through processing, alongside the
+/// actions that were completed before it happened.
+#[derive(Debug, Error)]
+#[error("{cause} (after completing {} actions)", completed.len())]
+pub struct PartialProcessingError {
+    pub completed: VecDeque<Action>,
+    #[source]
+    pub cause: ProcessingError,
+}
+
+/// An action that [`Bicycle::process_dry_run`] found would be executed.
+#[derive(Debug)]
+pub struct PlannedAction {
+    action: Action,
+    overwrites: bool,
+}
+
+impl PlannedAction {
+    /// The tag the action would actually be executed as, which accounts for
+    /// [`Bicycle::set_max_template_bytes`].
+    pub fn tag(&self) -> Tag {
+        self.action.tag()
+    }
+
+    pub fn src(&self) -> &Path {
+        self.action.src()
+    }
+
+    /// The destination after path templating and destination directives.
+    pub fn dst(&self) -> &Path {
+        self.action.dst()
+    }
+
+    /// Whether or not something already exists at the destination, which
+    /// would be overwritten (or for directories, reused).
+    pub fn overwrites(&self) -> bool {
+        self.overwrites
+    }
+
+    /// See [`Action::describe`].
+    pub fn describe(&self) -> String {
+        self.action.describe()
+    }
+}
+
+/// How a file in the destination would change if it were processed into, as
+/// reported by [`Bicycle::diff`]. Each variant holds the destination path.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FileDiff {
+    /// Nothing exists at the path yet.
+    Added(PathBuf),
+    /// Something exists at the path, and would be replaced or appended to.
+    Modified(PathBuf),
+    /// Something exists at the path, and would be left as it is.
+    Unchanged(PathBuf),
+    /// Something exists at the path, but has no corresponding source.
+    Removed(PathBuf),
+}
+
+impl FileDiff {
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Added(path)
+            | Self::Modified(path)
+            | Self::Unchanged(path)
+            | Self::Removed(path) => path,
+        }
+    }
+}
+
+/// Runs `render`, reporting any panic caught by [`escape::catching`] in the
+/// meantime.
+fn catch_escape_fn_panic<T>(
+    render: impl FnOnce() -> Result<T, handlebars::RenderError>,
+) -> Result<T, RenderingError> {
+    // Discard anything left over from a render that panicked some other way.
+    escape::take_panic();
+    let result = render();
+    match escape::take_panic() {
+        Some(escape::EscapeFnPanic { input, message }) => {
+            Err(RenderingError::EscapeFnPanicked { input, message })
+        }
+        None => result.map_err(Into::into),
+    }
 }
 
 #[derive(Debug)]
 pub struct Bicycle {
     handlebars: Handlebars<'static>,
     base_data: JsonMap,
+    options: ProcessOptions,
+    custom_escape: Option<CustomEscape>,
+    helper_names: BTreeSet<String>,
+    partial_names: BTreeSet<String>,
+    delimiters: Option<Delimiters>,
+    pre_render: Option<PreRender>,
+    post_render: Option<PostRender>,
+    path_sanitizer: Option<PathSanitizer>,
+}
+
+impl AsRef<Handlebars<'static>> for Bicycle {
+    fn as_ref(&self) -> &Handlebars<'static> {
+        &self.handlebars
+    }
 }
 
 impl Default for Bicycle {
@@ -171,174 +698,3620 @@ impl Bicycle {
     ) -> Self {
         let mut handlebars = Handlebars::new();
         handlebars.set_strict_mode(true);
-        match escape_fn {
-            EscapeFn::Custom(escape_fn) => handlebars.register_escape_fn(escape_fn),
-            EscapeFn::None => handlebars.register_escape_fn(handlebars::no_escape),
-            EscapeFn::Html => handlebars.register_escape_fn(handlebars::html_escape),
+        #[cfg(feature = "script_helper")]
+        handlebars.set_engine(script::sandboxed_engine());
+        let custom_escape = match escape_fn {
+            EscapeFn::Custom(escape_fn) => Some(CustomEscape(Arc::new(escape_fn))),
+            EscapeFn::BoxedCustom(escape_fn) => Some(CustomEscape(Arc::from(escape_fn))),
+            EscapeFn::None => {
+                handlebars.register_escape_fn(handlebars::no_escape);
+                None
+            }
+            EscapeFn::Html => {
+                handlebars.register_escape_fn(handlebars::html_escape);
+                None
+            }
+        };
+        if let Some(custom_escape) = &custom_escape {
+            handlebars.register_escape_fn(custom_escape.escape_fn());
         }
+        let mut helper_names = BTreeSet::new();
         for (name, helper) in helpers {
             handlebars.register_helper(name, helper);
+            helper_names.insert(name.to_owned());
         }
         Self {
             handlebars,
             base_data,
+            options: Default::default(),
+            custom_escape,
+            helper_names,
+            partial_names: BTreeSet::new(),
+            delimiters: None,
+            pre_render: None,
+            post_render: None,
+            path_sanitizer: None,
         }
     }
 
-    /// Renders a template.
+    /// Starts configuring a `Bicycle` using a [`BicycleBuilder`], which
+    /// can register helpers one at a time and disable strict mode.
+    pub fn builder() -> BicycleBuilder {
+        BicycleBuilder::default()
+    }
+
+    /// The same as [`Bicycle::new`], but additionally registers `partials`,
+    /// which can fail if any of them aren't valid templates.
     ///
-    /// Use `insert_data` to define any variables needed for the template.
+    /// Partials are pairs of names and template strings, and can be used from
+    /// other templates via `{{> name}}`.
     ///
     /// # Examples
     /// ```
-    /// use bicycle::Bicycle;
+    /// use bicycle::{Bicycle, EscapeFn, JsonMap};
+    /// use std::iter;
     ///
-    /// let bike = Bicycle::default();
-    /// let rendered = bike.render("Hello {{name}}!", |map| {
+    /// let bike = Bicycle::try_new(
+    ///     EscapeFn::None,
+    ///     iter::empty(),
+    ///     iter::once(("greeting", "Hello {{name}}!")),
+    ///     JsonMap::default(),
+    /// ).unwrap();
+    /// let rendered = bike.render("{{> greeting}}", |map| {
     ///     map.insert("name", "Shinji");
     /// }).unwrap();
     /// assert_eq!(rendered, "Hello Shinji!");
     /// ```
-    pub fn render(
+    pub fn try_new<'helper_name, 'partial_name, 'partial>(
+        escape_fn: EscapeFn,
+        helpers: impl iter::IntoIterator<
+            Item = (
+                &'helper_name str,
+                Box<dyn HelperDef + Send + Sync + 'static>,
+            ),
+        >,
+        partials: impl iter::IntoIterator<Item = (&'partial_name str, &'partial str)>,
+        base_data: JsonMap,
+    ) -> Result<Self, RenderingError> {
+        let mut bike = Self::new(escape_fn, helpers, base_data);
+        for (name, partial) in partials {
+            bike.register_partial(name, partial)?;
+        }
+        Ok(bike)
+    }
+
+    /// Registers a partial, which other templates can then use via
+    /// `{{> name}}`. This replaces any partial of the same name.
+    ///
+    /// Fails with [`RenderingError::PartialRegistrationFailed`] if `template`
+    /// isn't a valid template.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut bike = bicycle::Bicycle::default();
+    /// bike.register_partial("header", "// {{name}}").unwrap();
+    /// let rendered = bike.render("{{> header}} generated", |map| {
+    ///     map.insert("name", "main.rs");
+    /// }).unwrap();
+    /// assert_eq!(rendered, "// main.rs generated");
+    /// ```
+    pub fn register_partial(&mut self, name: &str, template: &str) -> Result<(), RenderingError> {
+        self.handlebars
+            .register_partial(name, template)
+            .map_err(|cause| RenderingError::PartialRegistrationFailed {
+                name: name.to_owned(),
+                cause: Box::new(cause),
+            })?;
+        self.partial_names.insert(name.to_owned());
+        Ok(())
+    }
+
+    /// The same as [`Bicycle::register_partial`], but reads the partial from
+    /// the file at `path`. Failing to read the file is reported the same way
+    /// as an invalid template.
+    pub fn register_partial_file(
+        &mut self,
+        name: &str,
+        path: impl AsRef<Path>,
+    ) -> Result<(), RenderingError> {
+        let template =
+            fs::read_to_string(path).map_err(|err| RenderingError::PartialRegistrationFailed {
+                name: name.to_owned(),
+                cause: Box::new((err, name.to_owned()).into()),
+            })?;
+        self.register_partial(name, &template)
+    }
+
+    /// Compiles `template` and stores it under `name`, replacing any template
+    /// of the same name, so that it can be rendered any number of times with
+    /// [`Bicycle::render_named`] without being parsed again. Unlike
+    /// [`Bicycle::compile`], this doesn't borrow the instance.
+    ///
+    /// Fails with [`RenderingError::TemplateRegistrationFailed`] if `template`
+    /// isn't a valid template. Note that handlebars stores partials as named
+    /// templates too, so the name shouldn't clash with a partial.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut bike = bicycle::Bicycle::default();
+    /// bike.register_template("greeting", "Hello {{name}}!").unwrap();
+    /// for name in ["Shinji", "Asuka", "Rei"] {
+    ///     let rendered = bike.render_named("greeting", |map| {
+    ///         map.insert("name", name);
+    ///     }).unwrap();
+    ///     assert_eq!(rendered, format!("Hello {}!", name));
+    /// }
+    /// ```
+    pub fn register_template(&mut self, name: &str, template: &str) -> Result<(), RenderingError> {
+        self.handlebars
+            .register_template_string(name, self.translate(template))
+            .map_err(|cause| RenderingError::TemplateRegistrationFailed {
+                name: name.to_owned(),
+                cause: Box::new(cause),
+            })
+    }
+
+    /// Renders the template registered as `name` by
+    /// [`Bicycle::register_template`]. Rendering a name that isn't registered
+    /// fails with a [`RenderingError::RenderingFailed`].
+    pub fn render_named(
         &self,
-        template: &str,
+        name: &str,
         insert_data: impl FnOnce(&mut JsonMap),
     ) -> Result<String, RenderingError> {
-        let mut data = self.base_data.clone();
-        insert_data(&mut data);
+        let data = self.data(insert_data);
+        catch_escape_fn_panic(|| self.handlebars.render(name, &data.0))
+    }
+
+    /// Registers a helper after construction, in addition to any passed to
+    /// [`Bicycle::new`]. This replaces any helper of the same name, including
+    /// the ones built into handlebars.
+    ///
+    /// # Examples
+    /// ```
+    /// use bicycle::{handlebars::handlebars_helper, Bicycle};
+    ///
+    /// handlebars_helper!(shout: |s: str| s.to_uppercase());
+    ///
+    /// let mut bike = Bicycle::default();
+    /// bike.register_helper("shout", Box::new(shout));
+    /// let rendered = bike.render("{{shout name}}", |map| {
+    ///     map.insert("name", "kaworu");
+    /// }).unwrap();
+    /// assert_eq!(rendered, "KAWORU");
+    /// ```
+    pub fn register_helper(
+        &mut self,
+        name: &str,
+        helper: Box<dyn HelperDef + Send + Sync + 'static>,
+    ) {
+        self.handlebars.register_helper(name, helper);
+        self.helper_names.insert(name.to_owned());
+    }
+
+    /// Registers a [rhai](https://docs.rs/rhai/) script as a helper, so that
+    /// helpers can be defined at runtime. Requires the `script_helper` feature.
+    ///
+    /// The helper's parameters and hash are available to the script as the
+    /// array `params` and the map `hash`, and the script's result is used as
+    /// the helper's output. Scripts are sandboxed: they can't import modules
+    /// or use `eval`, the sizes of the values they build are limited, and
+    /// they're stopped after [`SCRIPT_MAX_OPERATIONS`] operations.
+    ///
+    /// Fails if the script doesn't compile.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut bike = bicycle::Bicycle::default();
+    /// bike.register_script_helper("double", "params[0] * 2").unwrap();
+    /// let rendered = bike.render("{{double 21}}", |_| ()).unwrap();
+    /// assert_eq!(rendered, "42");
+    /// ```
+    #[cfg(feature = "script_helper")]
+    pub fn register_script_helper(
+        &mut self,
+        name: &str,
+        script: &str,
+    ) -> Result<(), RenderingError> {
         self.handlebars
-            .render_template(template, &data.0)
-            .map_err(Into::into)
+            .register_script_helper(name, script)
+            .map_err(|err| RenderingError::RenderingFailed(err.into()))?;
+        self.helper_names.insert(name.to_owned());
+        Ok(())
     }
 
-    /// Executes an [`Action`].
+    /// The same as [`Bicycle::register_script_helper`], but reads the script
+    /// from the file at `path`. Requires the `script_helper` feature.
     ///
-    /// - [`Action::CreateDirectory`] is executed with the same semantics as `mkdir -p`:
-    ///   any missing parent directories are also created, and creation succeeds even if
-    ///   the directory already exists. Failure results in a [`ProcessingError::DirectoryCreationFailed`].
-    /// - [`Action::CopyFile`] is executed with the same semantics as `cp`:
-    ///   if the destination file already exists, it will be overwritted with a copy of
-    ///   the source file. Failure results in a [`ProcessingError::FileCopyFailed`].
-    /// - [`Action::WriteTemplate`] is executed by reading the source file,
-    ///   rendering the contents as a template (using `insert_data` to pass
-    ///   any required values to the underlying [`Bicycle::render`] call),
-    ///   and then finally writing the result to the destination file. The destination
-    ///   file will be overwritten if it already exists. Failure for each step results
-    ///   in [`ProcessingError::TemplateReadFailed`], [`ProcessingError::TemplateRenderFailed`],
-    ///   and [`ProcessingError::TemplateWriteFailed`], respectively.
-    pub fn process_action(
-        &self,
-        action: &Action,
-        insert_data: impl Fn(&mut JsonMap),
-    ) -> Result<(), ProcessingError> {
-        log::info!("{:#?}", action);
-        match action.tag() {
-            Tag::CreateDirectory => {
-                fs::create_dir_all(action.dst()).map_err(|cause| {
-                    ProcessingError::DirectoryCreationFailed {
-                        dst: action.dst().to_owned(),
-                        cause,
-                    }
-                })?;
-            }
-            Tag::CopyFile => {
-                fs::copy(action.src(), action.dst()).map_err(|cause| {
-                    ProcessingError::FileCopyFailed {
-                        src: action.src().to_owned(),
-                        dst: action.dst().to_owned(),
-                        cause,
-                    }
-                })?;
+    /// Fails with [`RenderingError::ScriptHelperReadFailed`] if the file can't
+    /// be read, or if the script doesn't compile.
+    #[cfg(feature = "script_helper")]
+    pub fn register_script_helper_file(
+        &mut self,
+        name: &str,
+        path: impl AsRef<Path>,
+    ) -> Result<(), RenderingError> {
+        let path = path.as_ref();
+        let script =
+            fs::read_to_string(path).map_err(|cause| RenderingError::ScriptHelperReadFailed {
+                path: path.to_owned(),
+                cause,
+            })?;
+        self.register_script_helper(name, &script)
+    }
+
+    /// Registers each `.rhai` file in `dir` as a script helper named after the
+    /// file, so that `helpers/shout.rhai` becomes `{{shout}}`. This lets
+    /// template authors extend a generator without recompiling it. Requires
+    /// the `script_helper` feature.
+    ///
+    /// Subdirectories and other files are ignored. Returns the names of the
+    /// registered helpers in alphabetical order, and fails the same way
+    /// [`Bicycle::register_script_helper_file`] does, without registering
+    /// anything after the script that failed.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let mut bike = bicycle::Bicycle::default();
+    /// let helpers = bike.register_script_helpers_dir("helpers").unwrap();
+    /// println!("registered {}", helpers.join(", "));
+    /// ```
+    #[cfg(feature = "script_helper")]
+    pub fn register_script_helpers_dir(
+        &mut self,
+        dir: impl AsRef<Path>,
+    ) -> Result<Vec<String>, RenderingError> {
+        let dir = dir.as_ref();
+        let read_failed = |cause| RenderingError::ScriptHelperReadFailed {
+            path: dir.to_owned(),
+            cause,
+        };
+        let mut scripts = BTreeMap::new();
+        for entry in fs::read_dir(dir).map_err(read_failed)? {
+            let path = entry.map_err(read_failed)?.path();
+            if !path.is_file() || path.extension().is_none_or(|ext| ext != "rhai") {
+                continue;
             }
-            Tag::WriteTemplate => {
-                let mut template = String::new();
-                fs::File::open(action.src())
-                    .and_then(|mut file| file.read_to_string(&mut template))
-                    .map_err(|cause| ProcessingError::TemplateReadFailed {
-                        src: action.src().to_owned(),
-                        cause,
-                    })?;
-                let rendered = self.render(&template, insert_data).map_err(|cause| {
-                    ProcessingError::TemplateRenderFailed {
-                        src: action.src().to_owned(),
-                        cause,
-                    }
-                })?;
-                fs::File::create(action.dst())
-                    .and_then(|mut file| file.write_all(rendered.as_bytes()))
-                    .map_err(|cause| ProcessingError::TemplateWriteFailed {
-                        src: action.src().to_owned(),
-                        dst: action.dst().to_owned(),
-                        cause,
-                    })?;
+            match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => {
+                    scripts.insert(name.to_owned(), path);
+                }
+                None => log::warn!(
+                    "skipping script helper {:?}, since its name isn't valid UTF-8",
+                    path
+                ),
             }
         }
-        Ok(())
+        for (name, path) in &scripts {
+            self.register_script_helper_file(name, path)?;
+        }
+        Ok(scripts.into_keys().collect())
     }
 
-    /// Iterates over `actions`, passing each item to [`Bicycle::process_action`].
-    pub fn process_actions<'iter_item>(
+    /// Lists the names of all helpers available to templates, including the
+    /// ones built into handlebars, in alphabetical order.
+    pub fn registered_helpers(&self) -> Vec<String> {
+        variables::BUILTIN_HELPERS
+            .iter()
+            .map(|name| (*name).to_owned())
+            .chain(self.helper_names.iter().cloned())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Lists the names of all registered partials, in alphabetical order.
+    pub fn registered_partials(&self) -> Vec<String> {
+        self.partial_names.iter().cloned().collect()
+    }
+
+    /// Lists the names of all templates registered on the underlying
+    /// [`handlebars::Handlebars`] instance, in alphabetical order.
+    ///
+    /// handlebars stores partials as named templates, so this includes
+    /// everything listed by [`Bicycle::registered_partials`].
+    pub fn registered_templates(&self) -> Vec<String> {
+        self.handlebars
+            .get_templates()
+            .keys()
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// The options that processing currently uses, which reflect any setters
+    /// that have been called.
+    pub fn options(&self) -> &ProcessOptions {
+        &self.options
+    }
+
+    /// Replaces all of the processing options at once, which is the same as
+    /// calling the setter for each of them.
+    pub fn set_options(&mut self, options: ProcessOptions) {
+        let destination_metadata = options.destination_metadata;
+        self.options = options;
+        self.set_destination_metadata(destination_metadata);
+    }
+
+    /// Sets how [`Bicycle::process`] and [`Bicycle::filter_and_process`]
+    /// handle multiple files that would be written to the same destination.
+    ///
+    /// The default is [`CollisionStrategy::Overwrite`].
+    pub fn set_collision_strategy(&mut self, collision_strategy: CollisionStrategy) {
+        self.options.collision_strategy = collision_strategy;
+    }
+
+    /// Sets what [`Bicycle::process`] and friends do with templates that
+    /// only have the template extension, like `foo.hbs`.
+    ///
+    /// The default is [`SingleExtensionPolicy::Strip`].
+    pub fn set_single_extension_policy(&mut self, policy: SingleExtensionPolicy) {
+        self.options.single_extension = policy;
+    }
+
+    /// Sets whether or not a file that's in the way of creating a directory
+    /// is deleted and replaced with the directory, which can happen when
+    /// regenerating into a tree where a file has since become a directory.
+    ///
+    /// This is disabled by default, in which case processing fails with
+    /// [`ProcessingError::DirectoryPathIsFile`].
+    pub fn set_replace_conflicting_files(&mut self, enabled: bool) {
+        self.options.replace_conflicting_files = enabled;
+    }
+
+    /// Marks every file written by [`Bicycle::process`] and friends as
+    /// `linguist-generated=true` in a `.gitattributes` at the root of the
+    /// destination, so that forges like GitHub collapse them in diffs.
+    ///
+    /// This is disabled by default; pass `None` to disable it again.
+    pub fn set_mark_generated(&mut self, mode: Option<GitAttributesMode>) {
+        self.options.mark_generated = mode;
+    }
+
+    /// Records everything written by [`Bicycle::process`] and friends in an
+    /// [`OutputManifest`] named `name` (like `"manifest.json"`) at the root of
+    /// the destination, distinguishing what was created from what was
+    /// overwritten. This makes it possible to undo processing later on with
+    /// [`Bicycle::remove_from_manifest`]. The manifest is written even if
+    /// processing fails partway through, so that it covers whatever was done.
+    ///
+    /// This is disabled by default; pass `None` to disable it again.
+    pub fn set_output_manifest(&mut self, name: Option<&str>) {
+        self.options.output_manifest = name.map(ToOwned::to_owned);
+    }
+
+    /// Deletes the files and directories that the [`OutputManifest`] at
+    /// `path` says were created, along with the manifest itself, undoing
+    /// [`Bicycle::set_output_manifest`]. Files that were overwritten are left
+    /// alone, since they existed beforehand, and directories are only deleted
+    /// if they're empty once everything else is gone.
+    ///
+    /// Paths are relative to the directory containing the manifest. Anything
+    /// that's already been deleted is skipped; failing to delete anything
+    /// else results in a [`ProcessingError::DeletionFailed`].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let mut bike = bicycle::Bicycle::default();
+    /// bike.set_output_manifest(Some("manifest.json"));
+    /// bike.process("templates", "out", |_| ()).unwrap();
+    /// // Changed our minds!
+    /// bike.remove_from_manifest("out/manifest.json").unwrap();
+    /// ```
+    pub fn remove_from_manifest(&self, path: impl AsRef<Path>) -> Result<(), ProcessingError> {
+        output_manifest::remove(path.as_ref())
+    }
+
+    /// Sets whether or not templates can choose their own destination using a
+    /// directive on their first line, like `@@ dest: src/{{name}}/mod.rs`.
+    ///
+    /// The path is relative to the root of the destination, and is rendered
+    /// the same way as any other destination path. The directive line is
+    /// stripped from the output, and any missing parent directories of the
+    /// new destination are created. Templates without a directive are
+    /// unaffected. Since the directive is only found by reading the template,
+    /// destinations chosen this way aren't considered when resolving
+    /// collisions.
+    ///
+    /// This is disabled by default.
+    pub fn set_dest_directives(&mut self, enabled: bool) {
+        self.options.dest_directives = enabled;
+    }
+
+    /// Sets whether or not templates can start with a block of YAML front
+    /// matter between `---` lines, which is stripped before rendering and
+    /// controls how the template is processed (see [`FrontMatter`]). Requires
+    /// the `serde_yaml` feature.
+    ///
+    /// Front matter comes after the destination directive, if both are used.
+    /// Templates without front matter are unaffected, while front matter that
+    /// isn't valid fails with [`ProcessingError::FrontMatterParseFailed`].
+    /// Like destination directives, file names chosen this way aren't
+    /// considered when resolving collisions, and skipping only applies when
+    /// processing a whole tree, rather than a single action.
+    ///
+    /// This is disabled by default.
+    #[cfg(feature = "serde_yaml")]
+    pub fn set_front_matter(&mut self, enabled: bool) {
+        self.options.front_matter = enabled;
+    }
+
+    /// Sets a limit on the size of templates, which protects against reading a
+    /// huge file that was given the template extension by mistake into memory.
+    /// Templates larger than the limit (in bytes) are handled according to the
+    /// policy. The size is checked using the file's metadata before reading it,
+    /// including for destination directives and front matter, which oversized
+    /// templates don't get to use. This applies to [`Tag::AppendTemplate`]
+    /// too, in which case the copy replaces the destination.
+    ///
+    /// There's no limit by default; pass `None` to remove the limit again.
+    pub fn set_max_template_bytes(&mut self, limit: Option<(u64, OversizedTemplatePolicy)>) {
+        self.options.max_template_bytes = limit;
+    }
+
+    /// Sets what to do with templates that turn out to be binary files, like a
+    /// `logo.png.hbs` that was given the template extension by mistake. The
+    /// default, [`BinaryTemplatePolicy::Error`], fails with a
+    /// [`ProcessingError::TemplateIsBinary`].
+    ///
+    /// A template is considered binary if it contains a NUL byte or isn't
+    /// valid UTF-8 (or UTF-16 with a byte order mark, if the `encoding_rs`
+    /// feature is enabled). This is decided before destination directives or
+    /// front matter are read, so binary templates never use them.
+    pub fn set_binary_template_policy(&mut self, policy: BinaryTemplatePolicy) {
+        self.options.binary_templates = policy;
+    }
+
+    /// Sets a sentinel that templates can render to in order to not be
+    /// written at all, which lets a template's own logic decide whether or not
+    /// it gets generated. `Some("")` skips templates that render to nothing,
+    /// but keep in mind that a trailing newline still counts as output.
+    ///
+    /// Skipped templates are logged, and left out of what's returned by
+    /// [`Bicycle::process_returning`] and recorded by
+    /// [`Bicycle::process_actions_tracked`]. Nothing is skipped by default;
+    /// pass `None` to stop skipping again.
+    pub fn set_skip_sentinel(&mut self, sentinel: Option<&str>) {
+        self.options.skip_sentinel = sentinel.map(ToOwned::to_owned);
+    }
+
+    /// Sets whether or not templates that render to nothing but whitespace
+    /// are skipped, the same way as templates that render to the
+    /// [skip sentinel](Bicycle::set_skip_sentinel). This makes it easy to wrap
+    /// a whole file in a guard like `{{#if use_docker}}`, without worrying
+    /// about a trailing newline being left behind. This is disabled by default.
+    pub fn set_skip_empty(&mut self, enabled: bool) {
+        self.options.skip_empty = enabled;
+    }
+
+    /// Sets whether or not to traverse the destination when it's inside of the
+    /// source. By default, it's skipped (with a warning logged), since it'd
+    /// otherwise be processed as part of its own source: generating into
+    /// `src/build` would copy the previous output into `src/build/build`.
+    pub fn set_include_nested_dst(&mut self, enabled: bool) {
+        self.options.include_nested_dst = enabled;
+    }
+
+    /// Sets a hook that rewrites the contents of each template file before
+    /// it's rendered, like for stripping a license header or normalizing line
+    /// endings. This happens after any destination directive is removed, but
+    /// isn't applied to templates rendered directly with [`Bicycle::render`].
+    ///
+    /// # Examples
+    /// ```
+    /// let mut bike = bicycle::Bicycle::default();
+    /// bike.set_pre_render(Some(Box::new(|_path, template| {
+    ///     template.replace("\r\n", "\n")
+    /// })));
+    /// ```
+    ///
+    /// There's no hook by default; pass `None` to remove it again.
+    pub fn set_pre_render(&mut self, hook: Option<PreRenderFn>) {
+        self.pre_render = hook.map(PreRender);
+    }
+
+    /// Sets a hook that rewrites the output of each template before it's
+    /// written, like for running it through a formatter. The hook receives the
+    /// destination path, so it can decide what to do based on the extension.
+    ///
+    /// This applies to [`Tag::WriteTemplate`] actions only: appended output
+    /// is left alone, and so is anything rendered with [`Bicycle::render`].
+    /// It runs after the [skip sentinel](Bicycle::set_skip_sentinel) is
+    /// checked, and an error from it fails the action.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut bike = bicycle::Bicycle::default();
+    /// bike.set_post_render(Some(Box::new(|dst, rendered| {
+    ///     Ok(if dst.extension().is_some_and(|ext| ext == "md") {
+    ///         rendered.trim_end().to_owned() + "\n"
+    ///     } else {
+    ///         rendered
+    ///     })
+    /// })));
+    /// ```
+    ///
+    /// There's no hook by default; pass `None` to remove it again.
+    pub fn set_post_render(&mut self, hook: Option<PostRenderFn>) {
+        self.post_render = hook.map(PostRender);
+    }
+
+    /// Applies the post-render hook (if any) and line ending normalization to
+    /// the output of `action`.
+    fn post_rendered(
+        &self,
+        options: &ProcessOptions,
+        action: &Action,
+        rendered: String,
+    ) -> Result<String, ProcessingError> {
+        if !action.tag().write_template() {
+            return Ok(rendered);
+        }
+        let rendered = match &self.post_render {
+            Some(PostRender(hook)) => hook(action.dst(), rendered)?,
+            None => rendered,
+        };
+        Ok(options.line_endings.normalize(rendered))
+    }
+
+    /// Sets a sanitizer for the values interpolated into templated paths,
+    /// which keeps data containing characters like `/` from producing invalid
+    /// paths or unintended directories. [`sanitize_path_segment`] is a
+    /// reasonable default, but any function can be used.
+    ///
+    /// The sanitizer is used in place of the escape function when rendering
+    /// paths, so it applies to every `{{value}}`, but not to literal parts of
+    /// the path or to triple-stash expressions like `{{{value}}}`.
+    ///
+    /// # Examples
+    /// ```
+    /// use bicycle::Bicycle;
+    /// use std::path::Path;
+    ///
+    /// let mut bike = Bicycle::default();
+    /// bike.set_path_sanitizer(Some(&bicycle::sanitize_path_segment));
+    /// let path = bike.transform_dst(Path::new("src/{{name}}.rs"), |map| {
+    ///     map.insert("name", "../escape");
+    /// }).unwrap();
+    /// assert_eq!(path, Path::new("src/.._escape.rs"));
+    /// ```
+    ///
+    /// Paths aren't sanitized by default; pass `None` to stop sanitizing them
+    /// again.
+    pub fn set_path_sanitizer(&mut self, sanitizer: Option<CustomEscapeFn>) {
+        self.path_sanitizer = sanitizer.map(PathSanitizer);
+    }
+
+    /// Sets whether or not templated paths are rendered leniently, so that a
+    /// missing variable renders as an empty string instead of failing the
+    /// whole traversal. Template contents are still rendered strictly, unless
+    /// that's disabled via [`Bicycle::set_strict_mode`].
+    ///
+    /// This is disabled by default. A path component that renders as empty is
+    /// just dropped, so `{{missing}}/main.rs` is written to `main.rs` at the
+    /// root of the destination.
+    pub fn set_lenient_paths(&mut self, enabled: bool) {
+        self.options.lenient_paths = enabled;
+    }
+
+    /// Sets an extension that marks templates as appending to their
+    /// destination rather than overwriting it, which is useful for
+    /// accumulating into a shared file. With `"append"`, `lib.rs.append.hbs`
+    /// is rendered and appended to `lib.rs` as a [`Tag::AppendTemplate`], and
+    /// `lib.rs` is created if it doesn't exist yet.
+    ///
+    /// Any number of templates can append to the same destination without
+    /// colliding. Appended files aren't marked by
+    /// [`Bicycle::set_mark_generated`], since they're usually only partially
+    /// generated. Appending is disabled by default; pass `None` to disable it
+    /// again.
+    pub fn set_append_ext(&mut self, ext: Option<&str>) {
+        self.options.append_ext = ext.map(ToOwned::to_owned);
+    }
+
+    /// Sets the maximum number of threads used by
+    /// [`Bicycle::process_actions_par`]. The default, `None`, uses as many as
+    /// [`std::thread::available_parallelism`] suggests.
+    ///
+    /// There's always at least one thread, so `Some(0)` is treated like
+    /// `Some(1)`, with a warning logged when processing.
+    pub fn set_max_concurrency(&mut self, max: Option<usize>) {
+        self.options.max_concurrency = max;
+    }
+
+    /// Sets what to do when a file being copied or a template being rendered
+    /// already exists at its destination. The default,
+    /// [`OverwritePolicy::Always`], overwrites it.
+    ///
+    /// Skipped actions are treated the same as templates skipped via
+    /// [`Bicycle::set_skip_sentinel`]. This doesn't affect
+    /// [`Tag::AppendTemplate`], which never overwrites anything.
+    pub fn set_overwrite_policy(&mut self, policy: OverwritePolicy) {
+        self.options.overwrite_policy = policy;
+    }
+
+    /// Sets whether or not symlinks in the source are recreated as symlinks
+    /// (as [`Tag::Symlink`]), rather than being followed. By default, they're
+    /// followed: a symlinked directory is traversed, and a symlinked file is
+    /// copied (or rendered) as if it were its target.
+    ///
+    /// Targets of preserved symlinks are rendered like destination paths, so
+    /// relative targets stay relative. Absolute targets inside the directory
+    /// containing the symlink are moved along with it, while other absolute
+    /// targets are only rendered. An existing file or symlink at the destination is replaced,
+    /// subject to [`Bicycle::set_overwrite_policy`]. Output written to a
+    /// [`Sink`] always follows symlinks, since sinks can't hold them.
+    pub fn set_preserve_symlinks(&mut self, preserve: bool) {
+        self.options.preserve_symlinks = preserve;
+    }
+
+    /// Sets which line endings rendered templates are written with, which
+    /// keeps output consistent when templates were checked out with a mix of
+    /// line endings. The default, [`LineEndings::Preserve`], leaves them as
+    /// they are.
+    ///
+    /// This only applies to [`Tag::WriteTemplate`] actions, after any
+    /// [post-render hook](Bicycle::set_post_render). Copied files are never
+    /// touched, since they could be binary.
+    pub fn set_line_endings(&mut self, line_endings: LineEndings) {
+        self.options.line_endings = line_endings;
+    }
+
+    /// Sets whether or not to skip appending a template's output to a file
+    /// that already contains it, which makes repeated processing idempotent.
+    /// This is disabled by default.
+    pub fn set_append_dedupe(&mut self, enabled: bool) {
+        self.options.append_dedupe = enabled;
+    }
+
+    /// Whether or not `rendered` matches the skip sentinel, logging the skip
+    /// if it does.
+    fn skip_rendered(&self, options: &ProcessOptions, action: &Action, rendered: &str) -> bool {
+        if options.skip_sentinel.as_deref() == Some(rendered) {
+            log::info!(
+                "skipping {:?}, since it rendered to the skip sentinel",
+                action.src()
+            );
+            true
+        } else if options.skip_empty && rendered.trim().is_empty() {
+            log::info!("skipping {:?}, since it rendered to nothing", action.src());
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Enables sidecar data files, which provide data for a single template.
+    ///
+    /// When processing the template `foo.rs.hbs` with a `suffix` of `".json"`,
+    /// the JSON object in `foo.rs.hbs.json` (if that file exists) is inserted
+    /// into the data for that template only. Top-level keys in the sidecar
+    /// override any values of the same name from the base data or `insert_data`.
+    ///
+    /// Sidecar files aren't copied to the destination by [`Bicycle::process`],
+    /// [`Bicycle::filter_and_process`], or [`Bicycle::process_streaming`].
+    ///
+    /// Sidecars are disabled by default; pass `None` to disable them again.
+    pub fn set_sidecar_suffix(&mut self, suffix: Option<&str>) {
+        self.options.sidecar_suffix = suffix.map(ToOwned::to_owned);
+    }
+
+    /// Enables directory data files, which provide data for everything inside
+    /// of a directory, including subdirectories.
+    ///
+    /// When `name` is `"_data.json"`, each directory from the root of the
+    /// source down to a template's own directory can contain a `_data.json`
+    /// with a JSON object in it. These are layered using a [`ContextStack`],
+    /// so top-level keys from deeper directories override those from
+    /// shallower ones, and the data as seen from the parent directory is
+    /// available under the reserved [`PARENT_KEY`]. Directory data overrides
+    /// the base data and `insert_data`, but sidecar data overrides it in turn.
+    ///
+    /// This only applies to template contents, not to paths. Directory data
+    /// files aren't copied to the destination, and are only used by methods
+    /// that traverse the source themselves, like [`Bicycle::process`].
+    ///
+    /// Directory data is disabled by default; pass `None` to disable it again.
+    pub fn set_directory_data_name(&mut self, name: Option<&str>) {
+        self.options.directory_data_name = name.map(ToOwned::to_owned);
+    }
+
+    /// Sets file names that are always treated as templates when traversing,
+    /// regardless of their extension.
+    ///
+    /// This is useful for templates that need to keep their natural name, like
+    /// `Dockerfile` or `schema.sql`. Unlike files ending in the template
+    /// extension, these don't have their extension stripped.
+    pub fn set_template_names<'name>(&mut self, names: impl iter::IntoIterator<Item = &'name str>) {
+        self.options.template_names = names.into_iter().map(ToOwned::to_owned).collect();
+    }
+
+    /// Sets whether or not panics in an [`EscapeFn::Custom`] or
+    /// [`EscapeFn::BoxedCustom`] are caught and
+    /// reported as [`RenderingError::EscapeFnPanicked`], rather than unwinding
+    /// through the caller.
+    ///
+    /// This is disabled by default, and has no effect for other escape
+    /// functions. Note that this relies on [`std::panic::catch_unwind`], so it
+    /// does nothing when compiled with `panic = "abort"`, and the panic hook
+    /// still runs as usual.
+    pub fn set_catch_escape_fn_panics(&mut self, enabled: bool) {
+        if let Some(custom_escape) = &self.custom_escape {
+            if enabled {
+                self.handlebars
+                    .register_escape_fn(escape::catching(custom_escape.escape_fn()));
+            } else {
+                self.handlebars
+                    .register_escape_fn(custom_escape.escape_fn());
+            }
+        }
+    }
+
+    /// Adds data from environment variables prefixed with `prefix` to the data
+    /// used for every render, i.e. during processing. The variables are read
+    /// immediately using [`JsonMap::from_env`], and then nested using
+    /// [`JsonMap::nest`], so `MYAPP_DB__HOST` is available as `{{db.host}}`
+    /// with a prefix of `"MYAPP_"`.
+    ///
+    /// Top-level keys from the environment override any base data of the same
+    /// name, but values from `insert_data` still take precedence.
+    pub fn merge_env(&mut self, prefix: &str) -> Result<(), NestingError> {
+        let env = JsonMap::from_env(prefix).nest()?;
+        self.base_data.0.extend(env.0);
+        Ok(())
+    }
+
+    /// Sets whether or not templates get a `source` object describing the
+    /// template file itself when processing, containing:
+    /// - `size`: the size of the template file in bytes.
+    /// - `modified`: the time the template file was last modified, in seconds
+    ///   since the Unix epoch, or `null` if the platform doesn't provide it.
+    /// - `is_executable`: whether or not any of the template file's executable
+    ///   bits are set. This is always `false` on non-Unix platforms.
+    ///
+    /// `source` takes precedence over any other value of the same name. This
+    /// is disabled by default, since it costs an extra `stat` per template.
+    pub fn set_source_metadata(&mut self, enabled: bool) {
+        self.options.source_metadata = enabled;
+    }
+
+    /// Sets whether or not templates get a `destination` object describing
+    /// where they're being written to when processing, containing:
+    /// - `path`: the path of the output file.
+    /// - `exists`: whether or not the output file already existed before this
+    ///   render, which distinguishes generating fresh from regenerating.
+    ///
+    /// This also registers a `dest_exists` helper, which does the same check
+    /// as `exists` when called without parameters, and checks a path relative
+    /// to the output file's directory when given one:
+    /// `{{#unless (dest_exists "Cargo.lock")}}...{{/unless}}`.
+    ///
+    /// `destination` takes precedence over any other value of the same name,
+    /// and is only available when processing into a directory, so not with
+    /// [`Bicycle::process_into`]. This is disabled by default. Disabling it
+    /// again leaves `dest_exists` behind as a helper that always fails, since
+    /// helpers can't be unregistered, but it's no longer listed by
+    /// [`Bicycle::registered_helpers`].
+    pub fn set_destination_metadata(&mut self, enabled: bool) {
+        let was_enabled = std::mem::replace(&mut self.options.destination_metadata, enabled);
+        if enabled {
+            self.handlebars
+                .register_helper(helpers::DEST_EXISTS, Box::new(helpers::DestExists));
+            self.helper_names.insert(helpers::DEST_EXISTS.to_owned());
+        } else if was_enabled {
+            // Handlebars can't unregister helpers, so this replaces it with
+            // one that explains why it isn't available.
+            self.handlebars
+                .register_helper(helpers::DEST_EXISTS, Box::new(helpers::DestExistsDisabled));
+            self.helper_names.remove(helpers::DEST_EXISTS);
+        }
+    }
+
+    /// The underlying [`Handlebars`] instance, for inspecting configuration
+    /// that isn't exposed by `Bicycle` itself.
+    pub fn handlebars(&self) -> &Handlebars<'static> {
+        &self.handlebars
+    }
+
+    /// The underlying [`Handlebars`] instance, for configuration that isn't
+    /// exposed by `Bicycle` itself, like registering decorators or enabling
+    /// dev mode.
+    ///
+    /// This is an escape hatch: changes made here affect everything this
+    /// instance renders, and can conflict with settings made through
+    /// `Bicycle`. Helpers and partials registered this way also won't be
+    /// listed by [`Bicycle::registered_helpers`] or
+    /// [`Bicycle::registered_partials`], and replacing the escape function
+    /// bypasses [`Bicycle::set_catch_escape_fn_panics`].
+    ///
+    /// # Examples
+    /// ```
+    /// let mut bike = bicycle::Bicycle::default();
+    /// bike.handlebars_mut().set_dev_mode(true);
+    /// assert!(bike.handlebars().dev_mode());
+    /// ```
+    pub fn handlebars_mut(&mut self) -> &mut Handlebars<'static> {
+        &mut self.handlebars
+    }
+
+    /// Sets whether or not rendering fails when a template references a value
+    /// that's missing from the data. This is enabled by default; when it's
+    /// disabled, missing values render as empty strings instead.
+    ///
+    /// This applies to everything rendered by this instance, including
+    /// destination paths, though those can be made lenient on their own via
+    /// [`Bicycle::set_lenient_paths`].
+    pub fn set_strict_mode(&mut self, strict: bool) {
+        self.handlebars.set_strict_mode(strict);
+    }
+
+    /// Sets whether or not partials are left unindented when used on their own
+    /// line. By default, handlebars indents every line of such a partial to
+    /// match the indentation of the `{{> partial}}` tag, which isn't always
+    /// wanted in generated source files. When this is enabled, only the
+    /// indentation in front of the tag itself is kept.
+    ///
+    /// # Examples
+    /// ```
+    /// let mut bike = bicycle::Bicycle::default();
+    /// bike.register_partial("body", "a\nb\n").unwrap();
+    /// let template = "fn main() {\n    {{> body}}\n}\n";
+    /// let render = |bike: &bicycle::Bicycle| bike.render(template, |_| ()).unwrap();
+    /// assert_eq!(render(&bike), "fn main() {\n    a\n    b\n}\n");
+    /// bike.set_prevent_indent(true);
+    /// assert_eq!(render(&bike), "fn main() {\n    a\nb\n}\n");
+    /// ```
+    pub fn set_prevent_indent(&mut self, enabled: bool) {
+        self.handlebars.set_prevent_indent(enabled);
+    }
+
+    /// Sets an alternative pair of delimiters to use in place of `{{` and `}}`,
+    /// which is useful for templates that generate files containing
+    /// handlebars-like syntax of their own (i.e. GitHub Actions workflows).
+    ///
+    /// This applies to everything rendered by this instance, including
+    /// templates and destination paths during processing. Templates are
+    /// translated prior to rendering using these rules:
+    /// - `open` and `close` are replaced with `{{` and `}}`, respectively, so
+    ///   `<%name%>` and `<%#if x%>` work just like `{{name}}` and `{{#if x}}`.
+    /// - Any `{{` already in the template is preserved as-is in the output.
+    ///   `}}` never needs escaping outside of an expression.
+    /// - A delimiter preceded by a backslash (i.e. `\<%`) is output literally,
+    ///   without the backslash.
+    ///
+    /// Partials registered via [`Bicycle::try_new`] aren't translated, so they
+    /// need to use the standard delimiters.
+    ///
+    /// Custom delimiters are disabled by default; pass `None` to disable them
+    /// again.
+    ///
+    /// # Panics
+    /// Panics if either delimiter is empty.
+    ///
+    /// # Examples
+    /// ```
+    /// use bicycle::Bicycle;
+    ///
+    /// let mut bike = Bicycle::default();
+    /// bike.set_delimiters(Some(("<%", "%>")));
+    /// let rendered = bike.render("run: ${{ matrix.<%name%> }}", |map| {
+    ///     map.insert("name", "os");
+    /// }).unwrap();
+    /// assert_eq!(rendered, "run: ${{ matrix.os }}");
+    /// ```
+    pub fn set_delimiters(&mut self, delimiters: Option<(&str, &str)>) {
+        self.delimiters = delimiters.map(|(open, close)| Delimiters::new(open, close));
+    }
+
+    /// Translates custom delimiters, if any.
+    fn translate<'a>(&self, template: &'a str) -> Cow<'a, str> {
+        match &self.delimiters {
+            Some(delimiters) => Cow::Owned(delimiters.translate(template)),
+            None => Cow::Borrowed(template),
+        }
+    }
+
+    /// Renders a template.
+    ///
+    /// Use `insert_data` to define any variables needed for the template.
+    ///
+    /// # Examples
+    /// ```
+    /// use bicycle::Bicycle;
+    ///
+    /// let bike = Bicycle::default();
+    /// let rendered = bike.render("Hello {{name}}!", |map| {
+    ///     map.insert("name", "Shinji");
+    /// }).unwrap();
+    /// assert_eq!(rendered, "Hello Shinji!");
+    /// ```
+    pub fn render(
+        &self,
+        template: &str,
+        insert_data: impl FnOnce(&mut JsonMap),
+    ) -> Result<String, RenderingError> {
+        let template = self.translate(template);
+        let data = self.data(insert_data);
+        catch_escape_fn_panic(|| self.handlebars.render_template(&template, &data.0))
+    }
+
+    /// The same as [`Bicycle::render`], but writes the output to `writer` as
+    /// it's rendered rather than building up a string, which keeps memory
+    /// usage down when rendering something large. Failing to write is
+    /// reported as a [`RenderingError::RenderingFailed`].
+    ///
+    /// If rendering fails partway through, whatever was rendered up to that
+    /// point has already been written. Processing uses this to write templates
+    /// into new files when it can, as described by [`Bicycle::process_action`].
+    ///
+    /// # Examples
+    /// ```
+    /// use bicycle::Bicycle;
+    ///
+    /// let bike = Bicycle::default();
+    /// let mut rendered = Vec::new();
+    /// bike.render_to_writer("Hello {{name}}!", &mut rendered, |map| {
+    ///     map.insert("name", "Shinji");
+    /// }).unwrap();
+    /// assert_eq!(rendered, b"Hello Shinji!");
+    /// ```
+    pub fn render_to_writer(
+        &self,
+        template: &str,
+        writer: &mut impl Write,
+        insert_data: impl FnOnce(&mut JsonMap),
+    ) -> Result<(), RenderingError> {
+        let template = self.translate(template);
+        let data = self.data(insert_data);
+        catch_escape_fn_panic(|| {
+            self.handlebars
+                .render_template_to_write(&template, &data.0, writer)
+        })
+    }
+
+    /// Reads the template at `path` and renders it, the same way processing
+    /// renders a template file, but returns the output instead of writing it
+    /// anywhere. The [pre-render hook](Bicycle::set_pre_render) is applied,
+    /// while things that depend on the surrounding directory, like sidecar
+    /// data, aren't.
+    ///
+    /// Failing to read the file is reported as a
+    /// [`ProcessingError::TemplateReadFailed`], and failing to render it as a
+    /// [`ProcessingError::TemplateRenderFailed`].
+    pub fn render_file(
+        &self,
+        path: impl AsRef<Path>,
+        insert_data: impl FnOnce(&mut JsonMap),
+    ) -> Result<String, ProcessingError> {
+        let path = path.as_ref();
+        let template = read_template(path)?;
+        let template = match &self.pre_render {
+            Some(PreRender(hook)) => hook(path, template),
+            None => template,
+        };
+        self.render(&template, insert_data)
+            .map_err(|cause| ProcessingError::TemplateRenderFailed {
+                src: path.to_owned(),
+                dst: None,
+                cause,
+            })
+    }
+
+    /// Renders a template with additional helpers that are only available
+    /// for this one render.
+    ///
+    /// The helpers registered on this instance remain available, though
+    /// `extra_helpers` take precedence when names collide. This instance is
+    /// never modified, regardless of whether or not rendering succeeds.
+    ///
+    /// # Examples
+    /// ```
+    /// use bicycle::{handlebars::handlebars_helper, Bicycle, HelperDef};
+    ///
+    /// handlebars_helper!(shout: |s: str| s.to_uppercase());
+    ///
+    /// let bike = Bicycle::default();
+    /// let rendered = bike.render_with_helpers(
+    ///     "Hello {{shout name}}!",
+    ///     std::iter::once(("shout", Box::new(shout) as Box<dyn HelperDef + Send + Sync>)),
+    ///     |map| {
+    ///         map.insert("name", "Shinji");
+    ///     },
+    /// ).unwrap();
+    /// assert_eq!(rendered, "Hello SHINJI!");
+    /// ```
+    pub fn render_with_helpers<'helper_name, 'helper>(
+        &self,
+        template: &str,
+        extra_helpers: impl iter::IntoIterator<
+            Item = (
+                &'helper_name str,
+                Box<dyn HelperDef + Send + Sync + 'helper>,
+            ),
+        >,
+        insert_data: impl FnOnce(&mut JsonMap),
+    ) -> Result<String, RenderingError> {
+        // Registering on a clone (rather than registering and then restoring)
+        // means there's no state to clean up if rendering fails.
+        let mut handlebars: Handlebars<'helper> = self.handlebars.clone();
+        for (name, helper) in extra_helpers {
+            handlebars.register_helper(name, helper);
+        }
+        let template = self.translate(template);
+        let data = self.data(insert_data);
+        catch_escape_fn_panic(|| handlebars.render_template(&template, &data.0))
+    }
+
+    /// Lists the variables referenced by a template, in the order they first
+    /// appear.
+    ///
+    /// Only variables resolved against the top-level data are included, so
+    /// paths within `{{#each}}` and `{{#with}}` blocks are skipped unless
+    /// they explicitly refer to the top level (i.e. via `../` or `@root`).
+    /// Nested paths are reported in full, like `user.name`. Helpers registered
+    /// on this instance aren't mistaken for variables, and partials registered
+    /// on this instance are searched too.
+    ///
+    /// # Examples
+    /// ```
+    /// use bicycle::Bicycle;
+    ///
+    /// let bike = Bicycle::default();
+    /// let variables = bike.referenced_variables(
+    ///     "{{#if admin}}{{user.name}}{{/if}}{{#each items}}{{label}}{{/each}}",
+    /// ).unwrap();
+    /// assert_eq!(variables, vec!["admin", "user.name", "items"]);
+    /// ```
+    pub fn referenced_variables(&self, template: &str) -> Result<Vec<String>, RenderingError> {
+        let template = Template::compile(&self.translate(template))
+            .map_err(|err| RenderingError::RenderingFailed(err.into()))?;
+        let is_helper = |name: &str| {
+            variables::BUILTIN_HELPERS.contains(&name) || self.helper_names.contains(name)
+        };
+        let mut collector = variables::Collector::new(&is_helper, self.handlebars.get_templates());
+        collector.template(&template, 0);
+        Ok(collector.finish())
+    }
+
+    /// Parses a template ahead of time, producing a [`CompiledTemplate`] that
+    /// can be rendered repeatedly without being re-parsed.
+    ///
+    /// This is well-suited to rendering the same template in a hot loop.
+    ///
+    /// # Examples
+    /// ```
+    /// use bicycle::Bicycle;
+    ///
+    /// let bike = Bicycle::default();
+    /// let greeting = bike.compile("Hello {{name}}!").unwrap();
+    /// for name in &["Shinji", "Asuka"] {
+    ///     let rendered = greeting.render(|map| {
+    ///         map.insert("name", name);
+    ///     }).unwrap();
+    ///     assert_eq!(rendered, format!("Hello {}!", name));
+    /// }
+    /// ```
+    pub fn compile(&self, template: &str) -> Result<CompiledTemplate<'_>, RenderingError> {
+        Template::compile(&self.translate(template))
+            .map(|template| CompiledTemplate::new(self, template))
+            .map_err(|err| RenderingError::RenderingFailed(err.into()))
+    }
+
+    pub(crate) fn render_compiled(
+        &self,
+        template: &Template,
+        insert_data: impl FnOnce(&mut JsonMap),
+    ) -> Result<String, RenderingError> {
+        let data = self.data(insert_data);
+        catch_escape_fn_panic(|| {
+            let ctx = Context::wraps(&data.0)?;
+            let mut render_context = RenderContext::new(template.name.as_ref());
+            let mut output = StringOutput::new();
+            template.render(&self.handlebars, &ctx, &mut render_context, &mut output)?;
+            output.into_string().map_err(Into::into)
+        })
+    }
+
+    fn data(&self, insert_data: impl FnOnce(&mut JsonMap)) -> JsonMap {
+        let mut data = self.base_data.clone();
+        insert_data(&mut data);
+        data
+    }
+
+    /// Executes an [`Action`].
+    ///
+    /// - [`Tag::CreateDirectory`] is executed with the same semantics as `mkdir -p`:
+    ///   any missing parent directories are also created, and creation succeeds even if
+    ///   the directory already exists (including when it couldn't have been created,
+    ///   like the root of a read-only mount). Failure results in a [`ProcessingError::DirectoryCreationFailed`],
+    ///   or a [`ProcessingError::DirectoryPathIsFile`] if a file is in the way.
+    /// - [`Tag::CopyFile`] is executed with the same semantics as `cp`:
+    ///   if the destination file already exists, it will be overwritted with a copy of
+    ///   the source file. Failure results in a [`ProcessingError::FileCopyFailed`].
+    /// - [`Tag::WriteTemplate`] is executed by reading the source file,
+    ///   rendering the contents as a template (using `insert_data` to pass
+    ///   any required values to the underlying [`Bicycle::render`] call),
+    ///   and then finally writing the result to the destination file. The destination
+    ///   file will be overwritten if it already exists, and on Unix, it's given the
+    ///   same permissions as the source file. Failure for each step results
+    ///   in [`ProcessingError::TemplateReadFailed`], [`ProcessingError::TemplateRenderFailed`],
+    ///   and [`ProcessingError::TemplateWriteFailed`], respectively.
+    ///   Nothing is written if the result matches the sentinel set by
+    ///   [`Bicycle::set_skip_sentinel`]. When the destination doesn't exist yet
+    ///   and nothing needs the complete result first (like a skip sentinel or
+    ///   a [post-render hook](Bicycle::set_post_render)), the result is
+    ///   streamed into the new file as it's rendered, and the file is removed
+    ///   again if rendering fails.
+    /// - [`Tag::AppendTemplate`] is executed the same way as [`Tag::WriteTemplate`],
+    ///   except that the result is appended to the destination file instead of
+    ///   overwriting it (see [`Bicycle::set_append_dedupe`]).
+    /// - [`Tag::Delete`] is executed with the same semantics as `rm -r`, without
+    ///   following symlinks. Failure results in a [`ProcessingError::DeletionFailed`].
+    /// - [`Tag::Symlink`] is executed with the same semantics as `cp -P`: a symlink
+    ///   with the same target as the source (rendered as described by
+    ///   [`Bicycle::set_preserve_symlinks`]) is created, replacing any file or symlink
+    ///   already at the destination. Failure results in a
+    ///   [`ProcessingError::SymlinkCreationFailed`].
+    pub fn process_action(
+        &self,
+        action: &Action,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        self.execute(&self.options, action, insert_data, None)
+            .map(drop)
+    }
+
+    /// The same as [`Bicycle::process_action`], but also writes the contents
+    /// of each copied file or rendered template to `tee`, which is useful for
+    /// logging exactly what was written without reading it back.
+    pub fn process_action_with_tee(
+        &self,
+        action: &Action,
+        insert_data: impl Fn(&mut JsonMap),
+        tee: &mut dyn Write,
+    ) -> Result<(), ProcessingError> {
+        self.execute(&self.options, action, insert_data, Some(tee))
+            .map(drop)
+    }
+
+    /// Processes the single file at `src` into the file at `dst`, without
+    /// needing to traverse anything or build an [`Action`] by hand. `src` is
+    /// rendered if it'd be treated as a template when traversing (i.e. it ends
+    /// in the template extension, or is one of the
+    /// [template names](Bicycle::set_template_names)), and copied otherwise.
+    ///
+    /// `dst` is used exactly as given, so nothing is stripped from it, and its
+    /// parent directories are created as needed.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let bike = bicycle::Bicycle::default();
+    /// bike.process_file("templates/main.rs.hbs", "out/src/main.rs", |map| {
+    ///     map.insert("name", "shinji");
+    /// })
+    /// .unwrap();
+    /// ```
+    pub fn process_file(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        let options = &self.options;
+        let tag = if src.is_dir() {
+            Tag::CreateDirectory
+        } else if options.traverse_config().is_template(src) {
+            Tag::WriteTemplate
+        } else {
+            Tag::CopyFile
+        };
+        log::info!("detected tag {:?} for path {:?}", tag, src);
+        if let Some(parent) = dst.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            self.create_directory(options, parent)?;
+        }
+        let action = Action::from_parts(src, dst, tag);
+        self.execute(options, &action, insert_data, None).map(drop)
+    }
+
+    /// Produces what executing an [`Action`] would write, without writing
+    /// anything: the rendered template for [`Tag::WriteTemplate`] and
+    /// [`Tag::AppendTemplate`], or the source file's contents for
+    /// [`Tag::CopyFile`]. This is useful for snapshot testing generated output.
+    ///
+    /// `None` is returned for [`Tag::CreateDirectory`], [`Tag::Delete`], and
+    /// [`Tag::Symlink`],
+    /// as well as for templates that render to the sentinel set by
+    /// [`Bicycle::set_skip_sentinel`]. Reading a copied file fails with a
+    /// [`ProcessingError::FileCopyFailed`], and templates fail the same way
+    /// they do in [`Bicycle::process_action`].
+    pub fn render_action(
+        &self,
+        action: &Action,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<Option<Vec<u8>>, ProcessingError> {
+        let options = &self.options;
+        match self.effective_tag(options, action)? {
+            Tag::CreateDirectory | Tag::Delete | Tag::Symlink => Ok(None),
+            Tag::CopyFile => {
+                fs::read(action.src())
+                    .map(Some)
+                    .map_err(|cause| ProcessingError::FileCopyFailed {
+                        src: action.src().to_owned(),
+                        dst: action.dst().to_owned(),
+                        cause,
+                    })
+            }
+            Tag::WriteTemplate | Tag::AppendTemplate => Ok(self
+                .rendered_output(options, action, insert_data)?
+                .map(String::into_bytes)),
+        }
+    }
+
+    /// Renders a template action, returning `None` if its output should be
+    /// skipped.
+    fn rendered_output(
+        &self,
+        options: &ProcessOptions,
+        action: &Action,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<Option<String>, ProcessingError> {
+        let rendered = self.render_template_file(options, action, insert_data, true)?;
+        if self.skip_rendered(options, action, &rendered) {
+            Ok(None)
+        } else {
+            self.post_rendered(options, action, rendered).map(Some)
+        }
+    }
+
+    /// Whether or not `action` can be rendered straight into its destination,
+    /// which requires that nothing needs the complete output first. Existing
+    /// destinations aren't streamed into, since the overwrite policy needs the
+    /// output, and a render that fails partway shouldn't clobber them. Neither
+    /// is anything with destination metadata, which would see the new file.
+    fn streams(&self, options: &ProcessOptions, action: &Action) -> bool {
+        action.tag().write_template()
+            && !options.destination_metadata
+            && options.skip_sentinel.is_none()
+            && !options.skip_empty
+            && self.post_render.is_none()
+            && options.line_endings == LineEndings::Preserve
+            && fs::symlink_metadata(action.dst()).is_err()
+    }
+
+    /// Renders the template of `action` into a new file at its destination
+    /// (and `tee`, if any) as it's rendered. The file is removed again if
+    /// rendering fails.
+    fn stream_template(
+        &self,
+        options: &ProcessOptions,
+        action: &Action,
+        insert_data: impl Fn(&mut JsonMap),
+        tee: Option<&mut dyn Write>,
+    ) -> Result<(), ProcessingError> {
+        let write_failed = |cause| ProcessingError::TemplateWriteFailed {
+            src: action.src().to_owned(),
+            dst: action.dst().to_owned(),
+            cause,
+        };
+        let file = fs::File::create(action.dst())
+            .and_then(|file| {
+                copy_mode(action.src(), &file)?;
+                Ok(file)
+            })
+            .map_err(write_failed)?;
+        let mut writer = KeepError {
+            writer: Tee { file, tee },
+            error: None,
+        };
+        let result =
+            self.render_template_file_with(options, action, insert_data, true, |template, data| {
+                self.render_to_writer(template, &mut writer, data)
+            });
+        let KeepError { writer, error } = writer;
+        if result.is_err() {
+            // Don't leave half of the output behind.
+            drop(writer);
+            if let Err(err) = fs::remove_file(action.dst()) {
+                log::warn!("failed to remove {:?}: {}", action.dst(), err);
+            }
+        }
+        match (result, error) {
+            // Rendering only reports that writing failed, not why.
+            (Err(_), Some(cause)) => Err(write_failed(cause)),
+            (result, _) => result,
+        }
+    }
+
+    /// Does the work of [`Bicycle::process_action`], returning `false` if the
+    /// action was skipped.
+    fn execute(
+        &self,
+        options: &ProcessOptions,
+        action: &Action,
+        insert_data: impl Fn(&mut JsonMap),
+        tee: Option<&mut dyn Write>,
+    ) -> Result<bool, ProcessingError> {
+        log::info!("{:#?}", action);
+        match self.effective_tag(options, action)? {
+            Tag::CreateDirectory => self.create_directory(options, action.dst())?,
+            Tag::CopyFile => {
+                let copy_failed = |cause| ProcessingError::FileCopyFailed {
+                    src: action.src().to_owned(),
+                    dst: action.dst().to_owned(),
+                    cause,
+                };
+                if overwrite_skipped(options.overwrite_policy, action.dst(), || {
+                    fs::read(action.src())
+                })
+                .map_err(copy_failed)?
+                {
+                    return Ok(false);
+                }
+                match tee {
+                    Some(tee) => copy_with_tee(action.src(), action.dst(), tee),
+                    None => fs::copy(action.src(), action.dst()).map(drop),
+                }
+                .map_err(copy_failed)?;
+            }
+            Tag::WriteTemplate if self.streams(options, action) => {
+                self.create_directive_parent(options, action)?;
+                self.stream_template(options, action, &insert_data, tee)?;
+            }
+            tag @ (Tag::WriteTemplate | Tag::AppendTemplate) => {
+                let rendered = match self.rendered_output(options, action, &insert_data)? {
+                    Some(rendered) => rendered,
+                    None => return Ok(false),
+                };
+                self.create_directive_parent(options, action)?;
+                let write_failed = |cause| ProcessingError::TemplateWriteFailed {
+                    src: action.src().to_owned(),
+                    dst: action.dst().to_owned(),
+                    cause,
+                };
+                let file = if tag.append_template() {
+                    if options.append_dedupe
+                        && already_appended(action.dst(), &rendered).map_err(write_failed)?
+                    {
+                        log::info!(
+                            "skipping {:?}, since {:?} already contains its output",
+                            action.src(),
+                            action.dst()
+                        );
+                        return Ok(false);
+                    }
+                    fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(action.dst())
+                } else {
+                    if overwrite_skipped(options.overwrite_policy, action.dst(), || {
+                        Ok(rendered.as_bytes().to_vec())
+                    })
+                    .map_err(write_failed)?
+                    {
+                        return Ok(false);
+                    }
+                    // Keep the executable bit on scripts like `gradlew`.
+                    fs::File::create(action.dst()).and_then(|file| {
+                        copy_mode(action.src(), &file)?;
+                        Ok(file)
+                    })
+                };
+                file.and_then(|mut file| file.write_all(rendered.as_bytes()))
+                    .and_then(|()| match tee {
+                        Some(tee) => tee.write_all(rendered.as_bytes()),
+                        None => Ok(()),
+                    })
+                    .map_err(write_failed)?;
+            }
+            Tag::Symlink => {
+                let failed = |cause| ProcessingError::SymlinkCreationFailed {
+                    src: action.src().to_owned(),
+                    dst: action.dst().to_owned(),
+                    cause,
+                };
+                let target = self.symlink_target(options, action, &insert_data)?;
+                if symlink_skipped(options.overwrite_policy, &target, action.dst()) {
+                    return Ok(false);
+                }
+                // Unlike copying, creating a symlink doesn't replace what's
+                // already there. Directories are left alone, so that creation
+                // fails instead.
+                if fs::symlink_metadata(action.dst()).is_ok_and(|metadata| !metadata.is_dir()) {
+                    fs::remove_file(action.dst()).map_err(failed)?;
+                }
+                symlink(&target, action.src(), action.dst()).map_err(failed)?;
+            }
+            Tag::Delete => {
+                fs::symlink_metadata(action.dst())
+                    .and_then(|metadata| {
+                        if metadata.is_dir() {
+                            fs::remove_dir_all(action.dst())
+                        } else {
+                            fs::remove_file(action.dst())
+                        }
+                    })
+                    .map_err(|cause| ProcessingError::DeletionFailed {
+                        dst: action.dst().to_owned(),
+                        cause,
+                    })?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Destination directives can point anywhere, so parent directories can't
+    /// be assumed to exist when they're enabled.
+    fn create_directive_parent(
+        &self,
+        options: &ProcessOptions,
+        action: &Action,
+    ) -> Result<(), ProcessingError> {
+        match action.dst().parent() {
+            Some(parent) if options.dest_directives => self.create_directory(options, parent),
+            _ => Ok(()),
+        }
+    }
+
+    fn create_directory(
+        &self,
+        options: &ProcessOptions,
+        dst: &Path,
+    ) -> Result<(), ProcessingError> {
+        let cause = match fs::create_dir_all(dst) {
+            Ok(()) => return Ok(()),
+            Err(cause) => cause,
+        };
+        // A directory that already exists is fine, even if creating it failed
+        // for another reason first, like a read-only mount or a parent that
+        // can't be written to.
+        if fs::metadata(dst).is_ok_and(|metadata| metadata.is_dir()) {
+            log::info!(
+                "failed to create directory {:?}, but it already exists: {}",
+                dst,
+                cause
+            );
+            return Ok(());
+        }
+        // Only bother looking for a conflicting file once creation has failed.
+        let conflict = dst
+            .ancestors()
+            .find(|path| fs::metadata(path).is_ok_and(|metadata| !metadata.is_dir()));
+        match conflict {
+            Some(path) if options.replace_conflicting_files => {
+                log::info!("replacing file {:?} with a directory", path);
+                fs::remove_file(path)
+                    .and_then(|()| fs::create_dir_all(dst))
+                    .map_err(|cause| ProcessingError::DirectoryCreationFailed {
+                        dst: dst.to_owned(),
+                        cause,
+                    })
+            }
+            Some(path) => Err(ProcessingError::DirectoryPathIsFile {
+                dst: dst.to_owned(),
+                path: path.to_owned(),
+            }),
+            None => Err(ProcessingError::DirectoryCreationFailed {
+                dst: dst.to_owned(),
+                cause,
+            }),
+        }
+    }
+
+    /// Reads and renders the source of a [`Tag::WriteTemplate`] action,
+    /// merging in its sidecar data if there is any. Destination metadata is
+    /// only added if the destination is `on_disk`.
+    fn render_template_file(
+        &self,
+        options: &ProcessOptions,
+        action: &Action,
+        insert_data: impl Fn(&mut JsonMap),
+        on_disk: bool,
+    ) -> Result<String, ProcessingError> {
+        self.render_template_file_with(options, action, insert_data, on_disk, |template, data| {
+            self.render(template, data)
+        })
+    }
+
+    /// Does the work of [`Bicycle::render_template_file`], using `render` to
+    /// render the prepared template with the data it's given.
+    fn render_template_file_with<T>(
+        &self,
+        options: &ProcessOptions,
+        action: &Action,
+        insert_data: impl Fn(&mut JsonMap),
+        on_disk: bool,
+        render: impl FnOnce(&str, Box<dyn FnOnce(&mut JsonMap) + '_>) -> Result<T, RenderingError>,
+    ) -> Result<T, ProcessingError> {
+        #[cfg_attr(not(feature = "serde_yaml"), allow(unused_variables))]
+        let use_front_matter = options.use_front_matter(action.src())?;
+        let template = read_template(action.src())?;
+        let template = if options.dest_directives {
+            dest_directive(&template).1
+        } else {
+            &template
+        };
+        #[cfg(feature = "serde_yaml")]
+        let (template, front_matter) = if use_front_matter {
+            let (front_matter, body) = front_matter::split(action.src(), template)?;
+            (body, Some(front_matter.data))
+        } else {
+            (template, None)
+        };
+        let template = match &self.pre_render {
+            Some(PreRender(hook)) => Cow::Owned(hook(action.src(), template.to_owned())),
+            None => Cow::Borrowed(template),
+        };
+        let sidecar = options.sidecar_data(action.src())?;
+        let source = if options.source_metadata {
+            let metadata = fs::metadata(action.src()).map_err(|cause| {
+                ProcessingError::TemplateReadFailed {
+                    src: action.src().to_owned(),
+                    cause,
+                }
+            })?;
+            Some(source_metadata(&metadata))
+        } else {
+            None
+        };
+        let destination = (options.destination_metadata && on_disk).then(|| {
+            serde_json::json!({
+                "path": action.dst(),
+                "exists": action.dst().exists(),
+            })
+        });
+        render(
+            &template,
+            Box::new(|map: &mut JsonMap| {
+                insert_data(map);
+                if let Some(sidecar) = sidecar {
+                    map.0.extend(sidecar);
+                }
+                #[cfg(feature = "serde_yaml")]
+                if let Some(front_matter) = front_matter {
+                    map.0.extend(front_matter);
+                }
+                if let Some(source) = source {
+                    map.0.insert("source".to_owned(), source);
+                }
+                if let Some(destination) = destination {
+                    map.0
+                        .insert(helpers::DESTINATION_KEY.to_owned(), destination);
+                }
+            }),
+        )
+        .map_err(|cause| ProcessingError::TemplateRenderFailed {
+            src: action.src().to_owned(),
+            dst: Some(action.dst().to_owned()),
+            cause,
+        })
+    }
+
+    /// The tag to actually process `action` as, which differs from its own tag
+    /// for templates that exceed the size limit (if any).
+    fn effective_tag(
+        &self,
+        options: &ProcessOptions,
+        action: &Action,
+    ) -> Result<Tag, ProcessingError> {
+        if !action.tag().renders() {
+            return Ok(action.tag());
+        }
+        let read_failed = |cause| ProcessingError::TemplateReadFailed {
+            src: action.src().to_owned(),
+            cause,
+        };
+        if let Some((max, policy)) = options.max_template_bytes {
+            let size = fs::metadata(action.src()).map_err(read_failed)?.len();
+            if size > max {
+                return match policy {
+                    OversizedTemplatePolicy::Copy => {
+                        log::warn!(
+                            "template {:?} is {} bytes, which exceeds the limit of {} bytes; copying it verbatim instead",
+                            action.src(),
+                            size,
+                            max
+                        );
+                        Ok(Tag::CopyFile)
+                    }
+                    OversizedTemplatePolicy::Error => Err(ProcessingError::TemplateTooLarge {
+                        path: action.src().to_owned(),
+                        size,
+                    }),
+                };
+            }
+        }
+        // With the default policy, binary templates are caught once they're
+        // read, so there's no need to read them an extra time here.
+        if options.binary_templates == BinaryTemplatePolicy::Copy
+            && is_binary(&fs::read(action.src()).map_err(read_failed)?)
+        {
+            log::warn!(
+                "template {:?} is a binary file; copying it verbatim instead",
+                action.src()
+            );
+            return Ok(Tag::CopyFile);
+        }
+        Ok(action.tag())
+    }
+
+    /// Redirects a template action to the destination named by the template's
+    /// destination directive or front matter, if enabled and present. Returns
+    /// `None` if front matter says to skip the template.
+    fn apply_directives(
+        &self,
+        options: &ProcessOptions,
+        mut action: Action,
+        root: &Path,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<Option<Action>, ProcessingError> {
+        if !action.tag().renders() {
+            return Ok(Some(action));
+        }
+        let use_front_matter = options.use_front_matter(action.src())?;
+        if !(options.dest_directives || use_front_matter) {
+            return Ok(Some(action));
+        }
+        // Templates that are going to be copied instead, like oversized ones,
+        // shouldn't be read here.
+        if !self.effective_tag(options, &action)?.renders() {
+            return Ok(Some(action));
+        }
+        let src = action.src().to_owned();
+        let planned_dst = action.dst().to_owned();
+        let render_failed = |cause| ProcessingError::TemplateRenderFailed {
+            src: src.clone(),
+            dst: Some(planned_dst.clone()),
+            cause,
+        };
+        let template = read_template(&src)?;
+        let template = if options.dest_directives {
+            let (dst, rest) = dest_directive(&template);
+            if let Some(dst) = dst {
+                let dst = self
+                    .transform_dst_with(options, &root.join(dst), &insert_data)
+                    .map_err(render_failed)?;
+                log::info!("template {:?} redirected itself to {:?}", action.src(), dst);
+                action.set_dst(dst);
+            }
+            rest
+        } else {
+            &template
+        };
+        #[cfg(feature = "serde_yaml")]
+        if use_front_matter {
+            let (front_matter, _) = front_matter::split(action.src(), template)?;
+            if front_matter.skip {
+                log::info!(
+                    "skipping {:?}, since its front matter says to",
+                    action.src()
+                );
+                return Ok(None);
+            }
+            if let Some(filename) = front_matter.filename {
+                let filename = self
+                    .transform_dst_with(options, Path::new(&filename), &insert_data)
+                    .map_err(render_failed)?;
+                let dst = action.dst().with_file_name(filename);
+                log::info!("template {:?} renamed itself to {:?}", action.src(), dst);
+                action.set_dst(dst);
+            }
+        }
+        #[cfg(not(feature = "serde_yaml"))]
+        let _ = template;
+        Ok(Some(action))
+    }
+
+    /// The same as [`Bicycle::process_actions_tracked`], but with the directory
+    /// data from within `src` (if enabled).
+    fn process_tree<'iter_item>(
+        &self,
+        options: &ProcessOptions,
+        src: &Path,
+        actions: impl iter::Iterator<Item = &'iter_item Action>,
+        insert_data: impl Fn(&mut JsonMap),
+        completed: &mut Vec<&'iter_item Action>,
+        mut on_finished: impl FnMut(),
+    ) -> Result<(), ProcessingError> {
+        let mut context = options.directory_context(src);
+        for action in actions {
+            let data = context.data_for(action)?;
+            if self.execute(options, action, with_data(&insert_data, data), None)? {
+                completed.push(action);
+            }
+            on_finished();
+        }
+        Ok(())
+    }
+
+    /// Iterates over `actions`, passing each item to [`Bicycle::process_action`].
+    pub fn process_actions<'iter_item>(
+        &self,
+        actions: impl iter::Iterator<Item = &'iter_item Action>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        self.process_actions_tracked(actions, insert_data, &mut Vec::new())
+    }
+
+    /// The same as [`Bicycle::process_actions`], but pushes each action onto
+    /// `completed` once it's been processed successfully (unless it was
+    /// skipped, per [`Bicycle::set_skip_sentinel`]). If processing fails
+    /// partway through, `completed` holds everything that was done before the
+    /// failure, which is useful for reporting progress or cleaning up.
+    pub fn process_actions_tracked<'iter_item>(
+        &self,
+        actions: impl iter::Iterator<Item = &'iter_item Action>,
+        insert_data: impl Fn(&mut JsonMap),
+        completed: &mut Vec<&'iter_item Action>,
+    ) -> Result<(), ProcessingError> {
+        for action in actions {
+            if self.execute(&self.options, action, &insert_data, None)? {
+                completed.push(action);
+            }
+        }
+        Ok(())
+    }
+
+    /// The same as [`Bicycle::process_actions`], but calls `before` with each
+    /// action before it's processed, and `after` with the action and its
+    /// result once it's been processed. This is handy for driving a progress
+    /// bar or structured logging.
+    ///
+    /// `after` is called even if processing fails, in which case it's given
+    /// the error that's then returned, and nothing more is processed. Skipped
+    /// actions (per [`Bicycle::set_skip_sentinel`]) count as successful.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use bicycle::Bicycle;
+    ///
+    /// let bike = Bicycle::default();
+    /// let actions = bicycle::traverse(
+    ///     "templates",
+    ///     "out",
+    ///     |path| bike.transform_dst(path, |_| ()),
+    ///     bicycle::DEFAULT_TEMPLATE_EXT,
+    /// )
+    /// .unwrap();
+    /// let mut done = 0;
+    /// bike.process_actions_with_hooks(
+    ///     actions.iter(),
+    ///     |_| (),
+    ///     |action| println!("{}...", action.describe()),
+    ///     |_, result| {
+    ///         if result.is_ok() {
+    ///             done += 1;
+    ///             println!("{}/{}", done, actions.len());
+    ///         }
+    ///     },
+    /// )
+    /// .unwrap();
+    /// ```
+    pub fn process_actions_with_hooks<'iter_item>(
+        &self,
+        actions: impl iter::Iterator<Item = &'iter_item Action>,
+        insert_data: impl Fn(&mut JsonMap),
+        mut before: impl FnMut(&Action),
+        mut after: impl FnMut(&Action, &Result<(), ProcessingError>),
+    ) -> Result<(), ProcessingError> {
+        for action in actions {
+            before(action);
+            let result = self
+                .execute(&self.options, action, &insert_data, None)
+                .map(drop);
+            after(action, &result);
+            result?;
+        }
+        Ok(())
+    }
+
+    /// The same as [`Bicycle::process_actions`], but if anything fails, undoes
+    /// everything that was done before returning the error, so that the
+    /// destination is left the way it was found.
+    ///
+    /// Before each action is executed, the directories and files it would
+    /// create are recorded, and any file it would overwrite (or replace with a
+    /// directory, per [`Bicycle::set_replace_conflicting_files`]) is backed up
+    /// in memory. [`Tag::Delete`] actions move their target aside instead,
+    /// which is only really deleted once everything has succeeded. On failure,
+    /// created files and directories are removed and backups are restored, in
+    /// reverse order. Undoing is best-effort: anything that can't be undone is
+    /// logged, and the original error is still returned.
+    ///
+    /// Nothing is recorded for changes made outside of `actions`, such as by
+    /// helpers.
+    pub fn process_actions_transactional<'iter_item>(
+        &self,
+        actions: impl iter::Iterator<Item = &'iter_item Action>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        let options = &self.options;
+        let mut transaction = Transaction::default();
+        for action in actions {
+            let result = match action.tag() {
+                Tag::Delete => transaction.delete(action.dst()).map_err(|cause| {
+                    ProcessingError::DeletionFailed {
+                        dst: action.dst().to_owned(),
+                        cause,
+                    }
+                }),
+                tag => if tag.create_directory() {
+                    transaction.record_directory(action.dst(), options.replace_conflicting_files)
+                } else {
+                    transaction.record_file(action.dst())
+                }
+                .map_err(|cause| ProcessingError::BackupFailed {
+                    path: action.dst().to_owned(),
+                    cause,
+                })
+                .and_then(|()| self.execute(options, action, &insert_data, None).map(drop)),
+            };
+            if let Err(cause) = result {
+                log::info!("rolling back after failure: {}", cause);
+                transaction.roll_back();
+                return Err(cause);
+            }
+        }
+        transaction.commit();
+        Ok(())
+    }
+
+    /// The same as [`Bicycle::process_actions`], but executes the actions
+    /// concurrently on up to [`Bicycle::set_max_concurrency`] threads, with
+    /// these ordering guarantees:
+    /// - Every [`Tag::CreateDirectory`] finishes before anything else starts,
+    ///   so directories always exist before anything is written into them.
+    ///   Directories are created concurrently with each other, which is safe
+    ///   since creating a directory creates any missing parents too.
+    /// - Files are then copied and templates are rendered concurrently, except
+    ///   that templates appending to the same destination are run one at a
+    ///   time, in the order they're given.
+    /// - Any [`Tag::Delete`] actions are run last, one at a time.
+    ///
+    /// `progress` is called on the calling thread with each action once it's
+    /// been processed (or skipped, per [`Bicycle::set_skip_sentinel`]), in the
+    /// same order as `actions` regardless of the order they finish in. If
+    /// anything fails, nothing more is started, and once whatever's already
+    /// running finishes, the error from the earliest failed action is returned.
+    /// Progress is never reported past that action.
+    pub fn process_actions_par<'a>(
+        &self,
+        actions: &'a [Action],
+        insert_data: impl Fn(&mut JsonMap) + Sync,
+        mut progress: impl FnMut(&'a Action),
+    ) -> Result<(), ProcessingError> {
+        let options = &self.options;
+        let threads = match options.max_concurrency {
+            Some(0) => {
+                log::warn!("the maximum concurrency is 0, so 1 thread is used instead");
+                1
+            }
+            Some(max) => max,
+            None => parallel::default_concurrency(),
+        };
+        let mut done = vec![false; actions.len()];
+        let mut reported = 0;
+        for groups in parallel_phases(actions) {
+            let mut failure: Option<(usize, ProcessingError)> = None;
+            parallel::run(
+                &groups,
+                threads,
+                |index| self.execute(options, &actions[index], &insert_data, None),
+                |index, result| {
+                    match result {
+                        Ok(_) => done[index] = true,
+                        Err(cause) => {
+                            if failure.as_ref().is_none_or(|(failed, _)| index < *failed) {
+                                failure = Some((index, cause));
+                            }
+                        }
+                    }
+                    while done.get(reported).copied().unwrap_or(false) {
+                        progress(&actions[reported]);
+                        reported += 1;
+                    }
+                },
+            );
+            if let Some((_, cause)) = failure {
+                return Err(cause);
+            }
+        }
+        Ok(())
+    }
+
+    /// The same as [`Bicycle::process_actions_par`], for when progress doesn't
+    /// need to be reported. The same ordering guarantees apply:
+    /// - Every [`Tag::CreateDirectory`] finishes before any files are copied
+    ///   or templates are rendered.
+    /// - Templates appending to the same destination are run one at a time,
+    ///   in the order they're given.
+    /// - Any [`Tag::Delete`] actions are run last, one at a time.
+    ///
+    /// If anything fails, nothing more is started, and the error from the
+    /// earliest failed action (in the order of `actions`) is returned.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let bike = bicycle::Bicycle::default();
+    /// let actions = Vec::from(
+    ///     bicycle::traverse(
+    ///         "templates",
+    ///         "out",
+    ///         |path| bike.transform_dst(path, |_| ()),
+    ///         bicycle::DEFAULT_TEMPLATE_EXT,
+    ///     )
+    ///     .unwrap(),
+    /// );
+    /// bike.process_actions_parallel(&actions, |map| {
+    ///     map.insert("name", "bicycle");
+    /// })
+    /// .unwrap();
+    /// ```
+    pub fn process_actions_parallel(
+        &self,
+        actions: &[Action],
+        insert_data: impl Fn(&mut JsonMap) + Sync,
+    ) -> Result<(), ProcessingError> {
+        self.process_actions_par(actions, insert_data, |_| ())
+    }
+
+    /// A convenience method that calls [`traverse`](traverse()) and passes the
+    /// output to [`Bicycle::process_actions`]. Uses [`Bicycle::transform_dst`]
+    /// as the `transform_dst` argument and `DEFAULT_TEMPLATE_EXT` ("hbs") as
+    /// the `template_ext` argument to [`traverse`](traverse()).
+    pub fn process(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        self.process_with_options(src, dst, &self.options, insert_data)
+    }
+
+    /// The same as [`Bicycle::process`], but uses `options` in place of the
+    /// options set on this instance, which are left untouched. This is handy
+    /// for processing several trees differently using the same helpers and
+    /// partials.
+    ///
+    /// The `dest_exists` helper is only available if destination metadata is
+    /// enabled on this instance, since helpers can't be registered for a
+    /// single call.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use bicycle::{Bicycle, CollisionStrategy, ProcessOptions};
+    ///
+    /// let bike = Bicycle::default();
+    /// let options = ProcessOptions {
+    ///     collision_strategy: CollisionStrategy::Error,
+    ///     dest_directives: true,
+    ///     ..Default::default()
+    /// };
+    /// bike.process_with_options("templates", "out", &options, |map| {
+    ///     map.insert("name", "bicycle");
+    /// }).unwrap();
+    /// ```
+    pub fn process_with_options(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        options: &ProcessOptions,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        self.process_filtered(
+            options,
+            src.as_ref(),
+            dst.as_ref(),
+            insert_data,
+            |_| true,
+            |_, _| (),
+        )
+    }
+
+    /// The same as [`Bicycle::process`], but calls `progress` with the number
+    /// of actions finished so far and the total number of actions after each
+    /// one is done, which is handy for showing a progress bar. The total is
+    /// known once the source has been traversed, before anything is processed.
+    /// Actions that are skipped (per [`Bicycle::set_skip_sentinel`] or
+    /// [`Bicycle::set_overwrite_policy`], for instance) still count as
+    /// finished, so the count always reaches the total if processing succeeds.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// bicycle::Bicycle::default()
+    ///     .process_with_progress("templates", "out", |_| (), |finished, total| {
+    ///         eprint!("\r{}/{}", finished, total);
+    ///     })
+    ///     .unwrap();
+    /// ```
+    pub fn process_with_progress(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+        progress: impl FnMut(usize, usize),
+    ) -> Result<(), ProcessingError> {
+        self.process_filtered(
+            &self.options,
+            src.as_ref(),
+            dst.as_ref(),
+            insert_data,
+            |_| true,
+            progress,
+        )
+    }
+
+    /// The same as [`Bicycle::process`], but uses `policy` in place of the
+    /// policy set by [`Bicycle::set_overwrite_policy`].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use bicycle::{Bicycle, OverwritePolicy};
+    ///
+    /// // Scaffold into an existing project without clobbering anything.
+    /// Bicycle::default()
+    ///     .process_with_policy("templates", ".", OverwritePolicy::Never, |_| ())
+    ///     .unwrap();
+    /// ```
+    pub fn process_with_policy(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        policy: OverwritePolicy,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        let options = ProcessOptions {
+            overwrite_policy: policy,
+            ..self.options.clone()
+        };
+        self.process_with_options(src, dst, &options, insert_data)
+    }
+
+    /// The same as [`Bicycle::process`], but with front matter enabled
+    /// regardless of [`Bicycle::set_front_matter`]. Requires the `serde_yaml`
+    /// feature.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// bicycle::Bicycle::default()
+    ///     .process_with_front_matter("templates", "out", |_| ())
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "serde_yaml")]
+    pub fn process_with_front_matter(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        let options = ProcessOptions {
+            front_matter: true,
+            ..self.options.clone()
+        };
+        self.process_with_options(src, dst, &options, insert_data)
+    }
+
+    /// The same as [`Bicycle::process`], but deletes everything inside of
+    /// `dst` first, so nothing from a previous run lingers. `dst` itself is
+    /// kept, and symlinks inside of it are deleted rather than followed.
+    ///
+    /// As a safeguard, this fails with [`ProcessingError::CleanRefused`]
+    /// without deleting anything if `dst` is or contains the current working
+    /// directory or `src`. Failing to delete anything results in a
+    /// [`ProcessingError::DeletionFailed`].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// bicycle::Bicycle::default()
+    ///     .process_clean("templates", "out", |_| ())
+    ///     .unwrap();
+    /// ```
+    pub fn process_clean(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        clean(src, dst)?;
+        self.process(src, dst, insert_data)
+    }
+
+    /// A convenience method that does the same work as [`Bicycle::process`],
+    /// but applies a filter predicate to each action prior to processing it.
+    pub fn filter_and_process(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+        filter: impl FnMut(&Action) -> bool,
+    ) -> Result<(), ProcessingError> {
+        self.process_filtered(
+            &self.options,
+            src.as_ref(),
+            dst.as_ref(),
+            insert_data,
+            filter,
+            |_, _| (),
+        )
+    }
+
+    /// Does the same work as [`Bicycle::process`], but only processes files
+    /// whose path relative to `src` matches `glob`, like `"src/**"` or
+    /// `"**/*.rs.hbs"`. Requires the `globset` feature.
+    ///
+    /// Directories are only created if they contain a matching file, so
+    /// unrelated parts of the tree don't show up empty in `dst`. Paths are
+    /// matched as found in `src`, before the template extension is stripped or
+    /// any templated components are rendered.
+    ///
+    /// Fails with [`ProcessingError::GlobParseFailed`] if `glob` isn't valid.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// // Only regenerate the sources, leaving everything else alone.
+    /// bicycle::Bicycle::default()
+    ///     .process_matching("templates", "out", "src/**", |_| ())
+    ///     .unwrap();
+    /// ```
+    #[cfg(feature = "globset")]
+    pub fn process_matching(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        glob: &str,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        let matcher = globset::Glob::new(glob)
+            .map_err(|cause| ProcessingError::GlobParseFailed {
+                glob: glob.to_owned(),
+                cause,
+            })?
+            .compile_matcher();
+        let options = &self.options;
+        let actions = self.plan(options, src, dst, dst, &insert_data)?;
+        let matched = actions
+            .iter()
+            .filter(|action| !action.tag().create_directory())
+            .filter_map(|action| action.src().strip_prefix(src).ok())
+            .filter(|relative| matcher.is_match(relative))
+            .collect::<HashSet<_>>();
+        let needed = matched
+            .iter()
+            .flat_map(|relative| relative.ancestors().skip(1))
+            .collect::<HashSet<_>>();
+        let actions = actions
+            .iter()
+            .filter(|action| match action.src().strip_prefix(src) {
+                Ok(relative) if action.tag().create_directory() => needed.contains(relative),
+                Ok(relative) => matched.contains(relative),
+                Err(_) => false,
+            })
+            .collect::<Vec<_>>();
+        log::info!("{} actions match glob {:?}", actions.len(), glob);
+        let mut completed = Vec::new();
+        self.process_tree(
+            options,
+            src,
+            actions.into_iter(),
+            insert_data,
+            &mut completed,
+            || (),
+        )?;
+        self.mark_generated(options, dst, completed.into_iter())
+    }
+
+    fn process_filtered(
+        &self,
+        options: &ProcessOptions,
+        src: &Path,
+        dst: &Path,
+        insert_data: impl Fn(&mut JsonMap),
+        mut filter: impl FnMut(&Action) -> bool,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<(), ProcessingError> {
+        let actions = self
+            .plan(options, src, dst, dst, &insert_data)?
+            .into_iter()
+            .filter(|action| filter(action))
+            .collect::<Vec<_>>();
+        let existed = match options.output_manifest {
+            Some(_) => actions
+                .iter()
+                .map(|action| action.dst())
+                .filter(|dst| fs::symlink_metadata(dst).is_ok())
+                .map(ToOwned::to_owned)
+                .collect(),
+            None => HashSet::new(),
+        };
+        let mut completed = Vec::new();
+        let total = actions.len();
+        let mut finished = 0;
+        let processed = self.process_tree(
+            options,
+            src,
+            actions.iter(),
+            insert_data,
+            &mut completed,
+            || {
+                finished += 1;
+                progress(finished, total);
+            },
+        );
+        let recorded = match &options.output_manifest {
+            Some(name) => OutputManifest::record(dst, &existed, completed.iter().copied())
+                .write(&dst.join(name)),
+            None => Ok(()),
+        };
+        processed.and(recorded)?;
+        self.mark_generated(options, dst, completed.into_iter())
+    }
+
+    /// Does the same work as [`Bicycle::process`], but only for the `subtree`
+    /// of `src`, which is a path relative to `src` like `"services/api"`.
+    ///
+    /// Destinations keep the subtree's path, so `src/services/api/main.rs` is
+    /// written to `dst/services/api/main.rs`, just as [`Bicycle::process`]
+    /// would write it; pointing [`Bicycle::process`] at the subtree instead
+    /// would write it to `dst/main.rs`. Templated components of `subtree` are
+    /// rendered like any other destination path. Directory data from `src`
+    /// down to the subtree still applies, and destination directives are
+    /// relative to `dst`.
+    ///
+    /// Fails with [`ProcessingError::SubtreeNotRelative`] if `subtree` is
+    /// absolute or contains `..`.
+    pub fn process_subtree(
+        &self,
+        src: impl AsRef<Path>,
+        subtree: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        let (src, subtree, dst) = (src.as_ref(), subtree.as_ref(), dst.as_ref());
+        if !subtree
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            return Err(ProcessingError::SubtreeNotRelative {
+                subtree: subtree.to_owned(),
+            });
+        }
+        let options = &self.options;
+        let actions = self.plan(
+            options,
+            src.join(subtree),
+            dst.join(subtree),
+            dst,
+            &insert_data,
+        )?;
+        let mut completed = Vec::new();
+        self.process_tree(
+            options,
+            src,
+            actions.iter(),
+            insert_data,
+            &mut completed,
+            || (),
+        )?;
+        self.mark_generated(options, dst, completed.into_iter())
+    }
+
+    /// Re-renders the templates in `src` into an existing output at `dst`,
+    /// without copying any files. This is faster than [`Bicycle::process`]
+    /// when iterating on template contents, since the static files are
+    /// already in place. Directories are only created if they're missing.
+    pub fn update(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        self.filter_and_process(src, dst, insert_data, |action| match action.tag() {
+            Tag::WriteTemplate | Tag::AppendTemplate => true,
+            Tag::CreateDirectory => !action.dst().is_dir(),
+            Tag::CopyFile | Tag::Delete | Tag::Symlink => false,
+        })
+    }
+
+    /// Does the same work as [`Bicycle::process`], but returns how many
+    /// actions of each kind were executed, for printing a summary like
+    /// "Created 12 directories, wrote 30 templates, copied 5 files." Actions
+    /// that were skipped aren't counted.
+    ///
+    /// This is built on [`Bicycle::process_returning`], so use that along
+    /// with [`ActionSummary::tally`] to get the completed actions too.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let bike = bicycle::Bicycle::default();
+    /// println!("{}", bike.process_summarized("templates", "out", |_| ()).unwrap());
+    /// ```
+    pub fn process_summarized(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<ActionSummary, ProcessingError> {
+        self.process_returning(src, dst, insert_data)
+            .map(|completed| ActionSummary::tally(&completed))
+            .map_err(|err| err.cause)
+    }
+
+    /// Does the same work as [`Bicycle::process`], but returns the actions
+    /// that were executed, which is handy for logging or writing a manifest.
+    ///
+    /// If processing fails partway through, the error contains the actions
+    /// that were completed before the failure.
+    pub fn process_returning(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<VecDeque<Action>, PartialProcessingError> {
+        let options = &self.options;
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        let actions = self
+            .plan(options, src, dst, dst, &insert_data)
+            .map_err(|cause| PartialProcessingError {
+                completed: VecDeque::new(),
+                cause,
+            })?;
+        let mut context = options.directory_context(src);
+        let mut completed = VecDeque::new();
+        for action in actions {
+            let result = context.data_for(&action).and_then(|data| {
+                self.execute(options, &action, with_data(&insert_data, data), None)
+            });
+            match result {
+                Ok(true) => completed.push_back(action),
+                Ok(false) => (),
+                Err(cause) => return Err(PartialProcessingError { completed, cause }),
+            }
+        }
+        match self.mark_generated(options, dst, completed.iter()) {
+            Ok(()) => Ok(completed),
+            Err(cause) => Err(PartialProcessingError { completed, cause }),
+        }
+    }
+
+    /// Plans a sync that makes `dst` mirror `src`. This returns the same
+    /// actions as [`Bicycle::process_returning`] would execute, followed by a
+    /// [`Tag::Delete`] for everything in `dst` that has no corresponding
+    /// source. Nothing is executed, so this doubles as a dry run for
+    /// [`Bicycle::sync`].
+    pub fn sync_actions(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<VecDeque<Action>, ProcessingError> {
+        let options = &self.options;
+        let dst = dst.as_ref();
+        let mut actions = self.plan(options, src, dst, dst, &insert_data)?;
+        let keep = actions
+            .iter()
+            .map(|action| action.dst().to_owned())
+            .collect();
+        let deletions = traverse::find_deletions(dst, &keep).map_err(|cause| {
+            ProcessingError::TraversalFailed {
+                src: dst.to_owned(),
+                cause,
+            }
+        })?;
+        actions.extend(deletions);
+        Ok(actions)
+    }
+
+    /// Makes `dst` mirror `src`, by processing `src` as [`Bicycle::process`]
+    /// does and then deleting everything in `dst` that has no corresponding
+    /// source.
+    ///
+    /// Since deletion is destructive, `confirm_delete` is called with the path
+    /// of each file or directory before it's deleted, and only what it returns
+    /// `true` for is deleted. Everything else is processed before anything is
+    /// deleted. Use [`Bicycle::sync_actions`] to see what would happen without
+    /// changing anything.
+    pub fn sync(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+        mut confirm_delete: impl FnMut(&Path) -> bool,
+    ) -> Result<(), ProcessingError> {
+        let options = &self.options;
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        let actions = self.sync_actions(src, dst, &insert_data)?;
+        let mut completed = Vec::new();
+        self.process_tree(
+            options,
+            src,
+            actions
+                .iter()
+                .filter(|action| !action.tag().delete() || confirm_delete(action.dst())),
+            insert_data,
+            &mut completed,
+            || (),
+        )?;
+        self.mark_generated(options, dst, completed.into_iter())
+    }
+
+    /// Does the same work as [`Bicycle::process`], but merges output into
+    /// files that were edited since they were last generated, rather than
+    /// overwriting them. This makes generators safe to rerun over projects
+    /// that have been worked on since.
+    ///
+    /// What was generated for each file is recorded in a
+    /// [`MANIFEST_FILE_NAME`] file in the root of `dst`, which serves as the
+    /// base of a line-based three-way merge with the current file and the new
+    /// output the next time around. Where both changed the same lines
+    /// differently, both versions are written between `<<<<<<< current`,
+    /// `=======`, and `>>>>>>> generated` markers for the user to resolve. A
+    /// file that exists without having been generated before is treated as if
+    /// it had been generated empty, so it conflicts as a whole unless it
+    /// already matches the new output.
+    ///
+    /// Only rendered templates and copied text files are merged. Everything
+    /// else is processed as usual, and the overwrite policy doesn't apply,
+    /// since merging takes its place.
+    ///
+    /// Returns the destinations that were written with conflict markers.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let bike = bicycle::Bicycle::default();
+    /// for path in bike.process_merge("templates", ".", |_| ()).unwrap() {
+    ///     eprintln!("conflicts in {}", path.display());
+    /// }
+    /// ```
+    pub fn process_merge(
         &self,
-        actions: impl iter::Iterator<Item = &'iter_item Action>,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
         insert_data: impl Fn(&mut JsonMap),
-    ) -> Result<(), ProcessingError> {
-        for action in actions {
-            self.process_action(action, &insert_data)?;
+    ) -> Result<Vec<PathBuf>, ProcessingError> {
+        let options = &self.options;
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        let previous = Manifest::load(dst)?;
+        let mut manifest = Manifest::default();
+        let actions = self.plan(options, src, dst, dst, &insert_data)?;
+        let mut context = options.directory_context(src);
+        let mut written = Vec::new();
+        let mut conflicted = Vec::new();
+        for action in &actions {
+            let data = context.data_for(action)?;
+            let insert_data = with_data(&insert_data, data);
+            let tag = self.effective_tag(options, action)?;
+            let copy_failed = |cause| ProcessingError::FileCopyFailed {
+                src: action.src().to_owned(),
+                dst: action.dst().to_owned(),
+                cause,
+            };
+            let generated = match tag {
+                Tag::WriteTemplate => match self.rendered_output(options, action, &insert_data)? {
+                    Some(rendered) => rendered,
+                    None => continue,
+                },
+                Tag::CopyFile => match fs::read(action.src()).map(String::from_utf8) {
+                    Ok(Ok(text)) => text,
+                    // Binary files can't be merged, so they're copied as usual.
+                    Ok(Err(_)) => {
+                        if self.execute(options, action, &insert_data, None)? {
+                            written.push(action);
+                        }
+                        continue;
+                    }
+                    Err(cause) => return Err(copy_failed(cause)),
+                },
+                _ => {
+                    if self.execute(options, action, &insert_data, None)? && !tag.create_directory()
+                    {
+                        written.push(action);
+                    }
+                    continue;
+                }
+            };
+            let key = action.dst().strip_prefix(dst).ok().and_then(merge::key);
+            let merged = match fs::read_to_string(action.dst()) {
+                Ok(current) => {
+                    let base = key.as_deref().and_then(|key| previous.get(key));
+                    let merged = merge::merge(base.unwrap_or_default(), &current, &generated);
+                    if merged.conflicts {
+                        log::warn!("merging into {:?} produced conflicts", action.dst());
+                        conflicted.push(action.dst().to_owned());
+                    }
+                    Some(merged.text).filter(|merged| *merged != current)
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Some(generated.clone()),
+                Err(cause) => {
+                    return Err(ProcessingError::DestinationReadFailed {
+                        path: action.dst().to_owned(),
+                        cause,
+                    })
+                }
+            };
+            if let Some(key) = key {
+                manifest.insert(key, generated);
+            }
+            let merged = match merged {
+                Some(merged) => merged,
+                None => continue,
+            };
+            if options.dest_directives {
+                if let Some(parent) = action.dst().parent() {
+                    self.create_directory(options, parent)?;
+                }
+            }
+            fs::File::create(action.dst())
+                .and_then(|mut file| {
+                    copy_mode(action.src(), &file)?;
+                    file.write_all(merged.as_bytes())
+                })
+                .map_err(|cause| match tag {
+                    Tag::CopyFile => copy_failed(cause),
+                    _ => ProcessingError::TemplateWriteFailed {
+                        src: action.src().to_owned(),
+                        dst: action.dst().to_owned(),
+                        cause,
+                    },
+                })?;
+            written.push(action);
         }
-        Ok(())
+        manifest.write(dst)?;
+        self.mark_generated(options, dst, written.into_iter())?;
+        Ok(conflicted)
     }
 
-    /// A convenience method that calls [`traverse`](traverse()) and passes the
-    /// output to [`Bicycle::process_actions`]. Uses [`Bicycle::transform_dst`]
-    /// as the `transform_dst` argument and `DEFAULT_TEMPLATE_EXT` ("hbs") as
-    /// the `template_ext` argument to [`traverse`](traverse()).
-    pub fn process(
+    /// Marks the files written by `actions` in `.gitattributes`, if enabled.
+    fn mark_generated<'a>(
         &self,
-        src: impl AsRef<Path>,
-        dst: impl AsRef<Path>,
-        insert_data: impl Fn(&mut JsonMap),
+        options: &ProcessOptions,
+        dst: &Path,
+        actions: impl Iterator<Item = &'a Action>,
     ) -> Result<(), ProcessingError> {
-        self.filter_and_process(src, dst, insert_data, |_| true)
+        let mode = match options.mark_generated {
+            Some(mode) => mode,
+            None => return Ok(()),
+        };
+        let paths = actions
+            .filter(|action| action.tag().copy_file() || action.tag().write_template())
+            .filter_map(|action| action.dst().strip_prefix(dst).ok());
+        gitattributes::mark_generated(dst, mode, paths).map_err(|cause| {
+            ProcessingError::GitAttributesWriteFailed {
+                path: dst.join(gitattributes::FILE_NAME),
+                cause,
+            }
+        })
     }
 
-    /// A convenience method that does the same work as [`Bicycle::process`],
-    /// but applies a filter predicate to each action prior to processing it.
-    pub fn filter_and_process(
+    /// Traverses `src` the way [`Bicycle::process`] does, leaving out any
+    /// sidecar and directory data files. `dst_root` is the root of the whole
+    /// destination, which destination directives are relative to.
+    fn plan(
         &self,
+        options: &ProcessOptions,
         src: impl AsRef<Path>,
         dst: impl AsRef<Path>,
+        dst_root: &Path,
         insert_data: impl Fn(&mut JsonMap),
-        mut filter: impl FnMut(&Action) -> bool,
-    ) -> Result<(), ProcessingError> {
-        let src = src.as_ref();
-        traverse(
+    ) -> Result<VecDeque<Action>, ProcessingError> {
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        traverse_with_config(
             src,
             dst,
-            |path| self.transform_dst(path, &insert_data),
-            DEFAULT_TEMPLATE_EXT,
+            |path| self.transform_dst_with(options, path, &insert_data),
+            options.traverse_config(),
         )
         .map_err(|cause| ProcessingError::TraversalFailed {
             src: src.to_owned(),
             cause,
+        })?
+        .into_iter()
+        .filter(|action| !options.is_data_file(action))
+        .filter_map(|action| {
+            self.apply_directives(options, action, dst_root, &insert_data)
+                .transpose()
         })
-        .and_then(|actions| {
-            self.process_actions(actions.iter().filter(|action| filter(action)), insert_data)
-        })
+        .collect()
+    }
+
+    /// Does the same work as [`Bicycle::process`], but executes each action as
+    /// soon as it's discovered, rather than traversing the whole tree first.
+    ///
+    /// This lowers peak memory usage and gets output written sooner for huge
+    /// trees, but at the cost of never having a complete plan available: if
+    /// something fails partway through, everything discovered prior to the
+    /// failure will have already been processed. Directories are still always
+    /// created before anything is written into them.
+    pub fn process_streaming(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        let options = &self.options;
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        let actions = TraverseIter::new(
+            src,
+            dst,
+            |path| self.transform_dst_with(options, path, &insert_data),
+            options.traverse_config(),
+        );
+        let mut context = options.directory_context(src);
+        // Only the file actions need to be kept around, and only if they're
+        // going to be marked as generated.
+        let mut written = Vec::new();
+        for action in actions {
+            let action = action.map_err(|cause| ProcessingError::TraversalFailed {
+                src: src.to_owned(),
+                cause,
+            })?;
+            if options.is_data_file(&action) {
+                continue;
+            }
+            if let Some(action) = self.apply_directives(options, action, dst, &insert_data)? {
+                let data = context.data_for(&action)?;
+                let executed =
+                    self.execute(options, &action, with_data(&insert_data, data), None)?;
+                if executed && options.mark_generated.is_some() && !action.tag().create_directory()
+                {
+                    written.push(action);
+                }
+            }
+        }
+        self.mark_generated(options, dst, written.iter())
+    }
+
+    /// Does the same work as [`Bicycle::process_streaming`], but sends the
+    /// output to `sink` rather than writing it to a directory. This makes it
+    /// possible to generate straight into an archive (see `ZipSink` and
+    /// `TarSink`, behind the `zip` and `tar` features respectively) without
+    /// needing a temporary directory.
+    ///
+    /// Destination paths are relative to the root of the output. Templates and
+    /// copied files keep the permission bits of their source on Unix, so
+    /// executables stay executable as long as the sink supports it.
+    pub fn process_into(
+        &self,
+        src: impl AsRef<Path>,
+        mut sink: impl Sink,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        let options = &self.options;
+        let src = src.as_ref();
+        let actions = TraverseIter::new(
+            src,
+            "",
+            |path| self.transform_dst_with(options, path, &insert_data),
+            TraverseConfig {
+                // Sinks can't hold symlinks.
+                preserve_symlinks: false,
+                ..options.traverse_config()
+            },
+        );
+        let mut context = options.directory_context(src);
+        for action in actions {
+            let action = action.map_err(|cause| ProcessingError::TraversalFailed {
+                src: src.to_owned(),
+                cause,
+            })?;
+            if options.is_data_file(&action) {
+                continue;
+            }
+            let action =
+                match self.apply_directives(options, action, Path::new(""), &insert_data)? {
+                    Some(action) => action,
+                    None => continue,
+                };
+            log::info!("{:#?}", action);
+            match self.effective_tag(options, &action)? {
+                Tag::CreateDirectory => {
+                    // The root of the output doesn't need an entry.
+                    if action.dst().as_os_str().is_empty() {
+                        continue;
+                    }
+                    sink.create_dir(action.dst()).map_err(|cause| {
+                        ProcessingError::DirectoryCreationFailed {
+                            dst: action.dst().to_owned(),
+                            cause,
+                        }
+                    })?;
+                }
+                Tag::CopyFile => {
+                    fs::read(action.src())
+                        .and_then(|contents| {
+                            sink.write_file(action.dst(), &contents, source_mode(action.src()))
+                        })
+                        .map_err(|cause| ProcessingError::FileCopyFailed {
+                            src: action.src().to_owned(),
+                            dst: action.dst().to_owned(),
+                            cause,
+                        })?;
+                }
+                Tag::WriteTemplate => {
+                    let data = context.data_for(&action)?;
+                    let rendered = self.render_template_file(
+                        options,
+                        &action,
+                        with_data(&insert_data, data),
+                        false,
+                    )?;
+                    if self.skip_rendered(options, &action, &rendered) {
+                        continue;
+                    }
+                    let rendered = self.post_rendered(options, &action, rendered)?;
+                    sink.write_file(action.dst(), rendered.as_bytes(), source_mode(action.src()))
+                        .map_err(|cause| ProcessingError::TemplateWriteFailed {
+                            src: action.src().to_owned(),
+                            dst: action.dst().to_owned(),
+                            cause,
+                        })?;
+                }
+                Tag::AppendTemplate => {
+                    return Err(ProcessingError::AppendUnsupported {
+                        src: action.src().to_owned(),
+                        dst: action.dst().to_owned(),
+                    })
+                }
+                // Only `Bicycle::sync_actions` produces these, and symlinks
+                // are always followed here.
+                Tag::Delete | Tag::Symlink => unreachable!(),
+            }
+        }
+        Ok(())
+    }
+
+    /// Does the same work as [`Bicycle::process_streaming`], but creates,
+    /// writes, and reads back everything in `dst` through `target`. With a
+    /// [`MemFs`], this generates into memory while still respecting
+    /// [`Bicycle::set_overwrite_policy`] and appending to what's already
+    /// there, which makes it possible to test scaffolding logic without
+    /// touching the disk.
+    ///
+    /// Sources are still read from disk, and symlinks in them are always
+    /// followed. Only the overwrite policy, appending, and skipping apply to
+    /// the output; features that need the destination to be on disk, like
+    /// [`Bicycle::set_mark_generated`] and destination metadata, don't.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use bicycle::{Bicycle, MemFs};
+    ///
+    /// let fs = MemFs::default();
+    /// Bicycle::default()
+    ///     .process_with_fs("templates", "out", &fs, |map| map.insert("name", "shinji"))
+    ///     .unwrap();
+    /// assert!(fs.file("out/README.md").is_some());
+    /// ```
+    pub fn process_with_fs(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        target: &impl FileSystem,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<(), ProcessingError> {
+        let options = &self.options;
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        let actions = TraverseIter::new(
+            src,
+            dst,
+            |path| self.transform_dst_with(options, path, &insert_data),
+            TraverseConfig {
+                preserve_symlinks: false,
+                ..options.traverse_config()
+            },
+        );
+        // Whether or not `policy` leaves the file at `dst` alone, given what
+        // would replace it.
+        let overwrite_skipped = |dst: &Path, contents: &dyn Fn() -> io::Result<Vec<u8>>| {
+            let existing = match target.read(dst) {
+                Ok(existing) => Some(existing),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+                Err(err) => return Err(err),
+            };
+            let skip = match (options.overwrite_policy, existing) {
+                (_, None) | (OverwritePolicy::Always, _) => false,
+                (OverwritePolicy::Never, Some(_)) => true,
+                (OverwritePolicy::IfChanged, Some(existing)) => existing == contents()?,
+            };
+            if skip {
+                log::info!(
+                    "skipping {:?}, since the overwrite policy is {:?}",
+                    dst,
+                    options.overwrite_policy
+                );
+            }
+            Ok(skip)
+        };
+        let mut context = options.directory_context(src);
+        for action in actions {
+            let action = action.map_err(|cause| ProcessingError::TraversalFailed {
+                src: src.to_owned(),
+                cause,
+            })?;
+            if options.is_data_file(&action) {
+                continue;
+            }
+            let action = match self.apply_directives(options, action, dst, &insert_data)? {
+                Some(action) => action,
+                None => continue,
+            };
+            log::info!("{:#?}", action);
+            let tag = self.effective_tag(options, &action)?;
+            let rendered = match tag {
+                Tag::CreateDirectory => {
+                    target.create_dir_all(action.dst()).map_err(|cause| {
+                        ProcessingError::DirectoryCreationFailed {
+                            dst: action.dst().to_owned(),
+                            cause,
+                        }
+                    })?;
+                    continue;
+                }
+                Tag::CopyFile => {
+                    let skipped = overwrite_skipped(action.dst(), &|| fs::read(action.src()));
+                    skipped
+                        .and_then(|skipped| {
+                            if skipped {
+                                Ok(())
+                            } else {
+                                target.copy(action.src(), action.dst())
+                            }
+                        })
+                        .map_err(|cause| ProcessingError::FileCopyFailed {
+                            src: action.src().to_owned(),
+                            dst: action.dst().to_owned(),
+                            cause,
+                        })?;
+                    continue;
+                }
+                Tag::WriteTemplate | Tag::AppendTemplate => {
+                    let data = context.data_for(&action)?;
+                    let rendered = self.render_template_file(
+                        options,
+                        &action,
+                        with_data(&insert_data, data),
+                        false,
+                    )?;
+                    if self.skip_rendered(options, &action, &rendered) {
+                        continue;
+                    }
+                    self.post_rendered(options, &action, rendered)?
+                }
+                // Only `Bicycle::sync_actions` produces these, and symlinks
+                // are always followed here.
+                Tag::Delete | Tag::Symlink => unreachable!(),
+            };
+            let write_failed = |cause| ProcessingError::TemplateWriteFailed {
+                src: action.src().to_owned(),
+                dst: action.dst().to_owned(),
+                cause,
+            };
+            let contents = if tag.append_template() {
+                let mut existing = match target.read(action.dst()) {
+                    Ok(existing) => existing,
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+                    Err(cause) => return Err(write_failed(cause)),
+                };
+                if options.append_dedupe && String::from_utf8_lossy(&existing).contains(&rendered) {
+                    log::info!(
+                        "skipping {:?}, since {:?} already contains its output",
+                        action.src(),
+                        action.dst()
+                    );
+                    continue;
+                }
+                existing.extend(rendered.into_bytes());
+                existing
+            } else {
+                let rendered = rendered.into_bytes();
+                if overwrite_skipped(action.dst(), &|| Ok(rendered.clone()))
+                    .map_err(write_failed)?
+                {
+                    continue;
+                }
+                rendered
+            };
+            // Destination directives can point anywhere, so parent
+            // directories can't be assumed to exist.
+            if options.dest_directives {
+                if let Some(parent) = action.dst().parent() {
+                    target.create_dir_all(parent).map_err(|cause| {
+                        ProcessingError::DirectoryCreationFailed {
+                            dst: parent.to_owned(),
+                            cause,
+                        }
+                    })?;
+                }
+            }
+            target
+                .write(action.dst(), &contents)
+                .map_err(write_failed)?;
+        }
+        Ok(())
+    }
+
+    /// Reports what [`Bicycle::process`] would do, without creating, copying,
+    /// or writing anything. Templates are still rendered in memory, so this
+    /// fails the same way processing would if one of them fails to render.
+    /// Actions that would be skipped (per [`Bicycle::set_skip_sentinel`],
+    /// [`Bicycle::set_append_dedupe`], or [`Bicycle::set_overwrite_policy`])
+    /// are left out, just like they're left out of what
+    /// [`Bicycle::process_returning`] returns.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// let bike = bicycle::Bicycle::default();
+    /// for planned in bike.process_dry_run("templates", "out", |_| ()).unwrap() {
+    ///     println!(
+    ///         "{}{}",
+    ///         planned.describe(),
+    ///         if planned.overwrites() { " (overwrite)" } else { "" },
+    ///     );
+    /// }
+    /// ```
+    pub fn process_dry_run(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<Vec<PlannedAction>, ProcessingError> {
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        let options = &self.options;
+        let actions = self.plan(options, src, dst, dst, &insert_data)?;
+        let mut context = options.directory_context(src);
+        let mut planned = Vec::with_capacity(actions.len());
+        for mut action in actions {
+            let tag = self.effective_tag(options, &action)?;
+            let policy = options.overwrite_policy;
+            let skipped = match tag {
+                Tag::CopyFile => overwrite_skipped(policy, action.dst(), || fs::read(action.src()))
+                    .unwrap_or(false),
+                Tag::WriteTemplate | Tag::AppendTemplate => {
+                    let data = context.data_for(&action)?;
+                    let rendered = self.render_template_file(
+                        options,
+                        &action,
+                        with_data(&insert_data, data),
+                        true,
+                    )?;
+                    if self.skip_rendered(options, &action, &rendered) {
+                        true
+                    } else if tag.append_template() {
+                        options.append_dedupe
+                            && already_appended(action.dst(), &rendered).unwrap_or(false)
+                    } else {
+                        let rendered = self.post_rendered(options, &action, rendered)?;
+                        overwrite_skipped(policy, action.dst(), || Ok(rendered.into_bytes()))
+                            .unwrap_or(false)
+                    }
+                }
+                Tag::Symlink => {
+                    let target = self.symlink_target(options, &action, &insert_data)?;
+                    symlink_skipped(policy, &target, action.dst())
+                }
+                Tag::CreateDirectory | Tag::Delete => false,
+            };
+            if skipped {
+                continue;
+            }
+            action.set_tag(tag);
+            let overwrites = fs::symlink_metadata(action.dst()).is_ok();
+            planned.push(PlannedAction { action, overwrites });
+        }
+        Ok(planned)
+    }
+
+    /// Reports how processing `src` into `dst` would change what's on disk,
+    /// without writing anything. Templates are rendered in memory and compared
+    /// against the existing files, so this fails the same way processing would
+    /// if one of them fails to render.
+    ///
+    /// Files and symlinks are reported in the order they'd be processed,
+    /// followed by what has no corresponding source, which is what
+    /// [`Bicycle::sync`] would delete. Directories are only reported when
+    /// removed, and then only once rather than for each file inside of them.
+    /// Templates that would be skipped (per [`Bicycle::set_skip_sentinel`])
+    /// aren't reported, and files that [`Bicycle::set_overwrite_policy`]
+    /// would leave alone are [`FileDiff::Unchanged`].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use bicycle::{Bicycle, FileDiff};
+    ///
+    /// for diff in Bicycle::default().diff("templates", "out", |_| ()).unwrap() {
+    ///     match diff {
+    ///         FileDiff::Added(path) => println!("added: {}", path.display()),
+    ///         FileDiff::Modified(path) => println!("modified: {}", path.display()),
+    ///         FileDiff::Removed(path) => println!("removed: {}", path.display()),
+    ///         FileDiff::Unchanged(_) => (),
+    ///     }
+    /// }
+    /// ```
+    pub fn diff(
+        &self,
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<Vec<FileDiff>, ProcessingError> {
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        let options = &self.options;
+        let actions = self.sync_actions(src, dst, &insert_data)?;
+        let mut context = options.directory_context(src);
+        let mut diffs = Vec::new();
+        for action in actions {
+            let path = action.dst().to_owned();
+            let tag = self.effective_tag(options, &action)?;
+            let contents = match tag {
+                Tag::CreateDirectory => continue,
+                Tag::Delete => {
+                    diffs.push(FileDiff::Removed(path));
+                    continue;
+                }
+                Tag::Symlink => {
+                    let target = self.symlink_target(options, &action, &insert_data)?;
+                    diffs.push(if fs::symlink_metadata(&path).is_err() {
+                        FileDiff::Added(path)
+                    } else if fs::read_link(&path).is_ok_and(|existing| existing == target)
+                        || symlink_skipped(options.overwrite_policy, &target, &path)
+                    {
+                        FileDiff::Unchanged(path)
+                    } else {
+                        FileDiff::Modified(path)
+                    });
+                    continue;
+                }
+                Tag::CopyFile => {
+                    fs::read(action.src()).map_err(|cause| ProcessingError::FileCopyFailed {
+                        src: action.src().to_owned(),
+                        dst: path.clone(),
+                        cause,
+                    })?
+                }
+                Tag::WriteTemplate | Tag::AppendTemplate => {
+                    let data = context.data_for(&action)?;
+                    match self.rendered_output(options, &action, with_data(&insert_data, data))? {
+                        Some(rendered) => rendered.into_bytes(),
+                        None => continue,
+                    }
+                }
+            };
+            let existing = match fs::read(&path) {
+                Ok(existing) => existing,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    diffs.push(FileDiff::Added(path));
+                    continue;
+                }
+                Err(cause) => return Err(ProcessingError::DestinationReadFailed { path, cause }),
+            };
+            let unchanged = if tag.append_template() {
+                // Output is appended regardless of the overwrite policy.
+                contents.is_empty()
+                    || options.append_dedupe
+                        && already_appended(&path, &String::from_utf8_lossy(&contents))
+                            .unwrap_or(false)
+            } else {
+                existing == contents || options.overwrite_policy == OverwritePolicy::Never
+            };
+            diffs.push(if unchanged {
+                FileDiff::Unchanged(path)
+            } else {
+                FileDiff::Modified(path)
+            });
+        }
+        Ok(diffs)
+    }
+
+    /// Renders every template in `src` with the data processing would use,
+    /// without writing anything, to check ahead of time that processing would
+    /// succeed. This catches render-time errors like missing variables and
+    /// failing helpers.
+    ///
+    /// Rather than stopping at the first failure, this returns the error for
+    /// each template that failed, keyed by its source path; an empty map means
+    /// every template rendered successfully. Errors that would stop processing
+    /// before any template is rendered, like a destination path that fails to
+    /// render, are returned as the outer error instead. Destination metadata
+    /// isn't available, since nothing is written.
+    pub fn check(
+        &self,
+        src: impl AsRef<Path>,
+        insert_data: impl Fn(&mut JsonMap),
+    ) -> Result<BTreeMap<PathBuf, ProcessingError>, ProcessingError> {
+        let src = src.as_ref();
+        let options = &self.options;
+        let actions = self.plan(options, src, "", Path::new(""), &insert_data)?;
+        let mut context = options.directory_context(src);
+        let mut failures = BTreeMap::new();
+        for action in actions.iter().filter(|action| action.tag().renders()) {
+            let result = self.effective_tag(options, action).and_then(|tag| {
+                if tag.renders() {
+                    let data = context.data_for(action)?;
+                    self.render_template_file(options, action, with_data(&insert_data, data), false)
+                        .map(drop)
+                } else {
+                    Ok(())
+                }
+            });
+            if let Err(err) = result {
+                failures.insert(action.src().to_owned(), err);
+            }
+        }
+        Ok(failures)
     }
 
     /// Renders a path string itself as a template.
     /// Intended to be used as the `transform_dst` argument to [`traverse`](traverse()).
+    ///
+    /// Paths are rendered the same way as template contents, so the helpers
+    /// and partials registered on this instance are available here too. The
+    /// exceptions are strictness, which can be relaxed for paths via
+    /// [`Bicycle::set_lenient_paths`], and escaping, which is replaced by the
+    /// sanitizer set via [`Bicycle::set_path_sanitizer`] (if any).
     pub fn transform_dst(
         &self,
         path: &Path,
         insert_data: impl FnOnce(&mut JsonMap),
     ) -> Result<PathBuf, RenderingError> {
-        let path_str = path.to_str().unwrap();
+        self.transform_dst_with(&self.options, path, insert_data)
+    }
+
+    /// The same as [`Bicycle::transform_dst`], but always renders `path`,
+    /// rather than only rendering paths that contain an opening delimiter.
+    ///
+    /// Text without any template syntax renders to itself, so this is mainly
+    /// useful for paths that have been rewritten into templates on the fly,
+    /// like when supporting a placeholder convention of your own.
+    ///
+    /// # Examples
+    /// ```
+    /// use std::path::Path;
+    ///
+    /// let bike = bicycle::Bicycle::default();
+    /// let path = Path::new("__project__/src");
+    /// let transformed = bike.transform_dst_always(
+    ///     Path::new(&path.to_str().unwrap().replace("__project__", "{{project}}")),
+    ///     |map| map.insert("project", "nerv"),
+    /// ).unwrap();
+    /// assert_eq!(transformed, Path::new("nerv/src"));
+    /// ```
+    pub fn transform_dst_always(
+        &self,
+        path: &Path,
+        insert_data: impl FnOnce(&mut JsonMap),
+    ) -> Result<PathBuf, RenderingError> {
+        self.render_dst(&self.options, path, insert_data)
+    }
+
+    fn transform_dst_with(
+        &self,
+        options: &ProcessOptions,
+        path: &Path,
+        insert_data: impl FnOnce(&mut JsonMap),
+    ) -> Result<PathBuf, RenderingError> {
+        let open = self
+            .delimiters
+            .as_ref()
+            .map(Delimiters::open)
+            .unwrap_or("{{");
         // This is naïve, but optimistically isn't a problem in practice.
-        if path_str.contains("{{") {
-            self.render(path_str, insert_data).map(Into::into)
-        } else {
+        if !path.to_str().unwrap().contains(open) {
             Ok(path.to_owned())
+        } else {
+            self.render_dst(options, path, insert_data)
+        }
+    }
+
+    /// Reads the target of the symlink at the source of `action`, and renders
+    /// it like a destination path. A relative target is rendered as-is, while
+    /// an absolute target inside the directory containing the source symlink
+    /// is moved to the same place relative to the destination symlink.
+    fn symlink_target(
+        &self,
+        options: &ProcessOptions,
+        action: &Action,
+        insert_data: impl FnOnce(&mut JsonMap),
+    ) -> Result<PathBuf, ProcessingError> {
+        let target = fs::read_link(action.src()).map_err(|cause| {
+            ProcessingError::SymlinkCreationFailed {
+                src: action.src().to_owned(),
+                dst: action.dst().to_owned(),
+                cause,
+            }
+        })?;
+        let render_failed = |cause| ProcessingError::TemplateRenderFailed {
+            src: action.src().to_owned(),
+            dst: Some(action.dst().to_owned()),
+            cause,
+        };
+        let rebased = if target.is_absolute() {
+            action
+                .src()
+                .parent()
+                .zip(action.dst().parent())
+                .and_then(|(src_dir, dst_dir)| {
+                    target
+                        .strip_prefix(src_dir)
+                        .ok()
+                        .map(|rest| (dst_dir, rest))
+                })
+        } else {
+            None
+        };
+        match rebased {
+            Some((dst_dir, rest)) => self
+                .transform_dst_with(options, rest, insert_data)
+                .map(|rest| dst_dir.join(rest)),
+            None => self.transform_dst_with(options, &target, insert_data),
+        }
+        .map_err(render_failed)
+    }
+
+    /// Renders `path` as a template, with the path-specific strictness and
+    /// escaping described by [`Bicycle::transform_dst`].
+    fn render_dst(
+        &self,
+        options: &ProcessOptions,
+        path: &Path,
+        insert_data: impl FnOnce(&mut JsonMap),
+    ) -> Result<PathBuf, RenderingError> {
+        let path_str = path.to_str().unwrap();
+        if (options.lenient_paths && self.handlebars.strict_mode()) || self.path_sanitizer.is_some()
+        {
+            let mut handlebars = self.handlebars.clone();
+            if options.lenient_paths {
+                handlebars.set_strict_mode(false);
+            }
+            if let Some(PathSanitizer(sanitizer)) = self.path_sanitizer {
+                handlebars.register_escape_fn(sanitizer);
+            }
+            let template = self.translate(path_str);
+            let data = self.data(insert_data);
+            catch_escape_fn_panic(|| handlebars.render_template(&template, &data.0)).map(Into::into)
+        } else {
+            self.render(path_str, insert_data).map(Into::into)
+        }
+    }
+}
+
+/// Splits `actions` into the phases run by [`Bicycle::process_actions_par`],
+/// each of which is a list of groups of indices that can run concurrently.
+fn parallel_phases(actions: &[Action]) -> [Vec<Vec<usize>>; 3] {
+    let (mut directories, mut files, mut deletions) = (Vec::new(), Vec::new(), Vec::new());
+    let mut appends = HashMap::<&Path, usize>::new();
+    for (index, action) in actions.iter().enumerate() {
+        match action.tag() {
+            Tag::CreateDirectory => directories.push(vec![index]),
+            Tag::CopyFile | Tag::WriteTemplate | Tag::Symlink => files.push(vec![index]),
+            Tag::AppendTemplate => match appends.get(action.dst()) {
+                Some(&group) => files[group].push(index),
+                None => {
+                    appends.insert(action.dst(), files.len());
+                    files.push(vec![index]);
+                }
+            },
+            Tag::Delete => deletions.push(index),
+        }
+    }
+    let deletions = if deletions.is_empty() {
+        Vec::new()
+    } else {
+        vec![deletions]
+    };
+    [directories, files, deletions]
+}
+
+/// Whether or not `policy` says to leave the file at `dst` alone instead of
+/// replacing it with `contents`.
+fn overwrite_skipped(
+    policy: OverwritePolicy,
+    dst: &Path,
+    contents: impl FnOnce() -> io::Result<Vec<u8>>,
+) -> io::Result<bool> {
+    let skip = match policy {
+        OverwritePolicy::Always => false,
+        OverwritePolicy::Never => fs::symlink_metadata(dst).is_ok(),
+        OverwritePolicy::IfChanged => match fs::read(dst) {
+            Ok(existing) => existing == contents()?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => false,
+            Err(err) => return Err(err),
+        },
+    };
+    if skip {
+        log::info!(
+            "skipping {:?}, since the overwrite policy is {:?}",
+            dst,
+            policy
+        );
+    }
+    Ok(skip)
+}
+
+/// Whether or not `policy` says to leave what's at `dst` alone instead of
+/// replacing it with a symlink to `target`.
+fn symlink_skipped(policy: OverwritePolicy, target: &Path, dst: &Path) -> bool {
+    let skip = match policy {
+        OverwritePolicy::Always => false,
+        OverwritePolicy::Never => fs::symlink_metadata(dst).is_ok(),
+        OverwritePolicy::IfChanged => fs::read_link(dst).is_ok_and(|existing| existing == target),
+    };
+    if skip {
+        log::info!(
+            "skipping {:?}, since the overwrite policy is {:?}",
+            dst,
+            policy
+        );
+    }
+    skip
+}
+
+/// Creates a symlink at `dst` pointing to `target`, which is where the symlink
+/// at `src` points.
+#[cfg(unix)]
+fn symlink(target: &Path, _src: &Path, dst: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, dst)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, src: &Path, dst: &Path) -> io::Result<()> {
+    // Windows needs to know what kind of symlink to create.
+    if fs::metadata(src).is_ok_and(|metadata| metadata.is_dir()) {
+        std::os::windows::fs::symlink_dir(target, dst)
+    } else {
+        std::os::windows::fs::symlink_file(target, dst)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn symlink(_target: &Path, _src: &Path, _dst: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "symlinks aren't supported on this platform",
+    ))
+}
+
+/// Whether or not the file at `dst` already contains `rendered`.
+fn already_appended(dst: &Path, rendered: &str) -> io::Result<bool> {
+    match read_text(dst) {
+        Ok(existing) => Ok(existing.contains(rendered)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// Deletes everything inside of `dst`, unless that would delete the current
+/// working directory or `src`. A missing `dst` has nothing to delete.
+fn clean(src: &Path, dst: &Path) -> Result<(), ProcessingError> {
+    let failed = |cause| ProcessingError::DeletionFailed {
+        dst: dst.to_owned(),
+        cause,
+    };
+    let canonical = match dst.canonicalize() {
+        Ok(canonical) => canonical,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(cause) => return Err(failed(cause)),
+    };
+    let cwd = std::env::current_dir()
+        .and_then(|cwd| cwd.canonicalize())
+        .map_err(failed)?;
+    // A missing source is reported by traversal instead.
+    let src = src.canonicalize().ok();
+    if let Some(path) = iter::once(cwd)
+        .chain(src)
+        .find(|path| path.starts_with(&canonical))
+    {
+        return Err(ProcessingError::CleanRefused {
+            dst: dst.to_owned(),
+            path,
+        });
+    }
+    for entry in fs::read_dir(&canonical).map_err(failed)? {
+        let path = entry.map_err(failed)?.path();
+        log::info!("cleaning {:?}", path);
+        transaction::remove(&path)
+            .map_err(|cause| ProcessingError::DeletionFailed { dst: path, cause })?;
+    }
+    Ok(())
+}
+
+/// Writes to `file`, and then whatever was written to `tee` (if any).
+struct Tee<'a> {
+    file: fs::File,
+    tee: Option<&'a mut dyn Write>,
+}
+
+impl Write for Tee<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.file.write(buf)?;
+        if let Some(tee) = &mut self.tee {
+            tee.write_all(&buf[..written])?;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        match &mut self.tee {
+            Some(tee) => tee.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Keeps the first error from `writer`, for when whatever's writing to it
+/// doesn't pass the error along.
+struct KeepError<W> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> Write for KeepError<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf).map_err(|err| self.keep(err))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush().map_err(|err| self.keep(err))
+    }
+}
+
+impl<W> KeepError<W> {
+    fn keep(&mut self, err: io::Error) -> io::Error {
+        let copy = io::Error::new(err.kind(), err.to_string());
+        self.error.get_or_insert(err);
+        copy
+    }
+}
+
+/// Copies `src` to `dst` the way `fs::copy` does, while also writing the
+/// contents to `tee`.
+fn copy_with_tee(src: &Path, dst: &Path, tee: &mut dyn Write) -> io::Result<()> {
+    let mut reader = fs::File::open(src)?;
+    let permissions = reader.metadata()?.permissions();
+    let mut writer = Tee {
+        file: fs::File::create(dst)?,
+        tee: Some(tee),
+    };
+    io::copy(&mut reader, &mut writer)?;
+    writer.file.set_permissions(permissions)
+}
+
+/// Layers `data` (if any) over whatever `insert_data` inserts.
+fn with_data(insert_data: impl Fn(&mut JsonMap), data: Option<JsonMap>) -> impl Fn(&mut JsonMap) {
+    move |map| {
+        insert_data(map);
+        if let Some(data) = &data {
+            map.0.extend(data.0.clone());
         }
     }
 }
+
+fn read_template(src: &Path) -> Result<String, ProcessingError> {
+    let read_failed = |cause| ProcessingError::TemplateReadFailed {
+        src: src.to_owned(),
+        cause,
+    };
+    let bytes = fs::read(src).map_err(read_failed)?;
+    if is_binary(&bytes) {
+        return Err(ProcessingError::TemplateIsBinary {
+            path: src.to_owned(),
+        });
+    }
+    decode_text(src, bytes).map_err(read_failed)
+}
+
+/// Whether or not `bytes` look like the contents of a binary file, rather than
+/// text that [`decode_text`] can decode.
+fn is_binary(bytes: &[u8]) -> bool {
+    #[cfg(feature = "encoding_rs")]
+    {
+        // UTF-16 is full of NUL bytes, so leave it to decoding.
+        if let Some((encoding, _)) = encoding_rs::Encoding::for_bom(bytes) {
+            if encoding != encoding_rs::UTF_8 {
+                return false;
+            }
+        }
+    }
+    bytes.contains(&0) || std::str::from_utf8(bytes).is_err()
+}
+
+/// Reads a file as UTF-8, or as UTF-16 if it starts with a UTF-16 byte order
+/// mark and the `encoding_rs` feature is enabled.
+fn read_text(src: &Path) -> io::Result<String> {
+    decode_text(src, fs::read(src)?)
+}
+
+// `src` is only used for logging which encoding was detected.
+#[cfg_attr(not(feature = "encoding_rs"), allow(unused_variables))]
+fn decode_text(src: &Path, bytes: Vec<u8>) -> io::Result<String> {
+    #[cfg(feature = "encoding_rs")]
+    {
+        if let Some((encoding, _)) = encoding_rs::Encoding::for_bom(&bytes) {
+            if encoding != encoding_rs::UTF_8 {
+                log::info!("decoding {:?} as {}", src, encoding.name());
+                let (decoded, had_errors) = encoding.decode_with_bom_removal(&bytes);
+                return if had_errors {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("file isn't valid {}", encoding.name()),
+                    ))
+                } else {
+                    Ok(decoded.into_owned())
+                };
+            }
+        }
+    }
+    String::from_utf8(bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Splits a leading `@@ dest: path` line off of `template`, returning the path
+/// (if there was a directive) and the rest of the template.
+fn dest_directive(template: &str) -> (Option<&str>, &str) {
+    let (line, rest) = match template.find('\n') {
+        Some(end) => (&template[..end], &template[end + 1..]),
+        None => (template, ""),
+    };
+    match line
+        .trim_end_matches('\r')
+        .strip_prefix("@@")
+        .map(str::trim_start)
+        .and_then(|line| line.strip_prefix("dest:"))
+    {
+        Some(dst) => (Some(dst.trim()), rest),
+        None => (None, template),
+    }
+}
+
+fn source_metadata(metadata: &fs::Metadata) -> serde_json::Value {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|since_epoch| since_epoch.as_secs());
+    serde_json::json!({
+        "size": metadata.len(),
+        "modified": modified,
+        "is_executable": is_executable(metadata),
+    })
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt as _;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Gives `dst` the same permission bits as the file at `src`, except that the
+/// owner can always write to it, so that a read-only template doesn't keep its
+/// output from being regenerated.
+#[cfg(unix)]
+fn copy_mode(src: &Path, dst: &fs::File) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt as _;
+    let mode = fs::metadata(src)?.permissions().mode() | 0o200;
+    dst.set_permissions(fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn copy_mode(_src: &Path, _dst: &fs::File) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn source_mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt as _;
+    fs::metadata(path)
+        .ok()
+        .map(|metadata| metadata.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+fn source_mode(_path: &Path) -> Option<u32> {
+    None
+}
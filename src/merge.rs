@@ -0,0 +1,182 @@
+use crate::ProcessingError;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Component, Path},
+};
+
+/// The name of the manifest that [`Bicycle::process_merge`](crate::Bicycle::process_merge)
+/// keeps in the root of the destination, which records what was generated
+/// last time so that it can be used as the base of the next merge.
+pub const MANIFEST_FILE_NAME: &str = ".bicycle-manifest";
+
+/// What was generated for each file by the previous merge, keyed by its path
+/// relative to the root of the destination (using `/` as the separator).
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Manifest {
+    files: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    /// Reads the manifest in `dst`, which is empty if there isn't one yet.
+    pub(crate) fn load(dst: &Path) -> Result<Self, ProcessingError> {
+        let path = dst.join(MANIFEST_FILE_NAME);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(cause) => return Err(ProcessingError::ManifestReadFailed { path, cause }),
+        };
+        serde_json::from_slice(&bytes)
+            .map_err(|cause| ProcessingError::ManifestParseFailed { path, cause })
+    }
+
+    pub(crate) fn write(&self, dst: &Path) -> Result<(), ProcessingError> {
+        let path = dst.join(MANIFEST_FILE_NAME);
+        // Serializing a map of strings can't fail.
+        let mut json = serde_json::to_string_pretty(self).unwrap();
+        json.push('\n');
+        fs::write(&path, json).map_err(|cause| ProcessingError::ManifestWriteFailed { path, cause })
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&str> {
+        self.files.get(key).map(String::as_str)
+    }
+
+    pub(crate) fn insert(&mut self, key: String, generated: String) {
+        self.files.insert(key, generated);
+    }
+}
+
+/// Formats `path` (relative to the root of the destination) as a manifest
+/// key, or returns `None` if it isn't relative or isn't valid UTF-8.
+pub(crate) fn key(path: &Path) -> Option<String> {
+    let mut key = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => key.push(name.to_str()?),
+            Component::CurDir => (),
+            _ => return None,
+        }
+    }
+    Some(key.join("/"))
+}
+
+pub(crate) const CONFLICT_START: &str = "<<<<<<< current";
+pub(crate) const CONFLICT_SEPARATOR: &str = "=======";
+pub(crate) const CONFLICT_END: &str = ">>>>>>> generated";
+
+/// The outcome of a three-way merge.
+#[derive(Debug)]
+pub(crate) struct Merged {
+    pub(crate) text: String,
+    pub(crate) conflicts: bool,
+}
+
+/// Merges the changes from `base` to `current` with the changes from `base` to
+/// `generated`, line by line. Where both changed the same lines differently,
+/// both versions are kept between conflict markers, like `git merge` does.
+pub(crate) fn merge(base: &str, current: &str, generated: &str) -> Merged {
+    let base = base.split_inclusive('\n').collect::<Vec<_>>();
+    let current = current.split_inclusive('\n').collect::<Vec<_>>();
+    let generated = generated.split_inclusive('\n').collect::<Vec<_>>();
+    let in_current = matches(&base, &current);
+    let in_generated = matches(&base, &generated);
+    let mut merged = Merged {
+        text: String::new(),
+        conflicts: false,
+    };
+    let (mut i, mut j, mut k) = (0, 0, 0);
+    loop {
+        // The next base line that survived unchanged on both sides, which is
+        // where the two sides are back in sync.
+        let stable = (i..base.len()).find_map(|n| Some((n, in_current[n]?, in_generated[n]?)));
+        let (n, nj, nk) = stable.unwrap_or((base.len(), current.len(), generated.len()));
+        merged.chunk(&base[i..n], &current[j..nj], &generated[k..nk]);
+        if stable.is_none() {
+            return merged;
+        }
+        merged.text.push_str(base[n]);
+        (i, j, k) = (n + 1, nj + 1, nk + 1);
+    }
+}
+
+impl Merged {
+    /// Merges a stretch of lines where at least one side differs from the base.
+    fn chunk(&mut self, base: &[&str], current: &[&str], generated: &[&str]) {
+        if current == generated || generated == base {
+            self.text.extend(current.iter().copied());
+        } else if current == base {
+            self.text.extend(generated.iter().copied());
+        } else {
+            self.conflicts = true;
+            self.line(CONFLICT_START);
+            self.text.extend(current.iter().copied());
+            self.line(CONFLICT_SEPARATOR);
+            self.text.extend(generated.iter().copied());
+            self.line(CONFLICT_END);
+        }
+    }
+
+    /// Pushes a conflict marker on a line of its own, even if the text so far
+    /// doesn't end in a newline.
+    fn line(&mut self, marker: &str) {
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+        self.text.push_str(marker);
+        self.text.push('\n');
+    }
+}
+
+/// Finds the longest common subsequence of `a` and `b` using Myers' diff
+/// algorithm, returning the index in `b` matched by each line of `a`.
+fn matches(a: &[&str], b: &[&str]) -> Vec<Option<usize>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    // `v[k]` is the furthest `x` reached on diagonal `k = x - y`, offset so
+    // that negative diagonals can be indexed.
+    let index = |k: isize| (k + max) as usize;
+    let mut v = vec![0; 2 * max as usize + 2];
+    let mut trace = Vec::new();
+    'search: for d in 0..=max {
+        trace.push(v.clone());
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+    let mut matched = vec![None; a.len()];
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = v[index(prev_k)];
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            x -= 1;
+            y -= 1;
+            matched[x as usize] = Some(y as usize);
+        }
+        (x, y) = (prev_x, prev_y);
+    }
+    matched
+}
@@ -0,0 +1,181 @@
+use crate::{
+    context_stack::DirectoryContext, Action, BinaryTemplatePolicy, CollisionStrategy,
+    GitAttributesMode, LineEndings, OversizedTemplatePolicy, OverwritePolicy, ProcessingError,
+    SingleExtensionPolicy, TraverseConfig, DEFAULT_TEMPLATE_EXT,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Everything that affects how templates are processed, apart from their data
+/// and the helpers and hooks available to them.
+///
+/// Each field corresponds to a setter on [`Bicycle`](crate::Bicycle), which
+/// documents it in full. Since this can be (de)serialized, tools can load it
+/// from a config file and pass it to
+/// [`Bicycle::set_options`](crate::Bicycle::set_options) or
+/// [`Bicycle::process_with_options`](crate::Bicycle::process_with_options).
+/// Missing fields take
+/// their default values, and option enums use kebab-case names.
+///
+/// # Examples
+/// ```
+/// use bicycle::{CollisionStrategy, ProcessOptions, SingleExtensionPolicy};
+///
+/// let options: ProcessOptions = serde_json::from_str(r#"{
+///     "collision_strategy": "suffix",
+///     "single_extension": { "default-extension": "txt" },
+///     "dest_directives": true
+/// }"#).unwrap();
+/// assert_eq!(options, ProcessOptions {
+///     collision_strategy: CollisionStrategy::Suffix,
+///     single_extension: SingleExtensionPolicy::DefaultExtension("txt".to_owned()),
+///     dest_directives: true,
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProcessOptions {
+    /// See [`Bicycle::set_collision_strategy`](crate::Bicycle::set_collision_strategy).
+    pub collision_strategy: CollisionStrategy,
+    /// See [`Bicycle::set_single_extension_policy`](crate::Bicycle::set_single_extension_policy).
+    pub single_extension: SingleExtensionPolicy,
+    /// See [`Bicycle::set_template_names`](crate::Bicycle::set_template_names).
+    pub template_names: Vec<String>,
+    /// See [`Bicycle::set_sidecar_suffix`](crate::Bicycle::set_sidecar_suffix).
+    pub sidecar_suffix: Option<String>,
+    /// See [`Bicycle::set_directory_data_name`](crate::Bicycle::set_directory_data_name).
+    pub directory_data_name: Option<String>,
+    /// See [`Bicycle::set_source_metadata`](crate::Bicycle::set_source_metadata).
+    pub source_metadata: bool,
+    /// See [`Bicycle::set_destination_metadata`](crate::Bicycle::set_destination_metadata).
+    pub destination_metadata: bool,
+    /// See [`Bicycle::set_replace_conflicting_files`](crate::Bicycle::set_replace_conflicting_files).
+    pub replace_conflicting_files: bool,
+    /// See [`Bicycle::set_mark_generated`](crate::Bicycle::set_mark_generated).
+    pub mark_generated: Option<GitAttributesMode>,
+    /// See [`Bicycle::set_output_manifest`](crate::Bicycle::set_output_manifest).
+    pub output_manifest: Option<String>,
+    /// See [`Bicycle::set_dest_directives`](crate::Bicycle::set_dest_directives).
+    pub dest_directives: bool,
+    /// See `Bicycle::set_front_matter`. Enabling this without the `serde_yaml`
+    /// feature makes processing templates fail with
+    /// [`ProcessingError::FrontMatterUnsupported`](crate::ProcessingError::FrontMatterUnsupported).
+    pub front_matter: bool,
+    /// See [`Bicycle::set_max_template_bytes`](crate::Bicycle::set_max_template_bytes).
+    pub max_template_bytes: Option<(u64, OversizedTemplatePolicy)>,
+    /// See [`Bicycle::set_binary_template_policy`](crate::Bicycle::set_binary_template_policy).
+    pub binary_templates: BinaryTemplatePolicy,
+    /// See [`Bicycle::set_skip_sentinel`](crate::Bicycle::set_skip_sentinel).
+    pub skip_sentinel: Option<String>,
+    /// See [`Bicycle::set_skip_empty`](crate::Bicycle::set_skip_empty).
+    pub skip_empty: bool,
+    /// See [`Bicycle::set_include_nested_dst`](crate::Bicycle::set_include_nested_dst).
+    pub include_nested_dst: bool,
+    /// See [`Bicycle::set_lenient_paths`](crate::Bicycle::set_lenient_paths).
+    pub lenient_paths: bool,
+    /// See [`Bicycle::set_append_ext`](crate::Bicycle::set_append_ext). A
+    /// leading `.` is ignored.
+    pub append_ext: Option<String>,
+    /// See [`Bicycle::set_append_dedupe`](crate::Bicycle::set_append_dedupe).
+    pub append_dedupe: bool,
+    /// See [`Bicycle::set_max_concurrency`](crate::Bicycle::set_max_concurrency).
+    pub max_concurrency: Option<usize>,
+    /// See [`Bicycle::set_overwrite_policy`](crate::Bicycle::set_overwrite_policy).
+    pub overwrite_policy: OverwritePolicy,
+    /// See [`Bicycle::set_preserve_symlinks`](crate::Bicycle::set_preserve_symlinks).
+    pub preserve_symlinks: bool,
+    /// See [`Bicycle::set_line_endings`](crate::Bicycle::set_line_endings).
+    pub line_endings: LineEndings,
+}
+
+impl ProcessOptions {
+    pub(crate) fn traverse_config(&self) -> TraverseConfig<'_> {
+        TraverseConfig {
+            template_names: &self.template_names,
+            collisions: self.collision_strategy,
+            single_extension: &self.single_extension,
+            include_nested_dst: self.include_nested_dst,
+            append_ext: self
+                .append_ext
+                .as_deref()
+                .map(|ext| ext.trim_start_matches('.')),
+            preserve_symlinks: self.preserve_symlinks,
+            ..TraverseConfig::new(DEFAULT_TEMPLATE_EXT)
+        }
+    }
+
+    fn sidecar_path(&self, template: &Path) -> Option<PathBuf> {
+        self.sidecar_suffix.as_ref().map(|suffix| {
+            let mut path = template.as_os_str().to_owned();
+            path.push(suffix);
+            path.into()
+        })
+    }
+
+    pub(crate) fn sidecar_data(
+        &self,
+        template: &Path,
+    ) -> Result<Option<serde_json::Map<String, serde_json::Value>>, ProcessingError> {
+        let path = match self.sidecar_path(template) {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(cause) => return Err(ProcessingError::SidecarReadFailed { path, cause }),
+        };
+        log::info!("using sidecar data from {:?}", path);
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|cause| ProcessingError::SidecarParseFailed { path, cause })
+    }
+
+    /// Whether or not `action` copies a sidecar data file, which shouldn't be
+    /// emitted alongside the output of the template it belongs to.
+    fn is_sidecar(&self, action: &Action) -> bool {
+        match (&self.sidecar_suffix, action.src().to_str()) {
+            (Some(suffix), Some(src)) if action.tag().copy_file() => src
+                .strip_suffix(suffix.as_str())
+                .filter(|template| self.traverse_config().is_template(Path::new(template)))
+                .is_some(),
+            _ => false,
+        }
+    }
+
+    /// Whether or not `action` copies a directory data file.
+    fn is_directory_data(&self, action: &Action) -> bool {
+        match &self.directory_data_name {
+            Some(name) if action.tag().copy_file() => action
+                .src()
+                .file_name()
+                .is_some_and(|file| file == name.as_str()),
+            _ => false,
+        }
+    }
+
+    /// Whether or not to split front matter off of the template at `src`,
+    /// which fails if it's enabled without the `serde_yaml` feature.
+    pub(crate) fn use_front_matter(&self, src: &Path) -> Result<bool, ProcessingError> {
+        if self.front_matter && cfg!(not(feature = "serde_yaml")) {
+            return Err(ProcessingError::FrontMatterUnsupported {
+                path: src.to_owned(),
+            });
+        }
+        Ok(self.front_matter)
+    }
+
+    /// Whether or not `action` copies a data file that's used for rendering
+    /// instead of being part of the output.
+    pub(crate) fn is_data_file(&self, action: &Action) -> bool {
+        self.is_sidecar(action) || self.is_directory_data(action)
+    }
+
+    pub(crate) fn directory_context<'a>(&'a self, src: &'a Path) -> DirectoryContext<'a> {
+        DirectoryContext::new(src, self.directory_data_name.as_deref())
+    }
+}
@@ -0,0 +1,151 @@
+use crate::{merge, Action, ProcessingError};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Everything that processing wrote, as recorded by
+/// [`Bicycle::set_output_manifest`](crate::Bicycle::set_output_manifest).
+/// Paths are relative to the root of the destination, which is where the
+/// manifest itself is written, and use `/` as the separator.
+///
+/// ```json
+/// {
+///   "created_directories": ["", "src"],
+///   "created_files": ["src/main.rs"],
+///   "overwritten_files": ["Cargo.toml"]
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputManifest {
+    /// Directories that didn't exist before processing, where `""` is the root
+    /// of the destination.
+    pub created_directories: Vec<String>,
+    /// Files (and symlinks) that didn't exist before processing.
+    pub created_files: Vec<String>,
+    /// Files (and symlinks) that were replaced or appended to.
+    pub overwritten_files: Vec<String>,
+}
+
+impl OutputManifest {
+    /// Records the outcome of processing into `dst`, given the destinations
+    /// that already `existed` beforehand and the actions that `completed`.
+    pub(crate) fn record<'a>(
+        dst: &Path,
+        existed: &HashSet<PathBuf>,
+        completed: impl IntoIterator<Item = &'a Action>,
+    ) -> Self {
+        let mut manifest = Self::default();
+        for action in completed {
+            let key = match action.dst().strip_prefix(dst).ok().and_then(merge::key) {
+                Some(key) => key,
+                None => {
+                    log::warn!(
+                        "{:?} can't be recorded in the output manifest",
+                        action.dst()
+                    );
+                    continue;
+                }
+            };
+            let existed = existed.contains(action.dst());
+            if action.tag().create_directory() {
+                if !existed {
+                    manifest.created_directories.push(key);
+                }
+            } else if !action.tag().delete() {
+                if existed {
+                    manifest.overwritten_files.push(key);
+                } else {
+                    manifest.created_files.push(key);
+                }
+            }
+        }
+        manifest
+    }
+
+    /// Reads the manifest at `path`. Paths that could point outside of the
+    /// destination, like `../x` or `/etc/x`, fail with a
+    /// [`ProcessingError::ManifestParseFailed`], since the manifest must have
+    /// been edited or corrupted.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ProcessingError> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|cause| ProcessingError::ManifestReadFailed {
+            path: path.to_owned(),
+            cause,
+        })?;
+        serde_json::from_slice(&bytes)
+            .and_then(|manifest: Self| {
+                let keys = manifest
+                    .created_directories
+                    .iter()
+                    .chain(&manifest.created_files)
+                    .chain(&manifest.overwritten_files);
+                for key in keys {
+                    if merge::key(Path::new(key)).is_none() {
+                        return Err(serde::de::Error::custom(format!(
+                            "{:?} isn't a path inside of the destination",
+                            key
+                        )));
+                    }
+                }
+                Ok(manifest)
+            })
+            .map_err(|cause| ProcessingError::ManifestParseFailed {
+                path: path.to_owned(),
+                cause,
+            })
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> Result<(), ProcessingError> {
+        let mut json = serde_json::to_string_pretty(self).unwrap();
+        json.push('\n');
+        fs::write(path, json).map_err(|cause| ProcessingError::ManifestWriteFailed {
+            path: path.to_owned(),
+            cause,
+        })
+    }
+}
+
+/// Deletes what the manifest at `path` says was created, and then the manifest
+/// itself. Directories are only deleted once they're empty, and anything
+/// that's already gone is skipped.
+pub(crate) fn remove(path: &Path) -> Result<(), ProcessingError> {
+    let manifest = OutputManifest::load(path)?;
+    let root = path.parent().unwrap_or_else(|| Path::new(""));
+    let deletion_failed =
+        |dst: PathBuf| move |cause| ProcessingError::DeletionFailed { dst, cause };
+    for key in &manifest.created_files {
+        let file = root.join(key);
+        log::info!("removing {:?}", file);
+        ignore_missing(fs::remove_file(&file)).map_err(deletion_failed(file))?;
+    }
+    fs::remove_file(path).map_err(deletion_failed(path.to_owned()))?;
+    let mut dirs = manifest.created_directories.iter().collect::<Vec<_>>();
+    // Children sort after their parents, so this removes the deepest first.
+    dirs.sort_unstable_by(|a, b| b.cmp(a));
+    for key in dirs {
+        let dir = root.join(key);
+        let empty = match fs::read_dir(&dir) {
+            Ok(mut entries) => entries.next().is_none(),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(cause) => return Err(deletion_failed(dir)(cause)),
+        };
+        if empty {
+            log::info!("removing empty directory {:?}", dir);
+            fs::remove_dir(&dir).map_err(deletion_failed(dir))?;
+        } else {
+            log::info!("keeping {:?}, since it isn't empty", dir);
+        }
+    }
+    Ok(())
+}
+
+fn ignore_missing(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
@@ -0,0 +1,61 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+/// The number of threads to use when no limit was set, which is however many
+/// the platform says can run in parallel.
+pub(crate) fn default_concurrency() -> usize {
+    thread::available_parallelism().map_or(1, NonZeroUsize::get)
+}
+
+/// Runs `work` for every index in `groups` using up to `threads` threads. The
+/// groups are run concurrently, but the indices within each group are run one
+/// after another, in order.
+///
+/// `finished` is called on the current thread with each result as it comes
+/// in, so it doesn't need to be thread-safe. Once anything fails, no more work
+/// is started, but work that's already running is allowed to finish.
+pub(crate) fn run<T: Send, E: Send>(
+    groups: &[Vec<usize>],
+    threads: usize,
+    work: impl Fn(usize) -> Result<T, E> + Sync,
+    mut finished: impl FnMut(usize, Result<T, E>),
+) {
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let (tx, rx) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1).min(groups.len()) {
+            let tx = tx.clone();
+            let (next, failed, work) = (&next, &failed, &work);
+            scope.spawn(move || {
+                while !failed.load(Ordering::Relaxed) {
+                    let group = match groups.get(next.fetch_add(1, Ordering::Relaxed)) {
+                        Some(group) => group,
+                        None => break,
+                    };
+                    for &index in group {
+                        let result = work(index);
+                        let stop = result.is_err();
+                        if stop {
+                            failed.store(true, Ordering::Relaxed);
+                        }
+                        // The receiver only hangs up if `finished` panicked.
+                        if tx.send((index, result)).is_err() || stop {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+        drop(tx);
+        for (index, result) in rx {
+            finished(index, result);
+        }
+    });
+}
@@ -0,0 +1,31 @@
+/// The default sanitizer for [`Bicycle::set_path_sanitizer`](crate::Bicycle::set_path_sanitizer),
+/// which replaces characters that aren't allowed in file names on the current
+/// platform with `_`.
+///
+/// That's `/` and NUL everywhere, as well as `\`, `<`, `>`, `:`, `"`, `|`,
+/// `?`, `*` and control characters on Windows. Values made up entirely of
+/// `.`s are replaced too, since `..` would otherwise escape the destination.
+///
+/// # Examples
+/// ```
+/// assert_eq!(bicycle::sanitize_path_segment("a/b"), "a_b");
+/// assert_eq!(bicycle::sanitize_path_segment(".."), "__");
+/// assert_eq!(bicycle::sanitize_path_segment("v1.2"), "v1.2");
+/// ```
+pub fn sanitize_path_segment(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(|c| c == '.') {
+        return "_".repeat(value.len());
+    }
+    value
+        .chars()
+        .map(|c| if is_illegal(c) { '_' } else { c })
+        .collect()
+}
+
+fn is_illegal(c: char) -> bool {
+    match c {
+        '/' | '\0' => true,
+        '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*' => cfg!(windows),
+        c => cfg!(windows) && c.is_control(),
+    }
+}
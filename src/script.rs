@@ -0,0 +1,25 @@
+use rhai::{module_resolvers::DummyModuleResolver, Engine};
+
+/// The maximum number of operations a script helper can perform in a single
+/// call, which keeps a runaway loop from hanging rendering.
+pub const SCRIPT_MAX_OPERATIONS: u64 = 1_000_000;
+
+/// A rhai engine for script helpers, which can't reach anything outside of the
+/// values passed to them. The standard rhai engine has no filesystem or network
+/// access, but can still import modules from files and be used to build
+/// unbounded values, so those are ruled out too.
+pub(crate) fn sandboxed_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .disable_symbol("eval")
+        .set_max_operations(SCRIPT_MAX_OPERATIONS)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(1 << 20)
+        .set_max_array_size(10_000)
+        .set_max_map_size(10_000)
+        .on_print(|message| log::info!("script helper printed {:?}", message))
+        .on_debug(|message, _, _| log::debug!("script helper printed {:?}", message));
+    engine
+}
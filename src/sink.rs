@@ -0,0 +1,182 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io,
+    path::{Path, PathBuf},
+};
+
+/// A destination for the output of [`Bicycle::process_into`](crate::Bicycle::process_into)
+/// that isn't a directory on the filesystem, such as an archive.
+///
+/// All paths passed to a sink are relative to the root of the output, and a
+/// directory is always added before anything inside of it.
+pub trait Sink {
+    /// Adds a directory entry.
+    fn create_dir(&mut self, path: &Path) -> io::Result<()>;
+
+    /// Adds a file entry. `mode` contains the Unix permission bits of the
+    /// source file, which is only available when running on Unix.
+    fn write_file(&mut self, path: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()>;
+}
+
+impl<S: Sink + ?Sized> Sink for &mut S {
+    fn create_dir(&mut self, path: &Path) -> io::Result<()> {
+        (**self).create_dir(path)
+    }
+
+    fn write_file(&mut self, path: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()> {
+        (**self).write_file(path, contents, mode)
+    }
+}
+
+/// Keeps output in memory, which is handy for testing templates without
+/// touching the filesystem.
+///
+/// # Examples
+/// ```no_run
+/// let mut sink = bicycle::MemorySink::default();
+/// bicycle::Bicycle::default()
+///     .process_into("templates", &mut sink, |map| map.insert("name", "shinji"))
+///     .unwrap();
+/// for (path, contents) in sink.files() {
+///     println!("{:?}: {} bytes", path, contents.len());
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MemorySink {
+    dirs: BTreeSet<PathBuf>,
+    files: BTreeMap<PathBuf, Vec<u8>>,
+}
+
+impl MemorySink {
+    /// The directories that were added, not including the root.
+    pub fn dirs(&self) -> &BTreeSet<PathBuf> {
+        &self.dirs
+    }
+
+    /// The contents of each file that was added. Permission bits aren't kept.
+    pub fn files(&self) -> &BTreeMap<PathBuf, Vec<u8>> {
+        &self.files
+    }
+
+    /// Returns the contents of each file that was added.
+    pub fn into_files(self) -> BTreeMap<PathBuf, Vec<u8>> {
+        self.files
+    }
+}
+
+impl Sink for MemorySink {
+    fn create_dir(&mut self, path: &Path) -> io::Result<()> {
+        self.dirs.insert(path.to_owned());
+        Ok(())
+    }
+
+    fn write_file(&mut self, path: &Path, contents: &[u8], _mode: Option<u32>) -> io::Result<()> {
+        self.files.insert(path.to_owned(), contents.to_vec());
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "zip", feature = "tar"))]
+const DEFAULT_DIR_MODE: u32 = 0o755;
+#[cfg(any(feature = "zip", feature = "tar"))]
+const DEFAULT_FILE_MODE: u32 = 0o644;
+
+/// Archive entry names always use `/` as the separator, regardless of platform.
+#[cfg(any(feature = "zip", feature = "tar"))]
+fn entry_name(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Writes output into a zip archive. Requires the `zip` feature.
+#[cfg(feature = "zip")]
+pub struct ZipSink<W: io::Write + io::Seek> {
+    writer: zip::ZipWriter<W>,
+}
+
+#[cfg(feature = "zip")]
+impl<W: io::Write + io::Seek> ZipSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: zip::ZipWriter::new(writer),
+        }
+    }
+
+    /// Writes the central directory, which is required for the archive to be
+    /// readable, and returns the underlying writer.
+    pub fn finish(self) -> io::Result<W> {
+        self.writer.finish().map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "zip")]
+impl<W: io::Write + io::Seek> Sink for ZipSink<W> {
+    fn create_dir(&mut self, path: &Path) -> io::Result<()> {
+        let options = zip::write::SimpleFileOptions::default().unix_permissions(DEFAULT_DIR_MODE);
+        self.writer
+            .add_directory(entry_name(path), options)
+            .map_err(Into::into)
+    }
+
+    fn write_file(&mut self, path: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()> {
+        let options = zip::write::SimpleFileOptions::default()
+            .unix_permissions(mode.unwrap_or(DEFAULT_FILE_MODE));
+        self.writer.start_file(entry_name(path), options)?;
+        io::Write::write_all(&mut self.writer, contents)
+    }
+}
+
+/// Writes output into a tar archive. Requires the `tar` feature.
+///
+/// For a `.tar.gz`, wrap the writer in a gzip encoder before passing it in.
+#[cfg(feature = "tar")]
+pub struct TarSink<W: io::Write> {
+    builder: tar::Builder<W>,
+}
+
+#[cfg(feature = "tar")]
+impl<W: io::Write> TarSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            builder: tar::Builder::new(writer),
+        }
+    }
+
+    /// Writes the end-of-archive marker and returns the underlying writer.
+    pub fn finish(self) -> io::Result<W> {
+        self.builder.into_inner()
+    }
+
+    fn append(
+        &mut self,
+        path: &Path,
+        entry_type: tar::EntryType,
+        contents: &[u8],
+        mode: u32,
+    ) -> io::Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_size(contents.len() as u64);
+        header.set_mode(mode);
+        self.builder
+            .append_data(&mut header, entry_name(path), contents)
+    }
+}
+
+#[cfg(feature = "tar")]
+impl<W: io::Write> Sink for TarSink<W> {
+    fn create_dir(&mut self, path: &Path) -> io::Result<()> {
+        self.append(path, tar::EntryType::Directory, &[], DEFAULT_DIR_MODE)
+    }
+
+    fn write_file(&mut self, path: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()> {
+        self.append(
+            path,
+            tar::EntryType::Regular,
+            contents,
+            mode.unwrap_or(DEFAULT_FILE_MODE),
+        )
+    }
+}
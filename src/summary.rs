@@ -0,0 +1,118 @@
+use crate::{Action, Tag};
+use std::fmt::{self, Display};
+
+/// How many actions of each kind were executed, for printing a summary once
+/// processing is done. Returned by
+/// [`Bicycle::process_summarized`](crate::Bicycle::process_summarized), or
+/// built from the actions that
+/// [`Bicycle::process_actions_tracked`](crate::Bicycle::process_actions_tracked)
+/// completed using [`ActionSummary::tally`].
+///
+/// # Examples
+/// ```
+/// use bicycle::ActionSummary;
+///
+/// let summary = ActionSummary {
+///     directories_created: 12,
+///     templates_written: 30,
+///     files_copied: 5,
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     summary.to_string(),
+///     "Created 12 directories, wrote 30 templates, copied 5 files."
+/// );
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ActionSummary {
+    /// [`Tag::CreateDirectory`] actions.
+    pub directories_created: usize,
+    /// [`Tag::WriteTemplate`] actions.
+    pub templates_written: usize,
+    /// [`Tag::AppendTemplate`] actions.
+    pub templates_appended: usize,
+    /// [`Tag::CopyFile`] actions.
+    pub files_copied: usize,
+    /// [`Tag::Symlink`] actions.
+    pub symlinks_created: usize,
+    /// [`Tag::Delete`] actions.
+    pub deleted: usize,
+}
+
+impl ActionSummary {
+    /// Counts `actions` by their tags.
+    pub fn tally<'a>(actions: impl IntoIterator<Item = &'a Action>) -> Self {
+        let mut summary = Self::default();
+        for action in actions {
+            *summary.count_mut(action.tag()) += 1;
+        }
+        summary
+    }
+
+    /// The number of actions with `tag`.
+    pub fn count(&self, tag: Tag) -> usize {
+        let mut summary = *self;
+        *summary.count_mut(tag)
+    }
+
+    /// The total number of actions.
+    pub fn total(&self) -> usize {
+        self.directories_created
+            + self.templates_written
+            + self.templates_appended
+            + self.files_copied
+            + self.symlinks_created
+            + self.deleted
+    }
+
+    fn count_mut(&mut self, tag: Tag) -> &mut usize {
+        match tag {
+            Tag::CreateDirectory => &mut self.directories_created,
+            Tag::WriteTemplate => &mut self.templates_written,
+            Tag::AppendTemplate => &mut self.templates_appended,
+            Tag::CopyFile => &mut self.files_copied,
+            Tag::Symlink => &mut self.symlinks_created,
+            Tag::Delete => &mut self.deleted,
+        }
+    }
+}
+
+/// Lists the nonzero counts as a sentence, like "Created 2 directories, wrote
+/// 1 template."
+impl Display for ActionSummary {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        let parts = [
+            (
+                self.directories_created,
+                "created",
+                "directory",
+                "directories",
+            ),
+            (self.templates_written, "wrote", "template", "templates"),
+            (self.templates_appended, "appended", "template", "templates"),
+            (self.files_copied, "copied", "file", "files"),
+            (self.symlinks_created, "linked", "symlink", "symlinks"),
+            (self.deleted, "deleted", "path", "paths"),
+        ];
+        let mut first = true;
+        for (count, verb, singular, plural) in parts.iter().filter(|part| part.0 > 0) {
+            if first {
+                // Capitalize the first verb, which is always ASCII.
+                write!(fmtr, "{}{}", verb[..1].to_uppercase(), &verb[1..])?;
+                first = false;
+            } else {
+                write!(fmtr, ", {}", verb)?;
+            }
+            write!(
+                fmtr,
+                " {} {}",
+                count,
+                if *count == 1 { singular } else { plural }
+            )?;
+        }
+        if first {
+            write!(fmtr, "Nothing to do")?;
+        }
+        write!(fmtr, ".")
+    }
+}
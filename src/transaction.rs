@@ -0,0 +1,222 @@
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Something done to the filesystem that can be undone.
+#[derive(Debug)]
+enum Change {
+    CreatedDirectory(PathBuf),
+    CreatedFile(PathBuf),
+    /// A file that was about to be overwritten (or replaced by a directory),
+    /// backed up in memory.
+    Overwrote {
+        path: PathBuf,
+        contents: Vec<u8>,
+        permissions: fs::Permissions,
+    },
+    /// A symlink that was about to be written through, along with its target.
+    Symlink {
+        path: PathBuf,
+        target: PathBuf,
+    },
+    /// Something that was deleted by moving it aside, so it can be moved back.
+    Deleted {
+        path: PathBuf,
+        backup: PathBuf,
+    },
+}
+
+/// Records what processing is about to change, so that it can be undone if
+/// processing fails partway through.
+#[derive(Debug, Default)]
+pub(crate) struct Transaction {
+    changes: Vec<Change>,
+}
+
+impl Transaction {
+    /// Records the directories that creating `dir` would create. If
+    /// `replace_files` is set, a file in the way is backed up too, since it'd
+    /// be replaced by a directory.
+    pub(crate) fn record_directory(&mut self, dir: &Path, replace_files: bool) -> io::Result<()> {
+        let mut missing = Vec::new();
+        for ancestor in dir
+            .ancestors()
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+        {
+            match fs::metadata(ancestor) {
+                Ok(metadata) if metadata.is_dir() => break,
+                Ok(_) => {
+                    if replace_files {
+                        self.record_overwrite(ancestor)?;
+                        missing.push(ancestor);
+                    }
+                    break;
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => missing.push(ancestor),
+                Err(err) => return Err(err),
+            }
+        }
+        self.changes.extend(
+            missing
+                .into_iter()
+                .rev()
+                .map(|dir| Change::CreatedDirectory(dir.to_owned())),
+        );
+        Ok(())
+    }
+
+    /// Records a file that's about to be written, backing up its current
+    /// contents if it already exists.
+    pub(crate) fn record_file(&mut self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            self.record_directory(parent, false)?;
+        }
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => self.record_symlink(path),
+            Ok(metadata) if metadata.is_file() => self.record_overwrite(path),
+            // Writing over a directory fails, so there's nothing to undo.
+            Ok(_) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                self.changes.push(Change::CreatedFile(path.to_owned()));
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Writing to a symlink writes to whatever it points to instead, creating
+    /// it if it's missing, so that's recorded along with the symlink itself.
+    fn record_symlink(&mut self, path: &Path) -> io::Result<()> {
+        let target = fs::read_link(path)?;
+        match fs::canonicalize(path) {
+            Ok(resolved) => self.record_file(&resolved)?,
+            // Dangling symlinks can still be written through, but never loop.
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                let parent = path.parent().unwrap_or_else(|| Path::new(""));
+                self.record_file(&parent.join(&target))?;
+            }
+            // Writing through something like a symlink loop fails.
+            Err(_) => (),
+        }
+        self.changes.push(Change::Symlink {
+            path: path.to_owned(),
+            target,
+        });
+        Ok(())
+    }
+
+    fn record_overwrite(&mut self, path: &Path) -> io::Result<()> {
+        let contents = fs::read(path)?;
+        let permissions = fs::metadata(path)?.permissions();
+        self.changes.push(Change::Overwrote {
+            path: path.to_owned(),
+            contents,
+            permissions,
+        });
+        Ok(())
+    }
+
+    /// Deletes `path` by moving it aside, so that it's only really deleted
+    /// once the transaction is committed.
+    pub(crate) fn delete(&mut self, path: &Path) -> io::Result<()> {
+        let backup = backup_path(path);
+        fs::rename(path, &backup)?;
+        self.changes.push(Change::Deleted {
+            path: path.to_owned(),
+            backup,
+        });
+        Ok(())
+    }
+
+    /// Finishes deleting anything that was deleted.
+    pub(crate) fn commit(self) {
+        for change in self.changes {
+            if let Change::Deleted { backup, .. } = change {
+                if let Err(err) = remove(&backup) {
+                    log::warn!("failed to remove deleted path {:?}: {}", backup, err);
+                }
+            }
+        }
+    }
+
+    /// Undoes every recorded change, most recent first. This is best-effort:
+    /// failures are logged, and everything else is still undone.
+    pub(crate) fn roll_back(self) {
+        for change in self.changes.into_iter().rev() {
+            log::info!("rolling back {:?}", change);
+            let (path, result) = match change {
+                Change::CreatedDirectory(path) => {
+                    let result = fs::remove_dir(&path);
+                    (path, result)
+                }
+                Change::CreatedFile(path) => {
+                    let result = fs::remove_file(&path);
+                    (path, result)
+                }
+                Change::Overwrote {
+                    path,
+                    contents,
+                    permissions,
+                } => {
+                    // Removing it first means its new permissions, which could
+                    // be read-only, don't get in the way.
+                    let result = remove(&path)
+                        .or_else(|err| match err.kind() {
+                            io::ErrorKind::NotFound => Ok(()),
+                            _ => Err(err),
+                        })
+                        .and_then(|()| fs::write(&path, contents))
+                        .and_then(|()| fs::set_permissions(&path, permissions));
+                    (path, result)
+                }
+                Change::Symlink { path, target } => {
+                    // Windows needs to know whether the target is a directory.
+                    let resolved = path.parent().map(|parent| parent.join(&target));
+                    let result = remove(&path)
+                        .or_else(|err| match err.kind() {
+                            io::ErrorKind::NotFound => Ok(()),
+                            _ => Err(err),
+                        })
+                        .and_then(|()| {
+                            crate::symlink(&target, resolved.as_deref().unwrap_or(&path), &path)
+                        });
+                    (path, result)
+                }
+                Change::Deleted { path, backup } => {
+                    let result = fs::rename(&backup, &path);
+                    (path, result)
+                }
+            };
+            match result {
+                // The action was skipped, so there's nothing to undo.
+                Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                Err(err) => log::warn!("failed to roll back {:?}: {}", path, err),
+                Ok(()) => (),
+            }
+        }
+    }
+}
+
+/// Finds an unused path next to `path` to move it to.
+fn backup_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default();
+    (0..)
+        .map(|n| {
+            let mut backup = OsString::from(".");
+            backup.push(name);
+            backup.push(format!(".bicycle-backup{}", n));
+            path.with_file_name(backup)
+        })
+        .find(|backup| fs::symlink_metadata(backup).is_err())
+        .unwrap()
+}
+
+pub(crate) fn remove(path: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
@@ -1,13 +1,18 @@
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, HashSet, VecDeque},
+    convert::Infallible,
     error::Error as StdError,
-    fmt::{Debug, Display},
+    ffi::OsStr,
+    fmt::{self, Debug, Display},
     fs, io,
+    marker::PhantomData,
     path::{Path, PathBuf},
+    time::SystemTime,
 };
 use thiserror::Error;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Tag {
     /// Specifies to create a new directory at `dst`.
     CreateDirectory,
@@ -15,6 +20,18 @@ pub enum Tag {
     CopyFile,
     /// Specifies to render the template at `src` to `dst`.
     WriteTemplate,
+    /// Specifies to render the template at `src` and append the result to
+    /// `dst`, which is created if it doesn't exist. Only produced by traversal
+    /// when [`Bicycle::set_append_ext`](crate::Bicycle::set_append_ext) is used.
+    AppendTemplate,
+    /// Specifies to delete the file or directory at `dst`, which has no `src`.
+    /// Only produced by [`Bicycle::sync_actions`](crate::Bicycle::sync_actions).
+    Delete,
+    /// Specifies to create a symlink at `dst` with the same target as the
+    /// symlink at `src`. Only produced by traversal when
+    /// [`Bicycle::set_preserve_symlinks`](crate::Bicycle::set_preserve_symlinks)
+    /// is used.
+    Symlink,
 }
 
 impl Tag {
@@ -30,8 +47,38 @@ impl Tag {
         matches!(self, Self::WriteTemplate)
     }
 
+    pub fn append_template(&self) -> bool {
+        matches!(self, Self::AppendTemplate)
+    }
+
+    pub fn delete(&self) -> bool {
+        matches!(self, Self::Delete)
+    }
+
+    pub fn symlink(&self) -> bool {
+        matches!(self, Self::Symlink)
+    }
+
+    /// Whether or not the source is rendered as a template.
+    pub(crate) fn renders(&self) -> bool {
+        self.write_template() || self.append_template()
+    }
+
     fn strip_extension(&self) -> bool {
-        self.write_template()
+        self.renders()
+    }
+}
+
+impl Display for Tag {
+    fn fmt(&self, fmtr: &mut fmt::Formatter) -> fmt::Result {
+        fmtr.pad(match self {
+            Self::CreateDirectory => "create directory",
+            Self::CopyFile => "copy",
+            Self::WriteTemplate => "render template",
+            Self::AppendTemplate => "append template",
+            Self::Delete => "delete",
+            Self::Symlink => "symlink",
+        })
     }
 }
 
@@ -51,10 +98,47 @@ impl Action {
         tag: Tag,
     ) -> Result<Self, E> {
         let src = src.into();
-        let dst = append_path(dst, &src, tag.strip_extension());
+        let ext = src
+            .extension()
+            .filter(|_| tag.strip_extension())
+            .map(ToOwned::to_owned);
+        Self::build(src, dst, transform_dst, tag, ext.as_deref())
+    }
+
+    /// Builds an action whose `dst` is `dst` joined with the file name of
+    /// `src`, with `strip_ext` removed from the end if given.
+    fn build<E: Debug + Display + StdError>(
+        src: impl Into<PathBuf>,
+        dst: impl AsRef<Path>,
+        transform_dst: impl Fn(&Path) -> Result<PathBuf, E>,
+        tag: Tag,
+        strip_ext: Option<&OsStr>,
+    ) -> Result<Self, E> {
+        let src = src.into();
+        // A directory's `dst` already names the directory itself.
+        let dst = if tag.create_directory() {
+            dst.as_ref().to_owned()
+        } else {
+            append_path(dst, &src, strip_ext)
+        };
+        Self::with_dst(src, dst, transform_dst, tag)
+    }
+
+    /// Builds an action with an exact (untransformed) `dst`, rather than one
+    /// derived from `src`.
+    fn with_dst<E: Debug + Display + StdError>(
+        src: PathBuf,
+        dst: PathBuf,
+        transform_dst: impl Fn(&Path) -> Result<PathBuf, E>,
+        tag: Tag,
+    ) -> Result<Self, E> {
         let transformed_dst = transform_dst(&dst)?;
         log::info!("transformed {:?} into {:?}", dst, transformed_dst);
-        Ok(Self { src, dst, tag })
+        Ok(Self {
+            src,
+            dst: transformed_dst,
+            tag,
+        })
     }
 
     pub fn detect<E: Debug + Display + StdError>(
@@ -73,7 +157,40 @@ impl Action {
                 .unwrap_or_else(|| Tag::CopyFile)
         };
         log::info!("detected tag {:?} for path {:?}", tag, src);
-        Self::new(src, dst, transform_dst, tag)
+        let strip_ext = template_ext
+            .map(OsStr::new)
+            .filter(|_| tag.strip_extension());
+        Self::build(src, dst, transform_dst, tag, strip_ext)
+    }
+
+    /// Builds an action from its exact parts, for tools that plan their own
+    /// actions rather than traversing. Unlike [`Action::new`], `dst` is used
+    /// as-is, without being joined with the file name of `src` or transformed.
+    ///
+    /// For [`Tag::CreateDirectory`] and [`Tag::Delete`], `src` is ignored.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use bicycle::{Action, Bicycle, Tag};
+    ///
+    /// let action = Action::from_parts("templates/main.rs.hbs", "out/src/main.rs", Tag::WriteTemplate);
+    /// Bicycle::default().process_action(&action, |_| ()).unwrap();
+    /// ```
+    pub fn from_parts(src: impl Into<PathBuf>, dst: impl Into<PathBuf>, tag: Tag) -> Self {
+        Self {
+            src: src.into(),
+            dst: dst.into(),
+            tag,
+        }
+    }
+
+    /// An action that deletes `dst`.
+    fn deletion(dst: PathBuf) -> Self {
+        Self {
+            src: PathBuf::new(),
+            dst,
+            tag: Tag::Delete,
+        }
     }
 
     pub fn push_onto(self, vec: &mut VecDeque<Self>) {
@@ -94,28 +211,180 @@ impl Action {
         &self.dst
     }
 
+    pub(crate) fn set_dst(&mut self, dst: PathBuf) {
+        self.dst = dst;
+    }
+
+    pub(crate) fn set_tag(&mut self, tag: Tag) {
+        self.tag = tag;
+    }
+
     pub fn tag(&self) -> Tag {
         self.tag
     }
+
+    /// A human-readable summary of this action, i.e.
+    /// "render template foo.rs.hbs -> foo.rs". Directory creation and deletion
+    /// only mention the destination.
+    pub fn describe(&self) -> String {
+        if self.tag.create_directory() || self.tag.delete() {
+            format!("{} {}", self.tag, self.dst.display())
+        } else {
+            format!(
+                "{} {} -> {}",
+                self.tag,
+                self.src.display(),
+                self.dst.display()
+            )
+        }
+    }
 }
 
-fn append_path(base: impl AsRef<Path>, other: &Path, strip_extension: bool) -> PathBuf {
-    let tail = if strip_extension {
-        other.file_stem().unwrap()
-    } else {
-        other.file_name().unwrap()
+fn append_path(base: impl AsRef<Path>, other: &Path, strip_ext: Option<&OsStr>) -> PathBuf {
+    let name = other.file_name().unwrap();
+    let tail = match strip_ext {
+        Some(ext) => strip_template_ext(name, ext),
+        None => name,
     };
     let base = base.as_ref();
     let appended = base.join(tail);
     log::debug!(
-        "appended tail {:?} to base {:?} (strip extension set to {:?})",
+        "appended tail {:?} to base {:?} (stripped extension {:?})",
         tail,
         base,
-        strip_extension
+        strip_ext
     );
     appended
 }
 
+/// Removes `.{ext}` from the end of the file name `name`, as long as that
+/// leaves something behind. Only that exact suffix is removed, so `.env.hbs`
+/// becomes `.env` and `config.toml.hbs` becomes `config.toml`.
+fn strip_template_ext<'a>(name: &'a OsStr, ext: &OsStr) -> &'a OsStr {
+    match (name.to_str(), ext.to_str()) {
+        (Some(name_str), Some(ext)) => name_str
+            .strip_suffix(ext)
+            .and_then(|rest| rest.strip_suffix('.'))
+            .filter(|rest| !rest.is_empty())
+            .map_or(name, OsStr::new),
+        // Non-UTF-8 names can't be sliced, so fall back to the usual rules.
+        _ => Path::new(name).file_stem().unwrap_or(name),
+    }
+}
+
+/// Specifies what to do when multiple files in a traversal would be written to
+/// the same destination path.
+///
+/// Only file destinations are considered; multiple directories resolving to the
+/// same destination simply have their contents merged, and templates appending
+/// to the same destination are all appended. Collisions are only
+/// detected between actions within the same traversal, so files that already
+/// exist at the destination don't count.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CollisionStrategy {
+    /// The default setting. Emits all actions as-is, so whichever is processed
+    /// last wins.
+    #[default]
+    Overwrite,
+    /// Fails with [`TraversalError::DestinationCollision`].
+    Error,
+    /// Appends an incrementing counter to the file stem of each colliding
+    /// destination, i.e. `name.rs`, `name-2.rs`, `name-3.rs`.
+    Suffix,
+}
+
+/// Specifies what to do with templates that only have the template extension,
+/// like `foo.hbs`.
+///
+/// Stripping the template extension from a compound extension like
+/// `foo.rs.hbs` produces `foo.rs`, but doing the same to `foo.hbs` leaves
+/// `foo` with no extension at all, which is rarely what's wanted. Files with a
+/// compound extension are never affected by this policy, and neither are
+/// dotfiles like `.gitignore.hbs`.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SingleExtensionPolicy {
+    /// The default setting. Strips the template extension anyway, so `foo.hbs`
+    /// is rendered to `foo`.
+    #[default]
+    Strip,
+    /// Replaces the template extension with this one, so with `"txt"`,
+    /// `foo.hbs` is rendered to `foo.txt`.
+    DefaultExtension(String),
+    /// Fails with [`TraversalError::TemplateWithoutExtension`].
+    Error,
+}
+
+pub(crate) static DEFAULT_SINGLE_EXTENSION_POLICY: SingleExtensionPolicy =
+    SingleExtensionPolicy::Strip;
+
+#[derive(Debug)]
+struct Collisions {
+    strategy: CollisionStrategy,
+    claimed: HashMap<PathBuf, PathBuf>,
+}
+
+impl Collisions {
+    fn new(strategy: CollisionStrategy) -> Self {
+        Self {
+            strategy,
+            claimed: Default::default(),
+        }
+    }
+
+    fn resolve<E: Debug + Display + StdError>(
+        &mut self,
+        mut action: Action,
+    ) -> Result<Action, TraversalError<E>> {
+        // Appending to the same file more than once is the whole point.
+        if action.tag.create_directory()
+            || action.tag.append_template()
+            || self.strategy == CollisionStrategy::Overwrite
+        {
+            return Ok(action);
+        }
+        if let Some(other_src) = self.claimed.get(&action.dst) {
+            match self.strategy {
+                CollisionStrategy::Overwrite => unreachable!(),
+                CollisionStrategy::Error => {
+                    return Err(TraversalError::DestinationCollision {
+                        dst: action.dst,
+                        src: action.src,
+                        other_src: other_src.clone(),
+                    })
+                }
+                CollisionStrategy::Suffix => {
+                    let suffixed = (2..)
+                        .map(|n| suffix_path(&action.dst, n))
+                        .find(|suffixed| !self.claimed.contains_key(suffixed))
+                        .unwrap();
+                    log::info!(
+                        "destination {:?} was already claimed by {:?}; using {:?} for {:?} instead",
+                        action.dst,
+                        other_src,
+                        suffixed,
+                        action.src
+                    );
+                    action.dst = suffixed;
+                }
+            }
+        }
+        self.claimed.insert(action.dst.clone(), action.src.clone());
+        Ok(action)
+    }
+}
+
+fn suffix_path(path: &Path, n: usize) -> PathBuf {
+    let mut file_name = path.file_stem().unwrap().to_owned();
+    file_name.push(format!("-{}", n));
+    if let Some(ext) = path.extension() {
+        file_name.push(".");
+        file_name.push(ext);
+    }
+    path.with_file_name(file_name)
+}
+
 /// An error encountered when traversing a file tree.
 #[derive(Debug, Error)]
 pub enum TraversalError<E: Debug + Display + StdError + 'static = crate::RenderingError> {
@@ -133,54 +402,344 @@ pub enum TraversalError<E: Debug + Display + StdError + 'static = crate::Renderi
         #[source]
         cause: io::Error,
     },
-    /// Failed to transform path.
-    #[error("Failed to transform path at {path:?}: {cause}")]
+    /// Failed to get metadata for a path.
+    #[error("Failed to get metadata for {path:?}: {cause}")]
+    MetadataReadFailed {
+        path: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    /// Failed to transform a destination path. This is a problem with the path
+    /// itself rather than the contents of the file at it.
+    #[error("Failed to transform the destination path {path:?} (the path itself, not the file's contents): {cause}")]
     PathTransformFailed {
         path: PathBuf,
         #[source]
         cause: E,
     },
+    /// Multiple files would be written to the same destination.
+    /// Only produced when using [`CollisionStrategy::Error`].
+    #[error("Both {other_src:?} and {src:?} would be written to {dst:?}")]
+    DestinationCollision {
+        dst: PathBuf,
+        src: PathBuf,
+        other_src: PathBuf,
+    },
+    /// A template only has the template extension, so stripping it would
+    /// leave it without any extension. Only produced when using
+    /// [`SingleExtensionPolicy::Error`].
+    #[error("Template {src:?} would be rendered to a file without an extension")]
+    TemplateWithoutExtension { src: PathBuf },
+    /// Failed to read an ignore file. Only produced by
+    /// [`traverse_with_ignore`](crate::traverse_with_ignore).
+    #[cfg(feature = "ignore")]
+    #[error("Failed to read ignore file at {path:?}: {cause}")]
+    IgnoreFileReadFailed {
+        path: PathBuf,
+        #[source]
+        cause: io::Error,
+    },
+    /// An ignore pattern isn't valid gitignore syntax. Only produced by
+    /// [`traverse_with_ignore`](crate::traverse_with_ignore).
+    #[cfg(feature = "ignore")]
+    #[error("Failed to parse ignore pattern: {cause}")]
+    IgnoreParseFailed {
+        #[source]
+        cause: ignore::Error,
+    },
 }
 
-fn traverse_dir<E: Debug + Display + StdError>(
-    src: &Path,
-    dst: &Path,
-    transform_dst: &impl Fn(&Path) -> Result<PathBuf, E>,
-    template_ext: Option<&str>,
-    actions: &mut VecDeque<Action>,
-) -> Result<(), TraversalError<E>> {
-    Action::detect(src, dst, transform_dst, template_ext)
+/// Everything that affects how a file tree is interpreted during traversal.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct TraverseConfig<'a> {
+    pub(crate) template_ext: Option<&'a str>,
+    /// File names that are always treated as templates, without having their
+    /// extension stripped.
+    pub(crate) template_names: &'a [String],
+    pub(crate) collisions: CollisionStrategy,
+    pub(crate) single_extension: &'a SingleExtensionPolicy,
+    /// Whether or not to traverse the destination when it's inside of the
+    /// source, rather than skipping it.
+    pub(crate) include_nested_dst: bool,
+    /// The extension that marks a template as appending to its destination,
+    /// once the template extension has been stripped.
+    pub(crate) append_ext: Option<&'a str>,
+    /// Whether or not to produce a [`Tag::Symlink`] for symlinks, rather than
+    /// following them.
+    pub(crate) preserve_symlinks: bool,
+}
+
+impl<'a> TraverseConfig<'a> {
+    pub(crate) fn new(template_ext: Option<&'a str>) -> Self {
+        Self {
+            template_ext,
+            template_names: &[],
+            collisions: Default::default(),
+            single_extension: &DEFAULT_SINGLE_EXTENSION_POLICY,
+            include_nested_dst: false,
+            append_ext: None,
+            preserve_symlinks: false,
+        }
+    }
+
+    /// Whether or not the file at `path` would be treated as a template.
+    pub(crate) fn is_template(&self, path: &Path) -> bool {
+        self.is_template_name(path)
+            || self
+                .template_ext
+                .filter(|template_ext| path.extension().filter(|ext| ext == template_ext).is_some())
+                .is_some()
+    }
+
+    /// Whether or not the template at `path` only has the template extension,
+    /// i.e. `foo.hbs`.
+    fn has_single_extension(&self, path: &Path) -> bool {
+        path.file_stem()
+            .map(Path::new)
+            .filter(|stem| stem.extension().is_none() && !stem.to_string_lossy().starts_with('.'))
+            .is_some()
+    }
+
+    fn is_template_name(&self, path: &Path) -> bool {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .filter(|name| self.template_names.iter().any(|template| template == name))
+            .is_some()
+    }
+}
+
+/// Lazily walks a file tree, yielding each [`Action`] as it's discovered.
+///
+/// Entries are yielded in pre-order, so a directory's action is always yielded
+/// before the actions for any of its contents. If the destination is inside of
+/// the source, it's skipped (unless the config says otherwise), since it would
+/// otherwise be processed as part of its own source.
+pub(crate) struct TraverseIter<'a, F, E> {
+    transform_dst: F,
+    config: TraverseConfig<'a>,
+    collisions: Collisions,
+    root: Option<(PathBuf, PathBuf)>,
+    nested_dst: Option<PathBuf>,
+    // Entries this returns `false` for are skipped, along with their contents.
+    filter: Box<dyn Fn(&Path) -> bool + 'a>,
+    // Each open directory, alongside its destination path.
+    stack: Vec<(PathBuf, PathBuf, fs::ReadDir)>,
+    _marker: PhantomData<fn() -> E>,
+}
+
+impl<'a, F, E> TraverseIter<'a, F, E>
+where
+    F: Fn(&Path) -> Result<PathBuf, E>,
+    E: Debug + Display + StdError + 'static,
+{
+    pub(crate) fn new(
+        src: impl AsRef<Path>,
+        dst: impl AsRef<Path>,
+        transform_dst: F,
+        config: TraverseConfig<'a>,
+    ) -> Self {
+        let (src, dst) = (src.as_ref(), dst.as_ref());
+        let nested_dst = if config.include_nested_dst {
+            None
+        } else {
+            nested_dst(src, dst)
+        };
+        if let Some(nested_dst) = &nested_dst {
+            log::warn!(
+                "destination {:?} is inside of source {:?}, so {:?} won't be traversed",
+                dst,
+                src,
+                nested_dst
+            );
+        }
+        Self {
+            transform_dst,
+            config,
+            collisions: Collisions::new(config.collisions),
+            root: Some((src.to_owned(), dst.to_owned())),
+            nested_dst,
+            filter: Box::new(|_| true),
+            stack: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Skips every entry (besides the root) that `filter` returns `false`
+    /// for, without reading the contents of skipped directories.
+    pub(crate) fn with_filter(mut self, filter: impl Fn(&Path) -> bool + 'a) -> Self {
+        self.filter = Box::new(filter);
+        self
+    }
+
+    fn detect(&mut self, src: &Path, dst: &Path) -> Result<Action, TraversalError<E>> {
+        let is_file = !src.is_dir();
+        let action = if is_file && self.config.is_template_name(src) {
+            log::info!("path {:?} is always treated as a template", src);
+            Action::build(src, dst, &self.transform_dst, Tag::WriteTemplate, None)
+        } else if is_file
+            && self.config.is_template(src)
+            && self.config.has_single_extension(src)
+            && *self.config.single_extension != SingleExtensionPolicy::Strip
+        {
+            match self.config.single_extension {
+                SingleExtensionPolicy::Strip => unreachable!(),
+                SingleExtensionPolicy::DefaultExtension(ext) => {
+                    let mut name = match self.config.template_ext {
+                        Some(template_ext) => {
+                            strip_template_ext(src.file_name().unwrap(), OsStr::new(template_ext))
+                        }
+                        None => src.file_stem().unwrap(),
+                    }
+                    .to_owned();
+                    name.push(".");
+                    name.push(ext.trim_start_matches('.'));
+                    log::info!("giving template {:?} the default extension {:?}", src, ext);
+                    Action::with_dst(
+                        src.to_owned(),
+                        dst.join(name),
+                        &self.transform_dst,
+                        Tag::WriteTemplate,
+                    )
+                }
+                SingleExtensionPolicy::Error => {
+                    return Err(TraversalError::TemplateWithoutExtension {
+                        src: src.to_owned(),
+                    })
+                }
+            }
+        } else {
+            Action::detect(src, dst, &self.transform_dst, self.config.template_ext)
+        }
         .map_err(|cause| TraversalError::PathTransformFailed {
             path: dst.to_owned(),
             cause,
-        })?
-        .push_onto(actions);
-    if src.is_dir() {
-        log::info!("descending into dir {:?}", src);
-        for entry in fs::read_dir(src).map_err(|cause| TraversalError::DirectoryReadFailed {
-            path: src.to_owned(),
-            cause,
-        })? {
-            let new_src = entry
-                .map_err(|cause| TraversalError::EntryReadFailed {
-                    dir: src.to_owned(),
+        })?;
+        self.collisions.resolve(self.detect_append(action))
+    }
+
+    /// Turns a template into an [`Tag::AppendTemplate`] if its destination
+    /// ends in the append extension, which is then stripped too.
+    fn detect_append(&self, mut action: Action) -> Action {
+        let is_append = self
+            .config
+            .append_ext
+            .filter(|_| action.tag.write_template())
+            .is_some_and(|ext| action.dst.extension().is_some_and(|dst_ext| dst_ext == ext));
+        if is_append {
+            action.dst.set_extension("");
+            action.tag = Tag::AppendTemplate;
+            log::info!("template {:?} appends to {:?}", action.src, action.dst);
+        }
+        action
+    }
+
+    /// Whether or not `path` is a symlink that should be recreated as-is.
+    fn is_preserved_symlink(&self, path: &Path) -> bool {
+        self.config.preserve_symlinks
+            && fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+    }
+
+    fn detect_symlink(&mut self, src: &Path, dst: &Path) -> Result<Action, TraversalError<E>> {
+        log::info!("path {:?} is a symlink, so it won't be followed", src);
+        let action =
+            Action::build(src, dst, &self.transform_dst, Tag::Symlink, None).map_err(|cause| {
+                TraversalError::PathTransformFailed {
+                    path: dst.to_owned(),
+                    cause,
+                }
+            })?;
+        self.collisions.resolve(action)
+    }
+
+    fn visit(&mut self, src: PathBuf, dst: PathBuf) -> Result<Action, TraversalError<E>> {
+        let action = self.detect(&src, &dst)?;
+        if src.is_dir() {
+            log::info!("descending into dir {:?}", src);
+            let entries =
+                fs::read_dir(&src).map_err(|cause| TraversalError::DirectoryReadFailed {
+                    path: src.clone(),
                     cause,
-                })?
-                .path();
-            if new_src.is_dir() {
-                let new_dst = append_path(dst, &new_src, false);
-                traverse_dir(&new_src, &new_dst, transform_dst, template_ext, actions)?;
-            } else {
-                Action::detect(&new_src, dst, transform_dst, template_ext)
-                    .map_err(|cause| TraversalError::PathTransformFailed {
-                        path: dst.to_owned(),
+                })?;
+            self.stack.push((src, dst, entries));
+        }
+        Ok(action)
+    }
+}
+
+impl<'a, F, E> Iterator for TraverseIter<'a, F, E>
+where
+    F: Fn(&Path) -> Result<PathBuf, E>,
+    E: Debug + Display + StdError + 'static,
+{
+    type Item = Result<Action, TraversalError<E>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((src, dst)) = self.root.take() {
+            return Some(self.visit(src, dst));
+        }
+        loop {
+            let (dir, dst, entries) = self.stack.last_mut()?;
+            match entries.next() {
+                None => {
+                    self.stack.pop();
+                }
+                Some(Err(cause)) => {
+                    return Some(Err(TraversalError::EntryReadFailed {
+                        dir: dir.clone(),
                         cause,
-                    })?
-                    .push_onto(actions);
+                    }))
+                }
+                Some(Ok(entry)) => {
+                    let new_src = entry.path();
+                    if self.nested_dst.as_ref() == Some(&new_src) {
+                        continue;
+                    }
+                    if !(self.filter)(&new_src) {
+                        log::info!("skipping filtered path {:?}", new_src);
+                        continue;
+                    }
+                    let dst = dst.clone();
+                    return Some(if self.is_preserved_symlink(&new_src) {
+                        self.detect_symlink(&new_src, &dst)
+                    } else if new_src.is_dir() {
+                        let new_dst = append_path(&dst, &new_src, None);
+                        self.visit(new_src, new_dst)
+                    } else {
+                        self.detect(&new_src, &dst)
+                    });
+                }
             }
         }
     }
-    Ok(())
+}
+
+/// If `dst` is inside of `src` (by comparing canonical paths), returns the
+/// path to `dst` beneath `src` as it'd be found while traversing `src`. `dst`
+/// doesn't need to exist yet, and `src` itself is never considered nested.
+/// An empty `dst` is used for output that doesn't go to the filesystem, so it
+/// isn't considered nested either.
+fn nested_dst(src: &Path, dst: &Path) -> Option<PathBuf> {
+    if dst.as_os_str().is_empty() {
+        return None;
+    }
+    let src_canonical = src.canonicalize().ok()?;
+    let (existing, canonical) = dst.ancestors().find_map(|ancestor| {
+        let base = if ancestor.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            ancestor
+        };
+        base.canonicalize()
+            .ok()
+            .map(|canonical| (ancestor, canonical))
+    })?;
+    let canonical = canonical.join(dst.strip_prefix(existing).ok()?);
+    let relative = canonical.strip_prefix(&src_canonical).ok()?;
+    if relative.as_os_str().is_empty() {
+        None
+    } else {
+        Some(src.join(relative))
+    }
 }
 
 /// Traverse file tree at `src` to generate an [`Action`] list.
@@ -188,10 +747,18 @@ fn traverse_dir<E: Debug + Display + StdError>(
 /// and can be executed by [`Bicycle::process_actions`](crate::Bicycle::process_actions).
 ///
 /// File tree contents are interpreted as follows:
-/// - Each directory in the file tree generates an [`Action::CreateDirectory`].
+/// - Each directory in the file tree generates a [`Tag::CreateDirectory`] action.
 ///   Directories are traversed recursively.
-/// - Each file that doesn't end in `template_ext` generates an [`Action::CopyFile`].
-/// - Each file that ends in `template_ext` generates an [`Action::WriteTemplate`].
+/// - Each file that doesn't end in `template_ext` generates a [`Tag::CopyFile`] action.
+/// - Each file that ends in `template_ext` generates a [`Tag::WriteTemplate`] action.
+///   Only the final extension is stripped, so `foo.hbs.hbs` is rendered to `foo.hbs`.
+///   This makes it possible to generate templates meant to be rendered later on:
+///   escape the inner layer with `\{{` or a `{{{{raw}}}}` block to have it written out
+///   verbatim.
+///
+/// If `dst` is inside of `src`, like when generating into a `build` directory
+/// within the source, `dst` is skipped so that previous output isn't treated
+/// as part of the source.
 ///
 /// `transform_dst` is used to post-process destination path strings.
 /// [`Bicycle::transform_dst`](crate::Bicycle::transform_dst) is one possible implementation.
@@ -201,10 +768,202 @@ pub fn traverse<E: Debug + Display + StdError>(
     transform_dst: impl Fn(&Path) -> Result<PathBuf, E>,
     template_ext: Option<&str>,
 ) -> Result<VecDeque<Action>, TraversalError<E>> {
-    let src = src.as_ref();
-    let dst = dst.as_ref();
+    traverse_with_collisions(
+        src,
+        dst,
+        transform_dst,
+        template_ext,
+        CollisionStrategy::default(),
+    )
+}
+
+/// The same as [`traverse`], but uses `collisions` to resolve cases where
+/// multiple files would be written to the same destination.
+pub fn traverse_with_collisions<E: Debug + Display + StdError>(
+    src: impl AsRef<Path>,
+    dst: impl AsRef<Path>,
+    transform_dst: impl Fn(&Path) -> Result<PathBuf, E>,
+    template_ext: Option<&str>,
+    collisions: CollisionStrategy,
+) -> Result<VecDeque<Action>, TraversalError<E>> {
+    let config = TraverseConfig {
+        collisions,
+        ..TraverseConfig::new(template_ext)
+    };
+    traverse_with_config(src, dst, transform_dst, config)
+}
+
+/// The same as [`traverse`], but skips every file and directory in `src` that
+/// `filter` returns `false` for, which is handy for leaving out things like
+/// `.git`, `.DS_Store`, or `node_modules`.
+///
+/// `filter` is given the path of each entry (starting with `src`, which itself
+/// is never filtered) before anything is done with it, so the contents of a
+/// skipped directory aren't read at all.
+///
+/// # Examples
+/// ```no_run
+/// let actions = bicycle::traverse_filtered(
+///     "templates",
+///     "out",
+///     bicycle::no_transform,
+///     bicycle::DEFAULT_TEMPLATE_EXT,
+///     |path| path.file_name().is_none_or(|name| name != ".git"),
+/// )
+/// .unwrap();
+/// ```
+pub fn traverse_filtered<E: Debug + Display + StdError + 'static>(
+    src: impl AsRef<Path>,
+    dst: impl AsRef<Path>,
+    transform_dst: impl Fn(&Path) -> Result<PathBuf, E>,
+    template_ext: Option<&str>,
+    filter: impl Fn(&Path) -> bool,
+) -> Result<VecDeque<Action>, TraversalError<E>> {
+    let mut actions = VecDeque::new();
+    for action in TraverseIter::new(src, dst, transform_dst, TraverseConfig::new(template_ext))
+        .with_filter(filter)
+    {
+        action?.push_onto(&mut actions);
+    }
+    Ok(actions)
+}
+
+/// The same as [`traverse`], but carries on past any paths that fail, rather
+/// than stopping at the first error. Returns the actions for everything that
+/// could be traversed, alongside every error encountered, which is useful for
+/// reporting all of the problems with a tree at once.
+///
+/// A directory that can't be read is left out entirely, along with its
+/// contents.
+///
+/// # Examples
+/// ```no_run
+/// let (actions, errors) = bicycle::traverse_collecting(
+///     "templates",
+///     "out",
+///     bicycle::no_transform,
+///     bicycle::DEFAULT_TEMPLATE_EXT,
+/// );
+/// for error in &errors {
+///     eprintln!("{}", error);
+/// }
+/// ```
+pub fn traverse_collecting<E: Debug + Display + StdError>(
+    src: impl AsRef<Path>,
+    dst: impl AsRef<Path>,
+    transform_dst: impl Fn(&Path) -> Result<PathBuf, E>,
+    template_ext: Option<&str>,
+) -> (VecDeque<Action>, Vec<TraversalError<E>>) {
+    let mut actions = VecDeque::new();
+    let mut errors = Vec::new();
+    for action in TraverseIter::new(src, dst, transform_dst, TraverseConfig::new(template_ext)) {
+        match action {
+            Ok(action) => action.push_onto(&mut actions),
+            Err(err) => {
+                log::warn!("{}", err);
+                errors.push(err);
+            }
+        }
+    }
+    (actions, errors)
+}
+
+/// The same as [`traverse`], but lazily yields each [`Action`] as the tree is
+/// walked, rather than collecting them all first. This keeps memory usage flat
+/// for huge trees, and makes it cheap to stop early.
+///
+/// The order is discovery order (a pre-order walk), rather than the
+/// directories-first order produced by [`traverse`]: a directory's
+/// [`Tag::CreateDirectory`] action is yielded before anything inside of it, but not
+/// necessarily before files elsewhere in the tree. Callers executing actions
+/// as they go should thus create each directory as it's encountered.
+/// Iteration can continue after an error, though the failed path is skipped.
+pub fn traverse_iter<'a, E: Debug + Display + StdError + 'static>(
+    src: impl AsRef<Path>,
+    dst: impl AsRef<Path>,
+    transform_dst: impl Fn(&Path) -> Result<PathBuf, E> + 'a,
+    template_ext: Option<&'a str>,
+) -> impl Iterator<Item = Result<Action, TraversalError<E>>> + 'a {
+    TraverseIter::new(src, dst, transform_dst, TraverseConfig::new(template_ext))
+}
+
+/// Lists everything in `src` that was modified after `since`, alongside how
+/// it'd be processed, in discovery order. This is meant for build systems that
+/// want to know which outputs need to be regenerated.
+///
+/// Directories are included when their own modification time is newer, which
+/// generally means that entries were added to or removed from them.
+pub fn changed_since(
+    src: impl AsRef<Path>,
+    since: SystemTime,
+    template_ext: Option<&str>,
+) -> Result<Vec<(PathBuf, Tag)>, TraversalError<Infallible>> {
+    let config = TraverseConfig {
+        // Destinations are meaningless here.
+        include_nested_dst: true,
+        ..TraverseConfig::new(template_ext)
+    };
+    let mut changed = Vec::new();
+    for action in TraverseIter::new(src, "", no_transform, config) {
+        let action = action?;
+        let modified = fs::metadata(action.src())
+            .and_then(|metadata| metadata.modified())
+            .map_err(|cause| TraversalError::MetadataReadFailed {
+                path: action.src().to_owned(),
+                cause,
+            })?;
+        if modified > since {
+            changed.push((action.src, action.tag));
+        }
+    }
+    Ok(changed)
+}
+
+pub(crate) fn traverse_with_config<E: Debug + Display + StdError>(
+    src: impl AsRef<Path>,
+    dst: impl AsRef<Path>,
+    transform_dst: impl Fn(&Path) -> Result<PathBuf, E>,
+    config: TraverseConfig<'_>,
+) -> Result<VecDeque<Action>, TraversalError<E>> {
     let mut actions = VecDeque::new();
-    traverse_dir(src, dst, &transform_dst, template_ext, &mut actions).map(|_| actions)
+    for action in TraverseIter::new(src, dst, transform_dst, config) {
+        action?.push_onto(&mut actions);
+    }
+    Ok(actions)
+}
+
+/// Finds everything under `dst` that isn't in `keep`, producing a
+/// [`Tag::Delete`] action for each. Directories that aren't in `keep` are
+/// deleted as a whole, rather than having their contents listed.
+pub(crate) fn find_deletions<E: Debug + Display + StdError>(
+    dst: &Path,
+    keep: &HashSet<PathBuf>,
+) -> Result<Vec<Action>, TraversalError<E>> {
+    let mut deletions = Vec::new();
+    if !dst.is_dir() {
+        return Ok(deletions);
+    }
+    let mut stack = vec![dst.to_owned()];
+    while let Some(dir) = stack.pop() {
+        let entries = fs::read_dir(&dir).map_err(|cause| TraversalError::DirectoryReadFailed {
+            path: dir.clone(),
+            cause,
+        })?;
+        for entry in entries {
+            let entry = entry.map_err(|cause| TraversalError::EntryReadFailed {
+                dir: dir.clone(),
+                cause,
+            })?;
+            let path = entry.path();
+            if !keep.contains(&path) {
+                log::info!("{:?} has no corresponding source", path);
+                deletions.push(Action::deletion(path));
+            } else if entry.file_type().is_ok_and(|ty| ty.is_dir()) {
+                stack.push(path);
+            }
+        }
+    }
+    Ok(deletions)
 }
 
 /// Pass this to `traverse` if you don't want any path transformation at all.
@@ -0,0 +1,183 @@
+use handlebars::template::{
+    DecoratorTemplate, HelperTemplate, Parameter, Template, TemplateElement,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Helpers that are built into `handlebars`.
+pub(crate) static BUILTIN_HELPERS: &[&str] = &[
+    "if", "unless", "each", "with", "lookup", "raw", "log", "eq", "ne", "gt", "gte", "lt", "lte",
+    "and", "or", "not", "len",
+];
+
+/// Block helpers that render their contents with a different context, meaning
+/// that paths within them don't refer to top-level variables.
+static CONTEXT_HELPERS: &[&str] = &["each", "with"];
+
+/// Collects the top-level variable paths referenced by a template, in the order
+/// they first appear.
+pub(crate) struct Collector<'a> {
+    is_helper: &'a dyn Fn(&str) -> bool,
+    partials: &'a HashMap<String, Template>,
+    visited_partials: HashSet<String>,
+    seen: HashSet<String>,
+    variables: Vec<String>,
+}
+
+impl<'a> Collector<'a> {
+    pub(crate) fn new(
+        is_helper: &'a dyn Fn(&str) -> bool,
+        partials: &'a HashMap<String, Template>,
+    ) -> Self {
+        Self {
+            is_helper,
+            partials,
+            visited_partials: Default::default(),
+            seen: Default::default(),
+            variables: Default::default(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> Vec<String> {
+        self.variables
+    }
+
+    /// `depth` is how many context changes deep we are, where only paths
+    /// resolving to depth 0 are top-level variables.
+    pub(crate) fn template(&mut self, template: &Template, depth: usize) {
+        for element in &template.elements {
+            self.element(element, depth);
+        }
+    }
+
+    fn element(&mut self, element: &TemplateElement, depth: usize) {
+        match element {
+            TemplateElement::Expression(helper) | TemplateElement::HtmlExpression(helper) => {
+                self.expression(helper, depth)
+            }
+            TemplateElement::HelperBlock(helper) => self.block(helper, depth),
+            TemplateElement::PartialExpression(partial)
+            | TemplateElement::PartialBlock(partial) => self.partial(partial, depth),
+            TemplateElement::RawString(_)
+            | TemplateElement::Comment(_)
+            | TemplateElement::DecoratorExpression(_)
+            | TemplateElement::DecoratorBlock(_) => (),
+        }
+    }
+
+    fn expression(&mut self, helper: &HelperTemplate, depth: usize) {
+        let name_only = helper.params.is_empty() && helper.hash.is_empty();
+        match &helper.name {
+            // With no arguments, this is either a variable or a helper that
+            // takes no arguments.
+            Parameter::Path(_) | Parameter::Name(_) if name_only => {
+                let is_helper = helper
+                    .name
+                    .as_name()
+                    .filter(|name| (self.is_helper)(name))
+                    .is_some();
+                if !is_helper {
+                    self.parameter(&helper.name, depth);
+                }
+            }
+            name => {
+                if let Parameter::Subexpression(_) = name {
+                    self.parameter(name, depth);
+                }
+                self.arguments(helper, depth);
+            }
+        }
+    }
+
+    fn block(&mut self, helper: &HelperTemplate, depth: usize) {
+        self.arguments(helper, depth);
+        let changes_context = helper
+            .name
+            .as_name()
+            .filter(|name| CONTEXT_HELPERS.contains(name))
+            .is_some();
+        if let Some(template) = &helper.template {
+            self.template(template, depth + changes_context as usize);
+        }
+        // The inverse (i.e. the `{{else}}` branch) is rendered with the
+        // original context.
+        if let Some(inverse) = &helper.inverse {
+            self.template(inverse, depth);
+        }
+    }
+
+    fn partial(&mut self, partial: &DecoratorTemplate, depth: usize) {
+        for param in &partial.params {
+            self.parameter(param, depth);
+        }
+        for param in partial.hash.values() {
+            self.parameter(param, depth);
+        }
+        // Passing a parameter to a partial makes it the partial's context.
+        let partial_depth = depth + !partial.params.is_empty() as usize;
+        if let Some(name) = partial.name.as_name() {
+            if let Some(template) = self.partials.get(name) {
+                if self.visited_partials.insert(name.to_owned()) {
+                    self.template(template, partial_depth);
+                }
+            }
+        }
+        if let Some(template) = &partial.template {
+            self.template(template, depth);
+        }
+    }
+
+    fn arguments(&mut self, helper: &HelperTemplate, depth: usize) {
+        for param in &helper.params {
+            self.parameter(param, depth);
+        }
+        for param in helper.hash.values() {
+            self.parameter(param, depth);
+        }
+    }
+
+    fn parameter(&mut self, param: &Parameter, depth: usize) {
+        match param {
+            Parameter::Path(_) | Parameter::Name(_) => {
+                if let Some(variable) = param.as_name().and_then(|raw| top_level(raw, depth)) {
+                    if self.seen.insert(variable.to_owned()) {
+                        self.variables.push(variable.to_owned());
+                    }
+                }
+            }
+            Parameter::Subexpression(subexpression) => {
+                if let TemplateElement::Expression(helper) = subexpression.as_element() {
+                    self.arguments(helper, depth);
+                }
+            }
+            Parameter::Literal(_) => (),
+        }
+    }
+}
+
+/// Resolves a raw path relative to the top-level context, if it refers to it.
+fn top_level(raw: &str, depth: usize) -> Option<&str> {
+    if let Some(rest) = raw
+        .strip_prefix("@root.")
+        .or_else(|| raw.strip_prefix("@root/"))
+    {
+        return Some(rest);
+    }
+    if raw.starts_with('@') {
+        // Local variables like `@index` aren't data.
+        return None;
+    }
+    let mut path = raw;
+    let mut up = 0;
+    while let Some(rest) = path.strip_prefix("../") {
+        path = rest;
+        up += 1;
+    }
+    if up != depth {
+        return None;
+    }
+    let path = ["this.", "this/", "./"]
+        .iter()
+        .find_map(|prefix| path.strip_prefix(prefix))
+        .unwrap_or(path);
+    Some(path).filter(|path| !path.is_empty() && *path != "this")
+}
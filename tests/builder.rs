@@ -0,0 +1,37 @@
+use bicycle::{handlebars::handlebars_helper, Bicycle, JsonMap};
+
+handlebars_helper!(shout: |s: str| s.to_uppercase());
+handlebars_helper!(reverse: |s: str| s.chars().rev().collect::<String>());
+
+#[test]
+fn strict_by_default() {
+    let bike = Bicycle::builder().build();
+    let err = bike.render("{{missing}}", |_| ()).unwrap_err();
+    assert!(err.is_strict_missing());
+}
+
+#[test]
+fn strict_mode_off() {
+    let bike = Bicycle::builder().strict_mode(false).build();
+    assert_eq!(bike.render("[{{missing}}]", |_| ()).unwrap(), "[]");
+}
+
+#[test]
+fn incremental_helpers() {
+    let mut base_data = JsonMap::default();
+    base_data.insert("name", "misato");
+    let bike = Bicycle::builder()
+        .helper("shout", Box::new(shout))
+        .base_data(base_data)
+        .helper("reverse", Box::new(reverse))
+        .helper("shout", Box::new(reverse))
+        .build();
+    let helpers = bike.registered_helpers();
+    assert!(helpers.iter().any(|name| name == "reverse"));
+    assert!(helpers.iter().any(|name| name == "shout"));
+    assert_eq!(
+        bike.render("{{shout name}} {{reverse name}}", |_| ())
+            .unwrap(),
+        "otasim otasim"
+    );
+}
@@ -0,0 +1,41 @@
+#![allow(dead_code)]
+
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// Creates a temporary directory containing `files`, which are pairs of
+/// relative paths and contents.
+pub fn fixture(files: &[(&str, &str)]) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (path, contents) in files {
+        let path = dir.path().join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+/// Lists everything under `root`, mapping relative paths (using `/` as the
+/// separator) to file contents. Directories map to `None`.
+pub fn tree(root: &Path) -> BTreeMap<String, Option<String>> {
+    fn walk(root: &Path, dir: &Path, tree: &mut BTreeMap<String, Option<String>>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let rel = path
+                .strip_prefix(root)
+                .unwrap()
+                .components()
+                .map(|component| component.as_os_str().to_str().unwrap())
+                .collect::<Vec<_>>()
+                .join("/");
+            if path.is_dir() {
+                tree.insert(rel, None);
+                walk(root, &path, tree);
+            } else {
+                tree.insert(rel, Some(fs::read_to_string(&path).unwrap()));
+            }
+        }
+    }
+    let mut tree = BTreeMap::new();
+    walk(root, root, &mut tree);
+    tree
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use self::common::fixture;
+use bicycle::{find_duplicate_sources, no_transform, traverse, DEFAULT_TEMPLATE_EXT};
+use std::path::{Path, PathBuf};
+
+fn names(group: &[&bicycle::Action], root: &Path) -> Vec<PathBuf> {
+    let mut names = group
+        .iter()
+        .map(|action| action.src().strip_prefix(root).unwrap().to_owned())
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+#[test]
+fn find_duplicates() {
+    let src = fixture(&[
+        ("a.png", "same"),
+        ("b/c.png", "same"),
+        ("d.png", "diff"),
+        ("e.txt", "longer contents"),
+        ("f.txt.hbs", "same"),
+    ]);
+    #[cfg(unix)]
+    std::fs::hard_link(src.path().join("e.txt"), src.path().join("g.txt")).unwrap();
+    let actions = traverse(src.path(), "out", no_transform, DEFAULT_TEMPLATE_EXT).unwrap();
+    let groups = find_duplicate_sources(&actions).unwrap();
+    let groups = groups
+        .iter()
+        .map(|group| names(group, src.path()))
+        .collect::<Vec<_>>();
+    assert!(groups.contains(&vec![PathBuf::from("a.png"), Path::new("b").join("c.png")]));
+    #[cfg(unix)]
+    assert!(groups.contains(&vec![PathBuf::from("e.txt"), PathBuf::from("g.txt")]));
+    #[cfg(unix)]
+    assert_eq!(groups.len(), 2);
+    #[cfg(not(unix))]
+    assert_eq!(groups.len(), 1);
+}
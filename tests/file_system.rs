@@ -0,0 +1,74 @@
+mod common;
+
+use self::common::{fixture, tree};
+use bicycle::{Bicycle, FileSystem as _, JsonMap, MemFs, OverwritePolicy, RealFs};
+use std::{collections::HashMap, path::Path};
+
+fn insert_name(map: &mut JsonMap) {
+    map.insert("name", "asuka");
+}
+
+fn sample() -> tempfile::TempDir {
+    fixture(&[
+        ("README.md", "static"),
+        ("{{name}}/mod.rs.hbs", "// {{name}}"),
+        ("log.txt.append.hbs", "{{name}}\n"),
+    ])
+}
+
+#[test]
+fn mem_fs() {
+    let src = sample();
+    let out = src.path().join("out");
+    let fs = MemFs::default();
+    let mut bike = Bicycle::default();
+    bike.set_append_ext(Some("append"));
+    bike.process_with_fs(src.path(), &out, &fs, insert_name)
+        .unwrap();
+    bike.process_with_fs(src.path(), &out, &fs, insert_name)
+        .unwrap();
+    assert!(!out.exists());
+    assert!(fs.dirs().contains(&out.join("asuka")));
+    let files = fs.into_files();
+    assert_eq!(files.len(), 3);
+    assert_eq!(files[&out.join("README.md")], b"static");
+    assert_eq!(files[&out.join("asuka/mod.rs")], b"// asuka");
+    assert_eq!(files[&out.join("log.txt")], b"asuka\nasuka\n");
+}
+
+#[test]
+fn existing_files() {
+    let src = sample();
+    let out = Path::new("out");
+    let fs = MemFs::with_files(HashMap::from([
+        (out.join("README.md"), b"edited".to_vec()),
+        (out.join("log.txt"), b"existing\n".to_vec()),
+    ]));
+    let mut bike = Bicycle::default();
+    bike.set_append_ext(Some("append"));
+    bike.set_append_dedupe(true);
+    bike.set_overwrite_policy(OverwritePolicy::Never);
+    for _ in 0..2 {
+        bike.process_with_fs(src.path(), out, &fs, insert_name)
+            .unwrap();
+    }
+    assert_eq!(fs.read(&out.join("README.md")).unwrap(), b"edited");
+    assert_eq!(fs.read(&out.join("log.txt")).unwrap(), b"existing\nasuka\n");
+    assert_eq!(
+        fs.read(&out.join("missing.txt")).unwrap_err().kind(),
+        std::io::ErrorKind::NotFound
+    );
+}
+
+#[test]
+fn real_fs() {
+    let src = sample();
+    let (expected, dst) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+    let mut bike = Bicycle::default();
+    bike.set_append_ext(Some("append"));
+    bike.process(src.path(), expected.path(), insert_name)
+        .unwrap();
+    bike.process_with_fs(src.path(), dst.path(), &RealFs, insert_name)
+        .unwrap();
+    assert_eq!(tree(dst.path()), tree(expected.path()));
+}
@@ -0,0 +1,239 @@
+use bicycle::{FromDirError, JsonMap, NestingError};
+
+mod common;
+
+use self::common::fixture;
+use serde_json::json;
+
+fn map(pairs: &[(&str, serde_json::Value)]) -> JsonMap {
+    let mut map = JsonMap::default();
+    for (key, value) in pairs {
+        map.insert(key, value);
+    }
+    map
+}
+
+#[test]
+fn from_serialize() {
+    #[derive(serde::Serialize)]
+    struct Author {
+        name: String,
+        unit: u32,
+    }
+
+    #[derive(serde::Serialize)]
+    struct Config {
+        title: &'static str,
+        author: Author,
+        tags: Vec<&'static str>,
+    }
+
+    let config = Config {
+        title: "eva",
+        author: Author {
+            name: "shinji".to_owned(),
+            unit: 1,
+        },
+        tags: vec!["pilot"],
+    };
+    assert_eq!(
+        JsonMap::from_serialize(&config).unwrap(),
+        map(&[
+            ("title", json!("eva")),
+            ("author", json!({ "name": "shinji", "unit": 1 })),
+            ("tags", json!(["pilot"])),
+        ])
+    );
+}
+
+#[test]
+fn from_serialize_non_object() {
+    assert!(JsonMap::from_serialize(["shinji", "rei"]).is_err());
+    assert!(JsonMap::from_serialize(1).is_err());
+}
+
+#[test]
+fn insert_at() {
+    let mut map = map(&[("project", json!({ "name": "bicycle" }))]);
+    map.insert_at("project.author.name", "shinji").unwrap();
+    map.insert_at("project.author.unit", 1).unwrap();
+    map.insert_at("title", "eva").unwrap();
+    assert_eq!(
+        map,
+        self::map(&[
+            (
+                "project",
+                json!({ "name": "bicycle", "author": { "name": "shinji", "unit": 1 } }),
+            ),
+            ("title", json!("eva")),
+        ])
+    );
+}
+
+#[test]
+fn insert_at_conflict() {
+    let mut map = map(&[("project", json!({ "name": "bicycle" }))]);
+    let before = map.clone();
+    match map.insert_at("project.name.first", "bi") {
+        Err(NestingError::KeyConflict { key, other }) => {
+            assert_eq!(key, "project.name");
+            assert_eq!(other, "project.name.first");
+        }
+        other => panic!("expected a key conflict, but got {:?}", other),
+    }
+    assert_eq!(map, before);
+}
+
+#[test]
+fn merge_conflicting_scalars() {
+    let mut base = map(&[("name", json!("shinji")), ("unit", json!(1))]);
+    base.merge(&map(&[("name", json!("rei")), ("title", json!("pilot"))]));
+    assert_eq!(
+        base,
+        map(&[
+            ("name", json!("rei")),
+            ("unit", json!(1)),
+            ("title", json!("pilot")),
+        ])
+    );
+}
+
+#[test]
+fn merge_nested_objects() {
+    let mut base = map(&[(
+        "user",
+        json!({ "name": "shinji", "unit": { "id": 1, "color": "purple" }, "tags": ["pilot"] }),
+    )]);
+    base.merge(&map(&[(
+        "user",
+        json!({ "unit": { "id": 0 }, "tags": ["student"], "age": 14 }),
+    )]));
+    assert_eq!(
+        base,
+        map(&[(
+            "user",
+            json!({
+                "name": "shinji",
+                "unit": { "id": 0, "color": "purple" },
+                "tags": ["student"],
+                "age": 14,
+            }),
+        )])
+    );
+    // A non-object replaces an object, and vice versa.
+    base.merge(&map(&[("user", json!("shinji"))]));
+    assert_eq!(base, map(&[("user", json!("shinji"))]));
+}
+
+#[test]
+fn nest_and_flatten() {
+    let flat = map(&[
+        ("user.name", json!("shinji")),
+        ("user.unit.id", json!(1)),
+        ("user.tags", json!(["pilot"])),
+        ("title", json!("eva")),
+    ]);
+    let nested = map(&[
+        (
+            "user",
+            json!({ "name": "shinji", "unit": { "id": 1 }, "tags": ["pilot"] }),
+        ),
+        ("title", json!("eva")),
+    ]);
+    assert_eq!(flat.clone().nest().unwrap(), nested);
+    assert_eq!(nested.flatten(), flat);
+}
+
+#[test]
+fn nest_merges_objects() {
+    let partial = map(&[
+        ("user", json!({ "name": "shinji" })),
+        ("user.age", json!(14)),
+    ]);
+    assert_eq!(
+        partial.nest().unwrap(),
+        map(&[("user", json!({ "name": "shinji", "age": 14 }))])
+    );
+}
+
+#[test]
+fn nest_conflict() {
+    let conflicting = map(&[("user", json!("shinji")), ("user.name", json!("shinji"))]);
+    match conflicting.nest() {
+        Err(NestingError::KeyConflict { key, other }) => {
+            assert_eq!(key, "user");
+            assert_eq!(other, "user.name");
+        }
+        other => panic!("expected a key conflict, but got {:?}", other),
+    }
+}
+
+#[test]
+fn from_env() {
+    std::env::set_var("BICYCLE_TEST_FROM_ENV_NAME", "shinji");
+    std::env::set_var("BICYCLE_TEST_FROM_ENV_DB__HOST", "localhost");
+    let map = JsonMap::from_env("BICYCLE_TEST_FROM_ENV_");
+    assert_eq!(
+        map,
+        self::map(&[("name", json!("shinji")), ("db.host", json!("localhost"))])
+    );
+    assert_eq!(
+        map.nest().unwrap(),
+        self::map(&[
+            ("name", json!("shinji")),
+            ("db", json!({ "host": "localhost" }))
+        ])
+    );
+}
+
+#[test]
+fn merge_env() {
+    std::env::set_var("BICYCLE_TEST_MERGE_ENV_DB__HOST", "localhost");
+    let mut bike = bicycle::Bicycle::default();
+    bike.merge_env("BICYCLE_TEST_MERGE_ENV_").unwrap();
+    let rendered = bike.render("{{db.host}}", |_| ()).unwrap();
+    assert_eq!(rendered, "localhost");
+}
+
+#[test]
+fn from_dir() {
+    let dir = fixture(&[
+        (
+            "00-base.json",
+            r#"{ "db": { "host": "localhost", "port": 5432 }, "name": "eva" }"#,
+        ),
+        ("10-override.json", r#"{ "db": { "host": "nerv" } }"#),
+        ("notes.txt", "not data"),
+        ("units/01.json", r#"{ "unit-01": "shinji" }"#),
+        (
+            "units/00.json",
+            r#"{ "unit-00": "rei", "unit-01": "nobody" }"#,
+        ),
+    ]);
+    let expected = map(&[
+        ("db", json!({ "host": "nerv", "port": 5432 })),
+        ("name", json!("eva")),
+        ("units", json!({ "unit-00": "rei", "unit-01": "shinji" })),
+    ]);
+    assert_eq!(JsonMap::from_dir(dir.path()).unwrap(), expected);
+}
+
+#[test]
+fn from_dir_not_an_object() {
+    let dir = fixture(&[("list.json", "[1, 2, 3]")]);
+    assert!(matches!(
+        JsonMap::from_dir(dir.path()),
+        Err(FromDirError::NotAnObject { .. })
+    ));
+}
+
+#[cfg(feature = "serde_yaml")]
+#[test]
+fn from_dir_yaml() {
+    let dir = fixture(&[
+        ("a.yaml", "db:\n  host: localhost\n  port: 5432\n"),
+        ("b.json", r#"{ "db": { "port": 6543 } }"#),
+    ]);
+    let expected = map(&[("db", json!({ "host": "localhost", "port": 6543 }))]);
+    assert_eq!(JsonMap::from_dir(dir.path()).unwrap(), expected);
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use self::common::{fixture, tree};
+use bicycle::{
+    Bicycle, CollisionStrategy, GitAttributesMode, LineEndings, OversizedTemplatePolicy,
+    OverwritePolicy, ProcessOptions, SingleExtensionPolicy,
+};
+
+#[test]
+fn round_trip() {
+    let options = ProcessOptions {
+        collision_strategy: CollisionStrategy::Error,
+        single_extension: SingleExtensionPolicy::DefaultExtension("txt".to_owned()),
+        mark_generated: Some(GitAttributesMode::Append),
+        max_template_bytes: Some((1024, OversizedTemplatePolicy::Copy)),
+        append_ext: Some("append".to_owned()),
+        overwrite_policy: OverwritePolicy::IfChanged,
+        line_endings: LineEndings::CrLf,
+        ..Default::default()
+    };
+    let json = serde_json::to_value(&options).unwrap();
+    assert_eq!(json["collision_strategy"], "error");
+    assert_eq!(json["single_extension"]["default-extension"], "txt");
+    assert_eq!(json["mark_generated"], "append");
+    assert_eq!(json["overwrite_policy"], "if-changed");
+    assert_eq!(json["line_endings"], "crlf");
+    assert_eq!(
+        json["max_template_bytes"],
+        serde_json::json!([1024, "copy"])
+    );
+    assert_eq!(
+        serde_json::from_value::<ProcessOptions>(json).unwrap(),
+        options
+    );
+}
+
+#[test]
+fn unknown_fields() {
+    assert!(serde_json::from_str::<ProcessOptions>(r#"{ "collisions": "error" }"#).is_err());
+}
+
+#[test]
+fn setters_and_set_options() {
+    let mut bike = Bicycle::default();
+    bike.set_dest_directives(true);
+    bike.set_skip_sentinel(Some(""));
+    assert!(bike.options().dest_directives);
+    assert_eq!(bike.options().skip_sentinel.as_deref(), Some(""));
+
+    bike.set_options(ProcessOptions {
+        destination_metadata: true,
+        append_ext: Some(".append".to_owned()),
+        ..Default::default()
+    });
+    assert!(!bike.options().dest_directives);
+    let src = fixture(&[
+        ("log.txt", "first\n"),
+        (
+            "log.txt.append.hbs",
+            "{{#if destination.exists}}again{{/if}}\n",
+        ),
+    ]);
+    let dst = tempfile::tempdir().unwrap();
+    bike.process(src.path(), dst.path(), |_| ()).unwrap();
+    assert_eq!(
+        tree(dst.path())["log.txt"].as_deref(),
+        Some("first\nagain\n")
+    );
+}
+
+#[test]
+fn process_with_options() {
+    let src = fixture(&[("mod.rs.hbs", "@@ dest: {{name}}.rs\nmod {{name}};")]);
+    let dst = tempfile::tempdir().unwrap();
+    let bike = Bicycle::default();
+    let options = ProcessOptions {
+        dest_directives: true,
+        ..Default::default()
+    };
+    bike.process_with_options(src.path(), dst.path(), &options, |map| {
+        map.insert("name", "asuka")
+    })
+    .unwrap();
+    let tree = tree(dst.path());
+    assert_eq!(tree["asuka.rs"].as_deref(), Some("mod asuka;"));
+    assert!(!tree.contains_key("mod.rs"));
+    assert_eq!(bike.options(), &ProcessOptions::default());
+}
+
+#[test]
+fn front_matter_without_serde_yaml() {
+    let options = serde_json::from_str::<ProcessOptions>(r#"{ "front_matter": true }"#).unwrap();
+    assert!(options.front_matter);
+    let src = fixture(&[(
+        "greeting.txt.hbs",
+        "---\ndata:\n  name: rei\n---\nhi {{name}}",
+    )]);
+    let dst = tempfile::tempdir().unwrap();
+    let result = Bicycle::default().process_with_options(src.path(), dst.path(), &options, |_| ());
+    #[cfg(feature = "serde_yaml")]
+    {
+        result.unwrap();
+        assert_eq!(tree(dst.path())["greeting.txt"].as_deref(), Some("hi rei"));
+    }
+    #[cfg(not(feature = "serde_yaml"))]
+    match result {
+        Err(bicycle::ProcessingError::FrontMatterUnsupported { path }) => {
+            assert_eq!(path, src.path().join("greeting.txt.hbs"))
+        }
+        other => panic!(
+            "expected FrontMatterUnsupported, got {:?}",
+            other.map(|_| ())
+        ),
+    }
+}
@@ -0,0 +1,2448 @@
+mod common;
+
+use self::common::{fixture, tree};
+use bicycle::{ActionSummary, Bicycle, JsonMap, ProcessingError, Tag};
+
+fn insert_name(map: &mut JsonMap) {
+    map.insert("name", "shinji");
+}
+
+fn sample() -> tempfile::TempDir {
+    fixture(&[
+        ("README.md", "static"),
+        ("{{name}}/mod.rs.hbs", "// {{name}}"),
+        ("{{name}}/nested/deeper.txt", "deep"),
+        ("empty/.keep", ""),
+    ])
+}
+
+#[test]
+fn process() {
+    let src = sample();
+    let dst = tempfile::tempdir().unwrap();
+    Bicycle::default()
+        .process(src.path(), dst.path(), insert_name)
+        .unwrap();
+    let tree = tree(dst.path());
+    assert_eq!(tree["README.md"].as_deref(), Some("static"));
+    assert_eq!(tree["shinji/mod.rs"].as_deref(), Some("// shinji"));
+    assert_eq!(tree["shinji/nested/deeper.txt"].as_deref(), Some("deep"));
+    assert!(tree.contains_key("empty"));
+}
+
+#[test]
+fn process_returning() {
+    let src = sample();
+    let dst = tempfile::tempdir().unwrap();
+    let actions = Bicycle::default()
+        .process_returning(src.path(), dst.path(), insert_name)
+        .unwrap();
+    for action in &actions {
+        assert!(action.dst().exists(), "{:?} wasn't executed", action);
+    }
+    let template = actions
+        .iter()
+        .find(|action| action.tag().write_template())
+        .unwrap();
+    assert_eq!(template.dst(), dst.path().join("shinji").join("mod.rs"));
+    assert_eq!(actions.len(), 8);
+}
+
+#[test]
+fn process_with_progress() {
+    let src = sample();
+    let dst = tempfile::tempdir().unwrap();
+    let mut bike = Bicycle::default();
+    let expected = (1..=8).map(|finished| (finished, 8)).collect::<Vec<_>>();
+    let mut reported = Vec::new();
+    bike.process_with_progress(src.path(), dst.path(), insert_name, |finished, total| {
+        reported.push((finished, total))
+    })
+    .unwrap();
+    assert_eq!(reported, expected);
+    // Skipped actions still count.
+    bike.set_overwrite_policy(bicycle::OverwritePolicy::Never);
+    reported.clear();
+    bike.process_with_progress(src.path(), dst.path(), insert_name, |finished, total| {
+        reported.push((finished, total))
+    })
+    .unwrap();
+    assert_eq!(reported, expected);
+}
+
+#[test]
+fn process_summarized() {
+    let src = sample();
+    let dst = tempfile::tempdir().unwrap();
+    let summary = Bicycle::default()
+        .process_summarized(src.path(), dst.path(), insert_name)
+        .unwrap();
+    assert_eq!(
+        summary,
+        ActionSummary {
+            directories_created: 4,
+            templates_written: 1,
+            files_copied: 3,
+            ..Default::default()
+        }
+    );
+    assert_eq!(summary.count(Tag::CopyFile), 3);
+    assert_eq!(summary.total(), 8);
+    assert_eq!(
+        summary.to_string(),
+        "Created 4 directories, wrote 1 template, copied 3 files."
+    );
+    assert_eq!(ActionSummary::default().to_string(), "Nothing to do.");
+}
+
+#[test]
+fn errors_name_the_failing_template() {
+    let src = fixture(&[
+        ("a.txt.hbs", "{{name}}"),
+        ("sub/broken.txt.hbs", "{{missing}}"),
+        ("z.txt.hbs", "{{name}}"),
+    ]);
+    let dst = tempfile::tempdir().unwrap();
+    let err = Bicycle::default()
+        .process(src.path(), dst.path(), insert_name)
+        .unwrap_err();
+    let broken = src.path().join("sub/broken.txt.hbs");
+    let broken_dst = dst.path().join("sub/broken.txt");
+    match &err {
+        ProcessingError::TemplateRenderFailed { src, dst, .. } => {
+            assert_eq!(src, &broken);
+            assert_eq!(dst.as_ref(), Some(&broken_dst));
+        }
+        other => panic!("expected a render failure, but got {:?}", other),
+    }
+    assert!(err.to_string().contains(&format!("{:?}", broken)));
+    assert!(err.to_string().contains(&format!("{:?}", broken_dst)));
+}
+
+#[test]
+fn errors_box_as_dyn_error() {
+    fn process(src: &std::path::Path) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let dst = tempfile::tempdir()?;
+        Bicycle::default().process(src, dst.path(), |_| ())?;
+        Ok(())
+    }
+    let src = fixture(&[("broken.txt.hbs", "{{missing}}")]);
+    let err = process(src.path()).unwrap_err();
+    assert!(err.downcast_ref::<ProcessingError>().is_some());
+    let source = err.source().expect("the rendering error is the source");
+    assert!(source.downcast_ref::<bicycle::RenderingError>().is_some());
+}
+
+#[test]
+fn process_returning_partial() {
+    let src = fixture(&[("sub/ok.txt", "fine"), ("broken.txt.hbs", "{{missing}}")]);
+    let dst = tempfile::tempdir().unwrap();
+    let err = Bicycle::default()
+        .process_returning(src.path(), dst.path(), |_| ())
+        .unwrap_err();
+    assert!(matches!(
+        err.cause,
+        bicycle::ProcessingError::TemplateRenderFailed { .. }
+    ));
+    // Directories always come first, so at least those were completed.
+    assert!(err.completed.len() >= 2);
+    for action in &err.completed {
+        assert!(!action.tag().write_template());
+        assert!(action.dst().exists(), "{:?} wasn't executed", action);
+    }
+}
+
+#[test]
+fn process_streaming_matches_process() {
+    let src = sample();
+    let eager = tempfile::tempdir().unwrap();
+    let streaming = tempfile::tempdir().unwrap();
+    let bike = Bicycle::default();
+    bike.process(src.path(), eager.path(), insert_name).unwrap();
+    bike.process_streaming(src.path(), streaming.path(), insert_name)
+        .unwrap();
+    assert_eq!(tree(eager.path()), tree(streaming.path()));
+}
+
+#[test]
+fn nested_templates() {
+    let src = fixture(&[(
+        "greeting.hbs.hbs",
+        "{{{{raw}}}}{{#if name}}{{/if}}{{{{/raw}}}}Hello \\{{name}}, from {{name}}!",
+    )]);
+    let dst = tempfile::tempdir().unwrap();
+    let bike = Bicycle::default();
+    bike.process(src.path(), dst.path(), insert_name).unwrap();
+    let generated = tree(dst.path())["greeting.hbs"].clone().unwrap();
+    assert_eq!(generated, "{{#if name}}{{/if}}Hello {{name}}, from shinji!");
+    assert_eq!(
+        bike.render(&generated, |map| map.insert("name", "asuka"))
+            .unwrap(),
+        "Hello asuka, from shinji!",
+    );
+}
+
+mod sidecars {
+    use super::*;
+    use bicycle::ProcessingError;
+
+    fn sample() -> tempfile::TempDir {
+        fixture(&[
+            ("pilot.txt.hbs", "{{name}} pilots {{unit}}"),
+            (
+                "pilot.txt.hbs.json",
+                r#"{ "name": "rei", "unit": "unit-00" }"#,
+            ),
+            ("plain.txt.hbs", "{{name}}"),
+        ])
+    }
+
+    #[test]
+    fn override_data() {
+        let src = sample();
+        let dst = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_sidecar_suffix(Some(".json"));
+        bike.process(src.path(), dst.path(), insert_name).unwrap();
+        let tree = tree(dst.path());
+        assert_eq!(tree["pilot.txt"].as_deref(), Some("rei pilots unit-00"));
+        assert_eq!(tree["plain.txt"].as_deref(), Some("shinji"));
+        assert!(!tree.contains_key("pilot.txt.hbs.json"));
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let src = sample();
+        let dst = tempfile::tempdir().unwrap();
+        let result = Bicycle::default().process(src.path(), dst.path(), insert_name);
+        // Without the sidecar, `unit` is missing.
+        assert!(matches!(
+            result,
+            Err(ProcessingError::TemplateRenderFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn malformed() {
+        let src = fixture(&[("a.hbs", "{{name}}"), ("a.hbs.json", "[1, 2, 3]")]);
+        let dst = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_sidecar_suffix(Some(".json"));
+        let result = bike.process(src.path(), dst.path(), insert_name);
+        assert!(matches!(
+            result,
+            Err(ProcessingError::SidecarParseFailed { .. })
+        ));
+    }
+}
+
+mod directory_data {
+    use super::*;
+
+    fn sample() -> tempfile::TempDir {
+        fixture(&[
+            ("_data.json", r#"{ "project": "eva", "unit": "none" }"#),
+            ("top.txt.hbs", "{{project}} {{unit}}"),
+            ("units/_data.json", r#"{ "unit": "unit-01" }"#),
+            (
+                "units/pilot.txt.hbs",
+                "{{name}} pilots {{unit}} for {{project}}",
+            ),
+            ("units/spare/_data.json", r#"{ "unit": "unit-02" }"#),
+            (
+                "units/spare/parent.txt.hbs",
+                "{{unit}} after {{parent.unit}}",
+            ),
+            ("other/plain.txt.hbs", "{{unit}}"),
+        ])
+    }
+
+    #[test]
+    fn nested() {
+        let src = sample();
+        let eager = tempfile::tempdir().unwrap();
+        let streaming = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_directory_data_name(Some("_data.json"));
+        bike.process(src.path(), eager.path(), insert_name).unwrap();
+        bike.process_streaming(src.path(), streaming.path(), insert_name)
+            .unwrap();
+        let tree = tree(eager.path());
+        assert_eq!(tree["top.txt"].as_deref(), Some("eva none"));
+        assert_eq!(
+            tree["units/pilot.txt"].as_deref(),
+            Some("shinji pilots unit-01 for eva"),
+        );
+        assert_eq!(
+            tree["units/spare/parent.txt"].as_deref(),
+            Some("unit-02 after unit-01"),
+        );
+        // Siblings don't see each other's data.
+        assert_eq!(tree["other/plain.txt"].as_deref(), Some("none"));
+        assert!(!tree.keys().any(|path| path.ends_with("_data.json")));
+        assert_eq!(tree, self::tree(streaming.path()));
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let src = sample();
+        let dst = tempfile::tempdir().unwrap();
+        let result = Bicycle::default().process(src.path(), dst.path(), insert_name);
+        assert!(matches!(
+            result,
+            Err(bicycle::ProcessingError::TemplateRenderFailed { .. })
+        ));
+    }
+}
+
+#[test]
+fn template_names() {
+    let src = fixture(&[
+        ("Dockerfile", "FROM {{name}}"),
+        ("schema.sql", "CREATE TABLE {{name}};"),
+        ("other.sql", "{{name}}"),
+    ]);
+    let dst = tempfile::tempdir().unwrap();
+    let mut bike = Bicycle::default();
+    bike.set_template_names(vec!["Dockerfile", "schema.sql"]);
+    bike.process(src.path(), dst.path(), insert_name).unwrap();
+    let tree = tree(dst.path());
+    assert_eq!(tree["Dockerfile"].as_deref(), Some("FROM shinji"));
+    assert_eq!(tree["schema.sql"].as_deref(), Some("CREATE TABLE shinji;"));
+    assert_eq!(tree["other.sql"].as_deref(), Some("{{name}}"));
+}
+
+#[test]
+fn source_metadata() {
+    let template = "{{source.size}} {{source.is_executable}} {{#if source.modified}}dated{{/if}}";
+    let src = fixture(&[("info.txt.hbs", template)]);
+    let dst = tempfile::tempdir().unwrap();
+    let mut bike = Bicycle::default();
+    bike.set_source_metadata(true);
+    bike.process(src.path(), dst.path(), |_| ()).unwrap();
+    assert_eq!(
+        tree(dst.path())["info.txt"].as_deref(),
+        Some(format!("{} false dated", template.len()).as_str())
+    );
+    // With strict mode, this fails now that `source` is no longer inserted.
+    bike.set_source_metadata(false);
+    assert!(bike.process(src.path(), dst.path(), |_| ()).is_err());
+}
+
+mod single_extension {
+    use super::common::{fixture, tree};
+    use bicycle::{Bicycle, ProcessingError, SingleExtensionPolicy, TraversalError};
+
+    fn sample() -> tempfile::TempDir {
+        fixture(&[
+            ("LICENSE.hbs", "license"),
+            ("main.rs.hbs", "main"),
+            (".gitignore.hbs", "target"),
+        ])
+    }
+
+    fn process(policy: SingleExtensionPolicy) -> Result<Vec<String>, Box<ProcessingError>> {
+        let src = sample();
+        let dst = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_single_extension_policy(policy);
+        bike.process(src.path(), dst.path(), |_| ())
+            .map_err(Box::new)?;
+        Ok(tree(dst.path()).into_keys().collect())
+    }
+
+    #[test]
+    fn strip() {
+        assert_eq!(
+            process(SingleExtensionPolicy::Strip).unwrap(),
+            [".gitignore", "LICENSE", "main.rs"]
+        );
+    }
+
+    #[test]
+    fn default_extension() {
+        assert_eq!(
+            process(SingleExtensionPolicy::DefaultExtension("txt".to_owned())).unwrap(),
+            [".gitignore", "LICENSE.txt", "main.rs"]
+        );
+    }
+
+    #[test]
+    fn error() {
+        match process(SingleExtensionPolicy::Error).map_err(|err| *err) {
+            Err(ProcessingError::TraversalFailed {
+                cause: TraversalError::TemplateWithoutExtension { src },
+                ..
+            }) => assert!(src.ends_with("LICENSE.hbs")),
+            other => panic!("expected a missing extension error, but got {:?}", other),
+        }
+    }
+}
+
+mod sync {
+    use super::common::{fixture, tree};
+    use bicycle::{Bicycle, Tag};
+    use std::{fs, path::PathBuf};
+
+    fn setup() -> (tempfile::TempDir, tempfile::TempDir) {
+        let src = fixture(&[("keep.txt", "new"), ("dir/inner.txt", "inner")]);
+        let dst = fixture(&[
+            ("keep.txt", "old"),
+            ("stale.txt", "stale"),
+            ("dir/stale.txt", "stale"),
+            ("gone/deep.txt", "deep"),
+        ]);
+        (src, dst)
+    }
+
+    #[test]
+    fn sync_actions_is_a_dry_run() {
+        let (src, dst) = setup();
+        let before = tree(dst.path());
+        let actions = Bicycle::default()
+            .sync_actions(src.path(), dst.path(), |_| ())
+            .unwrap();
+        let mut deletions = actions
+            .iter()
+            .filter(|action| action.tag().delete())
+            .map(|action| action.dst().to_owned())
+            .collect::<Vec<_>>();
+        deletions.sort();
+        let expected: Vec<PathBuf> = vec![
+            dst.path().join("dir").join("stale.txt"),
+            dst.path().join("gone"),
+            dst.path().join("stale.txt"),
+        ];
+        assert_eq!(deletions, expected);
+        assert!(matches!(actions.back().unwrap().tag(), Tag::Delete));
+        assert_eq!(tree(dst.path()), before);
+    }
+
+    #[test]
+    fn sync() {
+        let (src, dst) = setup();
+        let mut confirmed = Vec::new();
+        Bicycle::default()
+            .sync(
+                src.path(),
+                dst.path(),
+                |_| (),
+                |path| {
+                    confirmed.push(path.to_owned());
+                    !path.ends_with("gone")
+                },
+            )
+            .unwrap();
+        assert_eq!(confirmed.len(), 3);
+        assert_eq!(
+            tree(dst.path()).into_keys().collect::<Vec<_>>(),
+            ["dir", "dir/inner.txt", "gone", "gone/deep.txt", "keep.txt"]
+        );
+        assert_eq!(
+            fs::read_to_string(dst.path().join("keep.txt")).unwrap(),
+            "new"
+        );
+    }
+}
+
+mod clean {
+    use super::common::{fixture, tree};
+    use bicycle::{Bicycle, ProcessingError};
+    use std::env;
+
+    #[test]
+    fn removes_stale_files() {
+        let src = fixture(&[("keep.txt", "new"), ("dir/inner.txt", "inner")]);
+        let dst = fixture(&[
+            ("keep.txt", "old"),
+            ("stale.txt", "stale"),
+            ("dir/stale.txt", "stale"),
+            ("gone/deep.txt", "deep"),
+        ]);
+        Bicycle::default()
+            .process_clean(src.path(), dst.path(), |_| ())
+            .unwrap();
+        assert_eq!(
+            tree(dst.path()),
+            tree(src.path()),
+            "only freshly generated files should remain"
+        );
+    }
+
+    #[test]
+    fn missing_dst() {
+        let src = fixture(&[("keep.txt", "new")]);
+        let dst = tempfile::tempdir().unwrap();
+        let dst = dst.path().join("out");
+        Bicycle::default()
+            .process_clean(src.path(), &dst, |_| ())
+            .unwrap();
+        assert_eq!(tree(&dst), tree(src.path()));
+    }
+
+    #[test]
+    fn refuses_cwd() {
+        let src = fixture(&[("keep.txt", "new")]);
+        let cwd = env::current_dir().unwrap();
+        for dst in [cwd.as_path(), ".".as_ref(), cwd.parent().unwrap()] {
+            match Bicycle::default().process_clean(src.path(), dst, |_| ()) {
+                Err(ProcessingError::CleanRefused { path, .. }) => {
+                    assert_eq!(path, cwd.canonicalize().unwrap())
+                }
+                other => panic!("unexpected result for {:?}: {:?}", dst, other),
+            }
+        }
+        assert!(cwd.join("Cargo.toml").exists());
+    }
+
+    #[test]
+    fn refuses_src() {
+        let root = fixture(&[("templates/keep.txt", "new"), ("other.txt", "other")]);
+        let src = root.path().join("templates");
+        let err = Bicycle::default()
+            .process_clean(&src, root.path(), |_| ())
+            .unwrap_err();
+        assert!(
+            matches!(err, ProcessingError::CleanRefused { .. }),
+            "unexpected error: {:?}",
+            err
+        );
+        assert_eq!(tree(root.path()).len(), 3);
+    }
+}
+
+mod diff {
+    use super::common::{fixture, tree};
+    use bicycle::{Bicycle, FileDiff, OverwritePolicy};
+
+    fn setup() -> (tempfile::TempDir, tempfile::TempDir) {
+        let src = fixture(&[
+            ("same.txt", "same"),
+            ("changed.txt.hbs", "hi {{name}}"),
+            ("new/new.txt", "new"),
+        ]);
+        let dst = fixture(&[
+            ("same.txt", "same"),
+            ("changed.txt", "hi rei"),
+            ("stale.txt", "stale"),
+            ("gone/deep.txt", "deep"),
+        ]);
+        (src, dst)
+    }
+
+    fn diff(bike: &Bicycle, src: &tempfile::TempDir, dst: &tempfile::TempDir) -> Vec<FileDiff> {
+        let mut diffs = bike
+            .diff(src.path(), dst.path(), super::insert_name)
+            .unwrap();
+        diffs.sort_by(|a, b| a.path().cmp(b.path()));
+        diffs
+    }
+
+    #[test]
+    fn categories() {
+        let (src, dst) = setup();
+        let before = tree(dst.path());
+        let path = |rel: &str| dst.path().join(rel);
+        assert_eq!(
+            diff(&Bicycle::default(), &src, &dst),
+            [
+                FileDiff::Modified(path("changed.txt")),
+                FileDiff::Removed(path("gone")),
+                FileDiff::Added(path("new").join("new.txt")),
+                FileDiff::Unchanged(path("same.txt")),
+                FileDiff::Removed(path("stale.txt")),
+            ]
+        );
+        assert_eq!(tree(dst.path()), before);
+    }
+
+    #[test]
+    fn overwrite_never() {
+        let (src, dst) = setup();
+        let mut bike = Bicycle::default();
+        bike.set_overwrite_policy(OverwritePolicy::Never);
+        assert!(matches!(
+            &diff(&bike, &src, &dst)[0],
+            FileDiff::Unchanged(path) if path.ends_with("changed.txt")
+        ));
+    }
+}
+
+mod merge {
+    use super::common::fixture;
+    use bicycle::{Bicycle, MANIFEST_FILE_NAME};
+    use std::{fs, path::PathBuf};
+
+    /// Generates `template` into a fresh destination, lets the user edit the
+    /// output with `edit`, and then merges `updated` into that.
+    fn merge(
+        template: &str,
+        edit: impl FnOnce(String) -> String,
+        updated: &str,
+    ) -> (String, Vec<PathBuf>) {
+        let src = fixture(&[("file.txt.hbs", template)]);
+        let dst = tempfile::tempdir().unwrap();
+        let out = dst.path().join("file.txt");
+        let bike = Bicycle::default();
+        let conflicts = bike
+            .process_merge(src.path(), dst.path(), super::insert_name)
+            .unwrap();
+        assert!(conflicts.is_empty());
+        assert!(dst.path().join(MANIFEST_FILE_NAME).is_file());
+        fs::write(&out, edit(fs::read_to_string(&out).unwrap())).unwrap();
+        fs::write(src.path().join("file.txt.hbs"), updated).unwrap();
+        let conflicts = bike
+            .process_merge(src.path(), dst.path(), super::insert_name)
+            .unwrap();
+        let conflicts = conflicts
+            .into_iter()
+            .map(|path| path.strip_prefix(dst.path()).unwrap().to_owned())
+            .collect();
+        (fs::read_to_string(&out).unwrap(), conflicts)
+    }
+
+    #[test]
+    fn clean() {
+        let (merged, conflicts) = merge(
+            "use {{name}};\n\nfn main() {}\n",
+            |out| out.replace("fn main() {}", "fn main() {\n    edited();\n}"),
+            "use {{name}};\nuse asuka;\n\nfn main() {}\n",
+        );
+        assert_eq!(
+            merged,
+            "use shinji;\nuse asuka;\n\nfn main() {\n    edited();\n}\n"
+        );
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn unedited() {
+        let (merged, conflicts) = merge("{{name}}\n", |out| out, "hi {{name}}\n");
+        assert_eq!(merged, "hi shinji\n");
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn conflict() {
+        let (merged, conflicts) = merge(
+            "first\n{{name}}\nlast",
+            |out| out.replace("shinji", "rei"),
+            "first\n{{name}} ikari\nlast",
+        );
+        assert_eq!(
+            merged,
+            "first\n<<<<<<< current\nrei\n=======\nshinji ikari\n>>>>>>> generated\nlast"
+        );
+        assert_eq!(conflicts, [PathBuf::from("file.txt")]);
+    }
+
+    #[test]
+    fn conflict_without_manifest() {
+        let src = fixture(&[("file.txt", "generated")]);
+        let dst = fixture(&[("file.txt", "existing")]);
+        let conflicts = Bicycle::default()
+            .process_merge(src.path(), dst.path(), |_| ())
+            .unwrap();
+        assert_eq!(conflicts, [dst.path().join("file.txt")]);
+        assert_eq!(
+            fs::read_to_string(dst.path().join("file.txt")).unwrap(),
+            "<<<<<<< current\nexisting\n=======\ngenerated\n>>>>>>> generated\n"
+        );
+    }
+}
+
+mod output_manifest {
+    use super::common::{fixture, tree};
+    use bicycle::{Bicycle, OutputManifest, ProcessingError};
+    use std::fs;
+
+    fn setup() -> (tempfile::TempDir, tempfile::TempDir, Bicycle) {
+        let src = fixture(&[
+            ("keep.txt", "new"),
+            ("dir/new.txt.hbs", "{{name}}"),
+            ("dir/deeper/deep.txt", "deep"),
+        ]);
+        let dst = fixture(&[("keep.txt", "old"), ("user.txt", "mine")]);
+        let mut bike = Bicycle::default();
+        bike.set_output_manifest(Some("manifest.json"));
+        bike.process(src.path(), dst.path(), super::insert_name)
+            .unwrap();
+        (src, dst, bike)
+    }
+
+    #[test]
+    fn records() {
+        let (_src, dst, _) = setup();
+        let mut manifest = OutputManifest::load(dst.path().join("manifest.json")).unwrap();
+        manifest.created_directories.sort();
+        manifest.created_files.sort();
+        assert_eq!(
+            manifest,
+            OutputManifest {
+                created_directories: vec!["dir".to_owned(), "dir/deeper".to_owned()],
+                created_files: vec!["dir/deeper/deep.txt".to_owned(), "dir/new.txt".to_owned()],
+                overwritten_files: vec!["keep.txt".to_owned()],
+            }
+        );
+    }
+
+    #[test]
+    fn remove() {
+        let (_src, dst, bike) = setup();
+        fs::write(dst.path().join("dir").join("user.txt"), "mine").unwrap();
+        bike.remove_from_manifest(dst.path().join("manifest.json"))
+            .unwrap();
+        assert_eq!(
+            tree(dst.path()),
+            [
+                ("dir", None),
+                ("dir/user.txt", Some("mine")),
+                ("keep.txt", Some("new")),
+                ("user.txt", Some("mine")),
+            ]
+            .iter()
+            .map(|(path, contents)| (path.to_string(), contents.map(ToOwned::to_owned)))
+            .collect()
+        );
+    }
+
+    #[test]
+    fn refuses_to_escape() {
+        let outside = fixture(&[("victim.txt", "precious")]);
+        let victim = outside.path().join("victim.txt");
+        let dst = fixture(&[("made.txt", "made")]);
+        let manifest = dst.path().join("manifest.json");
+        let relative = format!(
+            "../{}/victim.txt",
+            outside.path().file_name().unwrap().to_str().unwrap()
+        );
+        for key in [relative.as_str(), victim.to_str().unwrap()] {
+            let json = serde_json::json!({ "created_files": ["made.txt", key] });
+            fs::write(&manifest, json.to_string()).unwrap();
+            match Bicycle::default().remove_from_manifest(&manifest) {
+                Err(ProcessingError::ManifestParseFailed { path, .. }) => {
+                    assert_eq!(path, manifest)
+                }
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+        assert_eq!(fs::read_to_string(victim).unwrap(), "precious");
+        assert!(dst.path().join("made.txt").exists());
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let src = fixture(&[("keep.txt", "new")]);
+        let dst = tempfile::tempdir().unwrap();
+        Bicycle::default()
+            .process(src.path(), dst.path(), |_| ())
+            .unwrap();
+        assert_eq!(tree(dst.path()).len(), 1);
+    }
+}
+
+mod directory_path_is_file {
+    use super::common::{fixture, tree};
+    use bicycle::{Bicycle, ProcessingError};
+
+    #[test]
+    fn error() {
+        let src = fixture(&[("config/app.toml", "app")]);
+        let dst = fixture(&[("config", "was a file")]);
+        match Bicycle::default().process(src.path(), dst.path(), |_| ()) {
+            Err(ProcessingError::DirectoryPathIsFile { path, .. }) => {
+                assert_eq!(path, dst.path().join("config"))
+            }
+            other => panic!("expected a conflicting file error, but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn replace() {
+        let src = fixture(&[("config/app.toml", "app")]);
+        let dst = fixture(&[("config", "was a file")]);
+        let mut bike = Bicycle::default();
+        bike.set_replace_conflicting_files(true);
+        bike.process(src.path(), dst.path(), |_| ()).unwrap();
+        assert_eq!(tree(dst.path())["config/app.toml"].as_deref(), Some("app"));
+    }
+}
+
+mod mark_generated {
+    use super::common::fixture;
+    use bicycle::{Bicycle, GitAttributesMode};
+    use std::fs;
+
+    fn process(mode: GitAttributesMode) -> String {
+        let src = fixture(&[("gen.rs.hbs", "gen"), ("sub/my file[1].txt", "copied")]);
+        let dst = fixture(&[(".gitattributes", "*.png binary")]);
+        let mut bike = Bicycle::default();
+        bike.set_mark_generated(Some(mode));
+        bike.process(src.path(), dst.path(), |_| ()).unwrap();
+        // Running again shouldn't duplicate anything.
+        bike.process_streaming(src.path(), dst.path(), |_| ())
+            .unwrap();
+        fs::read_to_string(dst.path().join(".gitattributes")).unwrap()
+    }
+
+    #[test]
+    fn append() {
+        let mut lines = process(GitAttributesMode::Append)
+            .lines()
+            .map(ToOwned::to_owned)
+            .collect::<Vec<_>>();
+        lines[1..].sort();
+        assert_eq!(
+            lines,
+            [
+                "*.png binary",
+                r#""/sub/my file\\[1].txt" linguist-generated=true"#,
+                "/gen.rs linguist-generated=true",
+            ]
+        );
+    }
+
+    #[test]
+    fn overwrite() {
+        let contents = process(GitAttributesMode::Overwrite);
+        assert!(!contents.contains("binary"));
+        assert!(contents.contains("/gen.rs linguist-generated=true"));
+    }
+}
+
+mod dest_directives {
+    use super::common::{fixture, tree};
+    use bicycle::Bicycle;
+
+    #[test]
+    fn redirected() {
+        let src = fixture(&[
+            (
+                "templates/mod.hbs",
+                "@@ dest: src/{{name}}/mod.rs\r\npub mod {{name}};",
+            ),
+            ("plain.txt.hbs", "{{name}}"),
+        ]);
+        let dst = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_dest_directives(true);
+        bike.process(src.path(), dst.path(), |map| map.insert("name", "eva"))
+            .unwrap();
+        let tree = tree(dst.path());
+        assert_eq!(tree["src/eva/mod.rs"].as_deref(), Some("pub mod eva;"));
+        assert_eq!(tree["plain.txt"].as_deref(), Some("eva"));
+        assert!(!tree.contains_key("templates/mod"));
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let src = fixture(&[("mod.rs.hbs", "@@ dest: elsewhere.rs\nbody")]);
+        let dst = tempfile::tempdir().unwrap();
+        Bicycle::default()
+            .process(src.path(), dst.path(), |_| ())
+            .unwrap();
+        assert_eq!(
+            tree(dst.path())["mod.rs"].as_deref(),
+            Some("@@ dest: elsewhere.rs\nbody")
+        );
+    }
+}
+
+#[cfg(feature = "encoding_rs")]
+#[test]
+fn utf16_templates() {
+    use std::fs;
+    let src = tempfile::tempdir().unwrap();
+    let utf16 = |bom: [u8; 2], encode: fn(u16) -> [u8; 2]| {
+        let mut bytes = bom.to_vec();
+        for unit in "héllo {{name}}".encode_utf16() {
+            bytes.extend_from_slice(&encode(unit));
+        }
+        bytes
+    };
+    fs::write(
+        src.path().join("le.txt.hbs"),
+        utf16([0xFF, 0xFE], u16::to_le_bytes),
+    )
+    .unwrap();
+    fs::write(
+        src.path().join("be.txt.hbs"),
+        utf16([0xFE, 0xFF], u16::to_be_bytes),
+    )
+    .unwrap();
+    let dst = tempfile::tempdir().unwrap();
+    Bicycle::default()
+        .process(src.path(), dst.path(), insert_name)
+        .unwrap();
+    let tree = tree(dst.path());
+    assert_eq!(tree["le.txt"].as_deref(), Some("héllo shinji"));
+    assert_eq!(tree["be.txt"].as_deref(), Some("héllo shinji"));
+}
+
+#[test]
+fn path_helpers_and_partials() {
+    use bicycle::{handlebars::handlebars_helper, EscapeFn, HelperDef};
+
+    handlebars_helper!(snake: |s: str| s.to_lowercase().replace(' ', "_"));
+
+    let src = fixture(&[("{{snake name}}/{{snake name}}.rs.hbs", "mod")]);
+    let dst = tempfile::tempdir().unwrap();
+    let bike = Bicycle::try_new(
+        EscapeFn::None,
+        vec![("snake", Box::new(snake) as Box<dyn HelperDef + Send + Sync>)],
+        std::iter::once(("prefix", "gen")),
+        JsonMap::default(),
+    )
+    .unwrap();
+    bike.process(src.path(), dst.path(), |map| {
+        map.insert("name", "Third Impact")
+    })
+    .unwrap();
+    assert_eq!(
+        tree(dst.path())["third_impact/third_impact.rs"].as_deref(),
+        Some("mod")
+    );
+    // `>` isn't allowed in file names on Windows, so partials are checked on
+    // `transform_dst` directly.
+    let path = bike
+        .transform_dst(
+            std::path::Path::new("{{> prefix}}_{{snake name}}.rs"),
+            |map| map.insert("name", "Third Impact"),
+        )
+        .unwrap();
+    assert_eq!(path, std::path::Path::new("gen_third_impact.rs"));
+}
+
+mod max_template_bytes {
+    use super::common::{fixture, tree};
+    use bicycle::{Bicycle, OversizedTemplatePolicy, ProcessingError};
+
+    fn sample() -> tempfile::TempDir {
+        fixture(&[
+            ("small.txt.hbs", "{{name}}"),
+            ("huge.txt.hbs", "{{name}} is huge"),
+        ])
+    }
+
+    #[test]
+    fn copy() {
+        let (src, dst) = (sample(), tempfile::tempdir().unwrap());
+        let mut bike = Bicycle::default();
+        bike.set_max_template_bytes(Some((10, OversizedTemplatePolicy::Copy)));
+        bike.process(src.path(), dst.path(), |map| map.insert("name", "rei"))
+            .unwrap();
+        let tree = tree(dst.path());
+        assert_eq!(tree["small.txt"].as_deref(), Some("rei"));
+        assert_eq!(tree["huge.txt"].as_deref(), Some("{{name}} is huge"));
+    }
+
+    #[test]
+    fn error() {
+        let (src, dst) = (sample(), tempfile::tempdir().unwrap());
+        let mut bike = Bicycle::default();
+        bike.set_max_template_bytes(Some((10, OversizedTemplatePolicy::Error)));
+        match bike.process(src.path(), dst.path(), |map| map.insert("name", "rei")) {
+            Err(ProcessingError::TemplateTooLarge { path, size }) => {
+                assert!(path.ends_with("huge.txt.hbs"));
+                assert_eq!(size, 16);
+            }
+            other => panic!("expected a size error, but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn checked_before_directives() {
+        let src = fixture(&[
+            ("small.txt.hbs", "@@ dest: moved.txt\n{{name}}"),
+            ("huge.txt.hbs", "@@ dest: elsewhere.txt\n{{name}} is huge"),
+        ]);
+        // Too big to be read, and not valid UTF-8 either.
+        std::fs::write(
+            src.path().join("binary.txt.hbs"),
+            b"\xff\xfe\xfd huge binary template, honestly",
+        )
+        .unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_dest_directives(true);
+        bike.set_max_template_bytes(Some((30, OversizedTemplatePolicy::Copy)));
+        bike.process(src.path(), dst.path(), |map| map.insert("name", "rei"))
+            .unwrap();
+        let read = |path| std::fs::read_to_string(dst.path().join(path)).unwrap();
+        assert_eq!(read("moved.txt"), "rei");
+        assert_eq!(read("huge.txt"), "@@ dest: elsewhere.txt\n{{name}} is huge");
+        assert!(!dst.path().join("elsewhere.txt").exists());
+        assert_eq!(
+            std::fs::read(dst.path().join("binary.txt")).unwrap(),
+            b"\xff\xfe\xfd huge binary template, honestly"
+        );
+    }
+
+    #[test]
+    fn append() {
+        let src = fixture(&[("log.txt.append.hbs", "{{name}} is appended, but huge")]);
+        let dst = fixture(&[("log.txt", "existing\n")]);
+        let mut bike = Bicycle::default();
+        bike.set_append_ext(Some("append"));
+        bike.set_max_template_bytes(Some((10, OversizedTemplatePolicy::Error)));
+        assert!(matches!(
+            bike.process(src.path(), dst.path(), |map| map.insert("name", "rei")),
+            Err(ProcessingError::TemplateTooLarge { .. })
+        ));
+        bike.set_max_template_bytes(Some((10, OversizedTemplatePolicy::Copy)));
+        bike.process(src.path(), dst.path(), |map| map.insert("name", "rei"))
+            .unwrap();
+        assert_eq!(
+            tree(dst.path())["log.txt"].as_deref(),
+            Some("{{name}} is appended, but huge")
+        );
+    }
+}
+
+mod binary_templates {
+    use super::common::fixture;
+    use bicycle::{Bicycle, BinaryTemplatePolicy, ProcessingError};
+    use std::fs;
+
+    // Not valid UTF-8, like most image formats.
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    fn sample() -> tempfile::TempDir {
+        let src = fixture(&[("name.txt.hbs", "{{name}}")]);
+        fs::write(src.path().join("logo.png.hbs"), PNG).unwrap();
+        src
+    }
+
+    #[test]
+    fn error() {
+        let (src, dst) = (sample(), tempfile::tempdir().unwrap());
+        match Bicycle::default().process(src.path(), dst.path(), |map| map.insert("name", "rei")) {
+            Err(ProcessingError::TemplateIsBinary { path }) => {
+                assert_eq!(path, src.path().join("logo.png.hbs"))
+            }
+            other => panic!("expected a binary template error, but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn nul_bytes() {
+        let src = fixture(&[("nul.txt.hbs", "valid\0utf-8")]);
+        let dst = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            Bicycle::default().process(src.path(), dst.path(), |_| ()),
+            Err(ProcessingError::TemplateIsBinary { .. })
+        ));
+    }
+
+    #[test]
+    fn copy() {
+        let (src, dst) = (sample(), tempfile::tempdir().unwrap());
+        let mut bike = Bicycle::default();
+        bike.set_binary_template_policy(BinaryTemplatePolicy::Copy);
+        bike.process(src.path(), dst.path(), |map| map.insert("name", "rei"))
+            .unwrap();
+        assert_eq!(fs::read(dst.path().join("logo.png")).unwrap(), PNG);
+        assert_eq!(
+            fs::read_to_string(dst.path().join("name.txt")).unwrap(),
+            "rei"
+        );
+    }
+
+    #[test]
+    fn copy_with_directives() {
+        let (src, dst) = (sample(), tempfile::tempdir().unwrap());
+        fs::write(
+            src.path().join("name.txt.hbs"),
+            "@@ dest: moved.txt\n{{name}}",
+        )
+        .unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_binary_template_policy(BinaryTemplatePolicy::Copy);
+        bike.set_dest_directives(true);
+        bike.process(src.path(), dst.path(), |map| map.insert("name", "rei"))
+            .unwrap();
+        assert_eq!(fs::read(dst.path().join("logo.png")).unwrap(), PNG);
+        assert_eq!(
+            fs::read_to_string(dst.path().join("moved.txt")).unwrap(),
+            "rei"
+        );
+    }
+
+    #[cfg(feature = "serde_yaml")]
+    #[test]
+    fn copy_with_front_matter() {
+        let (src, dst) = (sample(), tempfile::tempdir().unwrap());
+        fs::write(
+            src.path().join("name.txt.hbs"),
+            "---\ndata:\n  name: asuka\n---\n{{name}}",
+        )
+        .unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_binary_template_policy(BinaryTemplatePolicy::Copy);
+        bike.set_front_matter(true);
+        bike.process(src.path(), dst.path(), |map| map.insert("name", "rei"))
+            .unwrap();
+        assert_eq!(fs::read(dst.path().join("logo.png")).unwrap(), PNG);
+        assert_eq!(
+            fs::read_to_string(dst.path().join("name.txt")).unwrap(),
+            "asuka"
+        );
+    }
+
+    #[test]
+    fn copy_append() {
+        let src = tempfile::tempdir().unwrap();
+        fs::write(src.path().join("logo.png.append.hbs"), PNG).unwrap();
+        let dst = fixture(&[("logo.png", "existing")]);
+        let mut bike = Bicycle::default();
+        bike.set_append_ext(Some("append"));
+        assert!(matches!(
+            bike.process(src.path(), dst.path(), |_| ()),
+            Err(ProcessingError::TemplateIsBinary { .. })
+        ));
+        bike.set_binary_template_policy(BinaryTemplatePolicy::Copy);
+        bike.process(src.path(), dst.path(), |_| ()).unwrap();
+        assert_eq!(fs::read(dst.path().join("logo.png")).unwrap(), PNG);
+    }
+}
+
+mod skip_sentinel {
+    use super::*;
+
+    fn sample() -> tempfile::TempDir {
+        fixture(&[
+            ("kept.txt.hbs", "{{name}}"),
+            ("empty.txt.hbs", "{{#if missing_ok}}never{{/if}}"),
+            ("marked.txt.hbs", "SKIP"),
+        ])
+    }
+
+    #[test]
+    fn empty() {
+        let src = sample();
+        let dst = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_skip_sentinel(Some(""));
+        let actions = bike
+            .process_returning(src.path(), dst.path(), |map| {
+                insert_name(map);
+                map.insert("missing_ok", false);
+            })
+            .unwrap();
+        let tree = tree(dst.path());
+        assert_eq!(tree["kept.txt"].as_deref(), Some("shinji"));
+        assert_eq!(tree["marked.txt"].as_deref(), Some("SKIP"));
+        assert!(!tree.contains_key("empty.txt"));
+        // The skipped template isn't reported as executed.
+        assert!(!actions
+            .iter()
+            .any(|action| action.dst().ends_with("empty.txt")));
+    }
+
+    #[test]
+    fn custom() {
+        let src = sample();
+        let dst = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_skip_sentinel(Some("SKIP"));
+        bike.process_streaming(src.path(), dst.path(), |map| {
+            insert_name(map);
+            map.insert("missing_ok", false);
+        })
+        .unwrap();
+        let tree = tree(dst.path());
+        assert_eq!(tree["empty.txt"].as_deref(), Some(""));
+        assert!(!tree.contains_key("marked.txt"));
+    }
+
+    #[test]
+    fn skip_empty() {
+        let src = fixture(&[
+            ("Dockerfile.hbs", "{{#if use_docker}}FROM rust{{/if}}\n"),
+            ("kept.txt.hbs", "{{name}}"),
+        ]);
+        let process = |use_docker: bool| {
+            let dst = tempfile::tempdir().unwrap();
+            let mut bike = Bicycle::default();
+            bike.set_skip_empty(true);
+            bike.process(src.path(), dst.path(), |map| {
+                insert_name(map);
+                map.insert("use_docker", use_docker);
+            })
+            .unwrap();
+            tree(dst.path())
+        };
+        let tree = process(false);
+        assert!(!tree.contains_key("Dockerfile"));
+        assert_eq!(tree["kept.txt"].as_deref(), Some("shinji"));
+        assert_eq!(process(true)["Dockerfile"].as_deref(), Some("FROM rust\n"));
+    }
+}
+
+mod nested_dst {
+    use super::*;
+
+    fn process_twice(include: bool) -> std::collections::BTreeMap<String, Option<String>> {
+        let src = fixture(&[("README.md", "static"), ("{{name}}.txt.hbs", "{{name}}")]);
+        let dst = src.path().join("build");
+        let mut bike = Bicycle::default();
+        bike.set_include_nested_dst(include);
+        bike.process(src.path(), &dst, insert_name).unwrap();
+        // Streaming would recurse into its own output forever if the
+        // destination were included.
+        if include {
+            bike.process(src.path(), &dst, insert_name).unwrap();
+        } else {
+            bike.process_streaming(src.path(), &dst, insert_name)
+                .unwrap();
+        }
+        tree(&dst)
+    }
+
+    #[test]
+    fn skipped() {
+        let tree = process_twice(false);
+        assert_eq!(tree["README.md"].as_deref(), Some("static"));
+        assert_eq!(tree["shinji.txt"].as_deref(), Some("shinji"));
+        assert!(!tree.keys().any(|path| path.starts_with("build")));
+    }
+
+    #[test]
+    fn included() {
+        let tree = process_twice(true);
+        assert_eq!(tree["build/README.md"].as_deref(), Some("static"));
+    }
+}
+
+#[test]
+fn pre_render() {
+    let src = fixture(&[
+        ("licensed.txt.hbs", "// LICENSE: {{nope}}\nhi {{name}}"),
+        ("plain.txt.hbs", "bye {{name}}"),
+        ("copied.txt", "// LICENSE: untouched"),
+    ]);
+    let dst = tempfile::tempdir().unwrap();
+    let mut bike = Bicycle::default();
+    bike.set_pre_render(Some(Box::new(|path, template| {
+        assert_eq!(path.extension().unwrap(), "hbs");
+        match template.strip_prefix("// LICENSE: ") {
+            Some(rest) => rest.split_once('\n').unwrap().1.to_owned(),
+            None => template,
+        }
+    })));
+    bike.process(src.path(), dst.path(), insert_name).unwrap();
+    let tree = tree(dst.path());
+    assert_eq!(tree["licensed.txt"].as_deref(), Some("hi shinji"));
+    assert_eq!(tree["plain.txt"].as_deref(), Some("bye shinji"));
+    assert_eq!(tree["copied.txt"].as_deref(), Some("// LICENSE: untouched"));
+}
+
+#[cfg(unix)]
+#[test]
+fn existing_root_in_read_only_parent() {
+    use std::{fs, os::unix::fs::PermissionsExt as _};
+
+    let src = fixture(&[("README.md", "static")]);
+    let parent = tempfile::tempdir().unwrap();
+    let dst = parent.path().join("out");
+    fs::create_dir(&dst).unwrap();
+    fs::set_permissions(parent.path(), fs::Permissions::from_mode(0o555)).unwrap();
+    let result = Bicycle::default().process(src.path(), &dst, |_| ());
+    fs::set_permissions(parent.path(), fs::Permissions::from_mode(0o755)).unwrap();
+    result.unwrap();
+    assert_eq!(tree(&dst)["README.md"].as_deref(), Some("static"));
+}
+
+#[cfg(unix)]
+#[test]
+fn templates_keep_permissions() {
+    use std::{fs, os::unix::fs::PermissionsExt as _};
+
+    let src = fixture(&[("gradlew.hbs", "#!/bin/sh\necho {{name}}")]);
+    fs::set_permissions(
+        src.path().join("gradlew.hbs"),
+        fs::Permissions::from_mode(0o755),
+    )
+    .unwrap();
+    let dst = tempfile::tempdir().unwrap();
+    Bicycle::default()
+        .process(src.path(), dst.path(), insert_name)
+        .unwrap();
+    let mode = fs::metadata(dst.path().join("gradlew"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o755);
+}
+
+#[cfg(unix)]
+#[test]
+fn read_only_templates_can_be_regenerated() {
+    use std::{fs, os::unix::fs::PermissionsExt as _};
+
+    let src = fixture(&[("locked.txt.hbs", "{{name}}")]);
+    fs::set_permissions(
+        src.path().join("locked.txt.hbs"),
+        fs::Permissions::from_mode(0o444),
+    )
+    .unwrap();
+    let dst = tempfile::tempdir().unwrap();
+    let bike = Bicycle::default();
+    bike.process(src.path(), dst.path(), insert_name).unwrap();
+    let mode = fs::metadata(dst.path().join("locked.txt"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o644);
+    bike.process(src.path(), dst.path(), |map| map.insert("name", "rei"))
+        .unwrap();
+    assert_eq!(tree(dst.path())["locked.txt"].as_deref(), Some("rei"));
+}
+
+#[test]
+fn update() {
+    let src = sample();
+    let dst = tempfile::tempdir().unwrap();
+    let bike = Bicycle::default();
+    bike.process(src.path(), dst.path(), insert_name).unwrap();
+    std::fs::write(dst.path().join("README.md"), "edited").unwrap();
+    std::fs::write(src.path().join("{{name}}/mod.rs.hbs"), "// {{name}}!").unwrap();
+    std::fs::write(src.path().join("new.txt.hbs"), "{{name}}").unwrap();
+    std::fs::create_dir(src.path().join("new")).unwrap();
+    std::fs::write(src.path().join("new/static.txt"), "static").unwrap();
+    bike.update(src.path(), dst.path(), insert_name).unwrap();
+    let tree = tree(dst.path());
+    assert_eq!(tree["README.md"].as_deref(), Some("edited"));
+    assert_eq!(tree["shinji/mod.rs"].as_deref(), Some("// shinji!"));
+    assert_eq!(tree["new.txt"].as_deref(), Some("shinji"));
+    assert!(tree.contains_key("new"));
+    assert!(!tree.contains_key("new/static.txt"));
+}
+
+#[test]
+fn process_action_with_tee() {
+    let src = fixture(&[("a.txt", "copied"), ("b.txt.hbs", "{{name}}")]);
+    let dst = tempfile::tempdir().unwrap();
+    let bike = Bicycle::default();
+    let actions = bicycle::traverse(
+        src.path(),
+        dst.path(),
+        bicycle::no_transform,
+        bicycle::DEFAULT_TEMPLATE_EXT,
+    )
+    .unwrap();
+    let mut teed = Vec::new();
+    for action in &actions {
+        let mut tee = Vec::new();
+        bike.process_action_with_tee(action, insert_name, &mut tee)
+            .unwrap();
+        if !action.tag().create_directory() {
+            let written = std::fs::read(action.dst()).unwrap();
+            assert_eq!(tee, written);
+        }
+        teed.extend(tee);
+    }
+    teed.sort_unstable();
+    let mut expected = b"copiedshinji".to_vec();
+    expected.sort_unstable();
+    assert_eq!(teed, expected);
+}
+
+#[test]
+fn failed_renders_leave_no_output() {
+    let src = fixture(&[("new.txt.hbs", "rendered first, then {{missing}}")]);
+    let dst = tempfile::tempdir().unwrap();
+    let bike = Bicycle::default();
+    assert!(matches!(
+        bike.process(src.path(), dst.path(), insert_name),
+        Err(ProcessingError::TemplateRenderFailed { .. })
+    ));
+    assert!(!dst.path().join("new.txt").exists());
+    // Existing files aren't streamed into, so they're left alone.
+    std::fs::write(dst.path().join("new.txt"), "existing").unwrap();
+    assert!(bike.process(src.path(), dst.path(), insert_name).is_err());
+    assert_eq!(tree(dst.path())["new.txt"].as_deref(), Some("existing"));
+}
+
+#[test]
+fn process_actions_with_hooks() {
+    let src = fixture(&[
+        ("a.txt", "copied"),
+        ("b.txt.hbs", "{{name}}"),
+        ("c.txt.hbs", "{{#if}}"),
+    ]);
+    let dst = tempfile::tempdir().unwrap();
+    let mut actions = Vec::from(
+        bicycle::traverse(src.path(), dst.path(), bicycle::no_transform, Some("hbs")).unwrap(),
+    );
+    // Make sure the broken template comes last.
+    actions.sort_by_key(|action| action.src().ends_with("c.txt.hbs"));
+    let (mut before, mut after, mut failed) = (0, 0, Vec::new());
+    let result = Bicycle::default().process_actions_with_hooks(
+        actions.iter(),
+        insert_name,
+        |_| before += 1,
+        |action, result| {
+            after += 1;
+            if result.is_err() {
+                failed.push(action.src().to_owned());
+            }
+        },
+    );
+    assert!(matches!(
+        result,
+        Err(ProcessingError::TemplateRenderFailed { .. })
+    ));
+    assert_eq!((before, after), (actions.len(), actions.len()));
+    assert_eq!(failed, [src.path().join("c.txt.hbs")]);
+}
+
+#[test]
+fn render_action() {
+    let src = fixture(&[("a.txt", "copied"), ("b.txt.hbs", "{{name}}")]);
+    let dst = tempfile::tempdir().unwrap();
+    let out = dst.path().join("out");
+    let bike = Bicycle::default();
+    let actions = bicycle::traverse(
+        src.path(),
+        &out,
+        bicycle::no_transform,
+        bicycle::DEFAULT_TEMPLATE_EXT,
+    )
+    .unwrap();
+    let mut rendered = actions
+        .iter()
+        .map(|action| {
+            let bytes = bike.render_action(action, insert_name).unwrap();
+            (action.tag(), bytes)
+        })
+        .collect::<Vec<_>>();
+    rendered.sort_by_key(|(_, bytes)| bytes.clone());
+    assert_eq!(
+        rendered,
+        vec![
+            (Tag::CreateDirectory, None),
+            (Tag::CopyFile, Some(b"copied".to_vec())),
+            (Tag::WriteTemplate, Some(b"shinji".to_vec())),
+        ]
+    );
+    assert!(!out.exists());
+}
+
+#[test]
+#[allow(clippy::result_large_err)]
+fn post_render() {
+    let src = fixture(&[
+        ("a.txt.hbs", "hello {{name}}"),
+        ("b.md.hbs", "hello {{name}}"),
+        ("c.txt", "copied"),
+    ]);
+    let dst = tempfile::tempdir().unwrap();
+    let mut bike = Bicycle::default();
+    bike.set_post_render(Some(Box::new(|dst, rendered| {
+        Ok(if dst.extension().is_some_and(|ext| ext == "txt") {
+            rendered.to_uppercase()
+        } else {
+            rendered
+        })
+    })));
+    bike.process(src.path(), dst.path(), insert_name).unwrap();
+    let tree = tree(dst.path());
+    assert_eq!(tree["a.txt"].as_deref(), Some("HELLO SHINJI"));
+    assert_eq!(tree["b.md"].as_deref(), Some("hello shinji"));
+    // Copied files are never post-processed.
+    assert_eq!(tree["c.txt"].as_deref(), Some("copied"));
+}
+
+#[test]
+#[allow(clippy::result_large_err)]
+fn post_render_failure() {
+    let src = fixture(&[("a.txt.hbs", "hello")]);
+    let dst = tempfile::tempdir().unwrap();
+    let mut bike = Bicycle::default();
+    bike.set_post_render(Some(Box::new(|dst, _| {
+        Err(ProcessingError::PostRenderFailed {
+            dst: dst.to_owned(),
+            cause: "formatter exploded".into(),
+        })
+    })));
+    assert!(matches!(
+        bike.process(src.path(), dst.path(), insert_name),
+        Err(ProcessingError::PostRenderFailed { .. })
+    ));
+    assert!(!dst.path().join("a.txt").exists());
+}
+
+#[cfg(feature = "globset")]
+mod matching {
+    use super::*;
+
+    fn sample() -> tempfile::TempDir {
+        fixture(&[
+            ("README.md", "readme"),
+            ("src/main.rs.hbs", "// {{name}}"),
+            ("src/bin/tool.rs", "tool"),
+            ("docs/guide.md", "guide"),
+        ])
+    }
+
+    #[test]
+    fn subtree() {
+        let src = sample();
+        let dst = tempfile::tempdir().unwrap();
+        Bicycle::default()
+            .process_matching(src.path(), dst.path(), "src/**", insert_name)
+            .unwrap();
+        let tree = tree(dst.path());
+        assert_eq!(
+            tree.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["src", "src/bin", "src/bin/tool.rs", "src/main.rs"]
+        );
+        assert_eq!(tree["src/main.rs"].as_deref(), Some("// shinji"));
+    }
+
+    #[test]
+    fn extension() {
+        let src = sample();
+        let dst = tempfile::tempdir().unwrap();
+        Bicycle::default()
+            .process_matching(src.path(), dst.path(), "**/*.md", insert_name)
+            .unwrap();
+        assert_eq!(
+            tree(dst.path())
+                .keys()
+                .map(String::as_str)
+                .collect::<Vec<_>>(),
+            ["README.md", "docs", "docs/guide.md"]
+        );
+    }
+
+    #[test]
+    fn invalid_glob() {
+        let src = sample();
+        let dst = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            Bicycle::default().process_matching(src.path(), dst.path(), "src/[", insert_name),
+            Err(ProcessingError::GlobParseFailed { .. })
+        ));
+    }
+}
+
+#[cfg(feature = "serde_yaml")]
+mod front_matter {
+    use super::*;
+
+    #[allow(clippy::result_large_err)]
+    fn process(
+        files: &[(&str, &str)],
+    ) -> Result<std::collections::BTreeMap<String, Option<String>>, ProcessingError> {
+        let src = fixture(files);
+        let dst = tempfile::tempdir().unwrap();
+        Bicycle::default().process_with_front_matter(src.path(), dst.path(), insert_name)?;
+        Ok(tree(dst.path()))
+    }
+
+    #[test]
+    fn filename_override() {
+        let tree = process(&[(
+            "sub/template.rs.hbs",
+            "---\nfilename: \"{{name}}.rs\"\n---\n// {{name}}\n",
+        )])
+        .unwrap();
+        assert_eq!(tree["sub/shinji.rs"].as_deref(), Some("// shinji\n"));
+        assert!(!tree.contains_key("sub/template.rs"));
+    }
+
+    #[test]
+    fn data_and_skip() {
+        let tree = process(&[
+            (
+                "greeting.txt.hbs",
+                "---\ndata:\n  greeting: Hello\n---\n{{greeting}}, {{name}}!",
+            ),
+            ("skipped.txt.hbs", "---\nskip: true\n---\nnever"),
+        ])
+        .unwrap();
+        assert_eq!(tree["greeting.txt"].as_deref(), Some("Hello, shinji!"));
+        assert!(!tree.contains_key("skipped.txt"));
+    }
+
+    #[test]
+    fn missing() {
+        let tree = process(&[
+            ("plain.txt.hbs", "{{name}}"),
+            ("rule.md.hbs", "---\n{{name}}"),
+        ])
+        .unwrap();
+        assert_eq!(tree["plain.txt"].as_deref(), Some("shinji"));
+        // An unclosed block isn't front matter.
+        assert_eq!(tree["rule.md"].as_deref(), Some("---\nshinji"));
+    }
+
+    #[test]
+    fn malformed() {
+        match process(&[("broken.txt.hbs", "---\nfilename: [\n---\nbody")]) {
+            Err(ProcessingError::FrontMatterParseFailed { path, .. }) => {
+                assert!(path.ends_with("broken.txt.hbs"))
+            }
+            other => panic!("expected a front matter error, but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let src = fixture(&[("a.txt.hbs", "---\nskip: true\n---\nkept")]);
+        let dst = tempfile::tempdir().unwrap();
+        Bicycle::default()
+            .process(src.path(), dst.path(), insert_name)
+            .unwrap();
+        assert_eq!(
+            tree(dst.path())["a.txt"].as_deref(),
+            Some("---\nskip: true\n---\nkept")
+        );
+    }
+}
+
+mod line_endings {
+    use super::*;
+    use bicycle::LineEndings;
+
+    fn process(line_endings: LineEndings, template: &str) -> (String, String) {
+        let src = fixture(&[("a.txt.hbs", template), ("b.txt", template)]);
+        let dst = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_line_endings(line_endings);
+        bike.process(src.path(), dst.path(), insert_name).unwrap();
+        let mut tree = tree(dst.path());
+        (
+            tree.remove("a.txt").unwrap().unwrap(),
+            tree.remove("b.txt").unwrap().unwrap(),
+        )
+    }
+
+    #[test]
+    fn crlf_to_lf() {
+        let (rendered, copied) = process(LineEndings::Lf, "{{name}}\r\nrei\r\n");
+        assert_eq!(rendered, "shinji\nrei\n");
+        // Copied files could be binary, so they're left alone.
+        assert_eq!(copied, "{{name}}\r\nrei\r\n");
+    }
+
+    #[test]
+    fn lf_to_crlf() {
+        let (rendered, copied) = process(LineEndings::CrLf, "{{name}}\nrei\r\n");
+        assert_eq!(rendered, "shinji\r\nrei\r\n");
+        assert_eq!(copied, "{{name}}\nrei\r\n");
+    }
+
+    #[test]
+    fn preserve() {
+        let (rendered, _) = process(LineEndings::Preserve, "{{name}}\nrei\r\n");
+        assert_eq!(rendered, "shinji\nrei\r\n");
+    }
+}
+
+#[test]
+fn hand_built_actions() {
+    use bicycle::Action;
+
+    let src = fixture(&[("greeting.hbs", "Hello {{name}}!"), ("data.bin", "raw")]);
+    let dst = tempfile::tempdir().unwrap();
+    let actions = [
+        Action::from_parts("", dst.path().join("out"), Tag::CreateDirectory),
+        Action::from_parts(
+            src.path().join("greeting.hbs"),
+            dst.path().join("out/hello.txt"),
+            Tag::WriteTemplate,
+        ),
+        Action::from_parts(
+            src.path().join("data.bin"),
+            dst.path().join("out/copy.bin"),
+            Tag::CopyFile,
+        ),
+    ];
+    let bike = Bicycle::default();
+    for action in &actions {
+        bike.process_action(action, insert_name).unwrap();
+    }
+    let tree = tree(dst.path());
+    assert_eq!(tree["out/hello.txt"].as_deref(), Some("Hello shinji!"));
+    assert_eq!(tree["out/copy.bin"].as_deref(), Some("raw"));
+}
+
+#[test]
+fn process_file() {
+    let src = fixture(&[("a.txt.hbs", "Hello {{name}}!"), ("b.txt", "{{name}}")]);
+    let dst = tempfile::tempdir().unwrap();
+    let bike = Bicycle::default();
+    bike.process_file(
+        src.path().join("a.txt.hbs"),
+        dst.path().join("nested/deeper/greeting.txt"),
+        insert_name,
+    )
+    .unwrap();
+    bike.process_file(
+        src.path().join("b.txt"),
+        dst.path().join("b.txt"),
+        insert_name,
+    )
+    .unwrap();
+    let tree = tree(dst.path());
+    assert_eq!(
+        tree["nested/deeper/greeting.txt"].as_deref(),
+        Some("Hello shinji!")
+    );
+    assert_eq!(tree["b.txt"].as_deref(), Some("{{name}}"));
+}
+
+#[test]
+fn render_file() {
+    let src = fixture(&[
+        ("a.txt.hbs", "Hello {{name}}!"),
+        ("b.txt.hbs", "{{missing}}"),
+    ]);
+    let bike = Bicycle::default();
+    assert_eq!(
+        bike.render_file(src.path().join("a.txt.hbs"), insert_name)
+            .unwrap(),
+        "Hello shinji!"
+    );
+    // There's no destination to report, so none is.
+    let broken = src.path().join("b.txt.hbs");
+    match bike.render_file(&broken, insert_name) {
+        Err(err @ ProcessingError::TemplateRenderFailed { dst: None, .. }) => {
+            let prefix = format!("Failed to render template from {:?}: ", broken);
+            assert!(err.to_string().starts_with(&prefix), "{}", err)
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(matches!(
+        bike.render_file(src.path().join("c.txt.hbs"), insert_name),
+        Err(ProcessingError::TemplateReadFailed { .. })
+    ));
+}
+
+#[test]
+fn destination_metadata() {
+    let src = fixture(&[
+        (
+            "status.txt.hbs",
+            "{{#if destination.exists}}regenerated{{else}}fresh{{/if}}",
+        ),
+        (
+            "lock.txt.hbs",
+            r#"{{#if (dest_exists "status.txt")}}after{{else}}first{{/if}} {{dest_exists}}"#,
+        ),
+    ]);
+    let dst = tempfile::tempdir().unwrap();
+    let mut bike = Bicycle::default();
+    bike.set_destination_metadata(true);
+    assert!(bike
+        .registered_helpers()
+        .contains(&"dest_exists".to_owned()));
+    let actions = bicycle::traverse(
+        src.path(),
+        dst.path(),
+        bicycle::no_transform,
+        bicycle::DEFAULT_TEMPLATE_EXT,
+    )
+    .unwrap();
+    // Render only `lock.txt` first, so `status.txt` doesn't exist yet.
+    let lock = actions
+        .iter()
+        .find(|action| action.src().ends_with("lock.txt.hbs"))
+        .unwrap();
+    bike.process_action(lock, |_| ()).unwrap();
+    assert_eq!(tree(dst.path())["lock.txt"].as_deref(), Some("first false"));
+    bike.process(src.path(), dst.path(), |_| ()).unwrap();
+    bike.process(src.path(), dst.path(), |_| ()).unwrap();
+    let tree = tree(dst.path());
+    assert_eq!(tree["status.txt"].as_deref(), Some("regenerated"));
+    assert_eq!(tree["lock.txt"].as_deref(), Some("after true"));
+}
+
+#[test]
+fn destination_metadata_disabled_again() {
+    let src = fixture(&[("lock.txt.hbs", r#"{{dest_exists "Cargo.lock"}}"#)]);
+    let dst = tempfile::tempdir().unwrap();
+    let mut bike = Bicycle::default();
+    bike.set_destination_metadata(true);
+    bike.set_destination_metadata(false);
+    assert!(!bike
+        .registered_helpers()
+        .contains(&"dest_exists".to_owned()));
+    match bike.process(src.path(), dst.path(), |_| ()) {
+        Err(err @ ProcessingError::TemplateRenderFailed { .. }) => assert!(
+            format!("{:?}", err).contains("destination metadata is disabled"),
+            "{:?}",
+            err
+        ),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+mod append {
+    use super::*;
+
+    fn sample() -> tempfile::TempDir {
+        fixture(&[
+            ("lib.rs.hbs", "// {{name}}\n"),
+            ("a/lib.rs.append.hbs", "mod a;\n"),
+            ("b/log.txt.append.hbs", "{{name}}\n"),
+        ])
+    }
+
+    #[test]
+    fn appended() {
+        let src = sample();
+        let dst = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dst.path().join("a")).unwrap();
+        std::fs::write(dst.path().join("a/lib.rs"), "mod existing;\n").unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_append_ext(Some("append"));
+        bike.process(src.path(), dst.path(), insert_name).unwrap();
+        bike.process(src.path(), dst.path(), insert_name).unwrap();
+        let tree = tree(dst.path());
+        assert_eq!(tree["lib.rs"].as_deref(), Some("// shinji\n"));
+        assert_eq!(
+            tree["a/lib.rs"].as_deref(),
+            Some("mod existing;\nmod a;\nmod a;\n")
+        );
+        // Missing files are created.
+        assert_eq!(tree["b/log.txt"].as_deref(), Some("shinji\nshinji\n"));
+    }
+
+    #[test]
+    fn dedupe() {
+        let src = sample();
+        let dst = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_append_ext(Some("append"));
+        bike.set_append_dedupe(true);
+        bike.process(src.path(), dst.path(), insert_name).unwrap();
+        let actions = bike
+            .process_returning(src.path(), dst.path(), insert_name)
+            .unwrap();
+        assert!(!actions.iter().any(|action| action.tag().append_template()));
+        let tree = tree(dst.path());
+        assert_eq!(tree["a/lib.rs"].as_deref(), Some("mod a;\n"));
+        assert_eq!(tree["b/log.txt"].as_deref(), Some("shinji\n"));
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let src = sample();
+        let dst = tempfile::tempdir().unwrap();
+        Bicycle::default()
+            .process(src.path(), dst.path(), insert_name)
+            .unwrap();
+        assert!(tree(dst.path()).contains_key("a/lib.rs.append"));
+    }
+}
+
+mod lenient_paths {
+    use super::common::{fixture, tree};
+    use bicycle::{Bicycle, ProcessingError, TraversalError};
+
+    #[test]
+    fn strict_by_default() {
+        let src = fixture(&[("{{module}}/main.rs.hbs", "fn main() {}")]);
+        let dst = tempfile::tempdir().unwrap();
+        let err = Bicycle::default()
+            .process(src.path(), dst.path(), |_| ())
+            .unwrap_err();
+        match err {
+            ProcessingError::TraversalFailed {
+                cause: TraversalError::PathTransformFailed { cause, .. },
+                ..
+            } => assert_eq!(cause.strict_missing_path().as_deref(), Some("module")),
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_path_variables_are_empty() {
+        let src = fixture(&[
+            ("{{module}}/main.rs.hbs", "fn main() {}"),
+            ("{{name}}_{{suffix}}.txt", "static"),
+        ]);
+        let dst = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_lenient_paths(true);
+        bike.process(src.path(), dst.path(), |map| map.insert("name", "rei"))
+            .unwrap();
+        let tree = tree(dst.path());
+        assert_eq!(tree["main.rs"].as_deref(), Some("fn main() {}"));
+        assert_eq!(tree["rei_.txt"].as_deref(), Some("static"));
+    }
+
+    #[test]
+    fn contents_stay_strict() {
+        let src = fixture(&[("{{module}}/main.rs.hbs", "{{body}}")]);
+        let dst = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_lenient_paths(true);
+        let err = bike.process(src.path(), dst.path(), |_| ()).unwrap_err();
+        assert!(
+            matches!(err, ProcessingError::TemplateRenderFailed { .. }),
+            "unexpected error: {:?}",
+            err
+        );
+    }
+}
+
+#[test]
+fn check() {
+    let src = fixture(&[
+        ("ok.txt.hbs", "{{name}}"),
+        ("missing.txt.hbs", "{{name}} {{surname}}"),
+        ("nested/broken.txt.hbs", "{{#if name}}"),
+        ("static.txt", "{{nope}}"),
+    ]);
+    let failures = Bicycle::default().check(src.path(), insert_name).unwrap();
+    assert_eq!(
+        failures.keys().cloned().collect::<Vec<_>>(),
+        vec![
+            src.path().join("missing.txt.hbs"),
+            src.path().join("nested/broken.txt.hbs"),
+        ]
+    );
+    match &failures[&src.path().join("missing.txt.hbs")] {
+        ProcessingError::TemplateRenderFailed { cause, .. } => {
+            assert_eq!(cause.strict_missing_path().as_deref(), Some("surname"))
+        }
+        other => panic!("unexpected error: {:?}", other),
+    }
+    assert!(!src.path().join("ok.txt").exists());
+}
+
+mod overwrite_policy {
+    use super::*;
+    use bicycle::OverwritePolicy;
+    use std::{fs, time::Duration};
+
+    fn run(policy: OverwritePolicy) -> tempfile::TempDir {
+        let src = fixture(&[("copied.txt", "new"), ("rendered.txt.hbs", "{{name}}")]);
+        let dst = fixture(&[("copied.txt", "old"), ("rendered.txt", "old")]);
+        Bicycle::default()
+            .process_with_policy(src.path(), dst.path(), policy, insert_name)
+            .unwrap();
+        dst
+    }
+
+    fn read(dst: &tempfile::TempDir, name: &str) -> String {
+        fs::read_to_string(dst.path().join(name)).unwrap()
+    }
+
+    #[test]
+    fn always() {
+        let dst = run(OverwritePolicy::Always);
+        assert_eq!(read(&dst, "copied.txt"), "new");
+        assert_eq!(read(&dst, "rendered.txt"), "shinji");
+    }
+
+    #[test]
+    fn never() {
+        let dst = run(OverwritePolicy::Never);
+        assert_eq!(read(&dst, "copied.txt"), "old");
+        assert_eq!(read(&dst, "rendered.txt"), "old");
+    }
+
+    #[test]
+    fn never_creates_missing_files() {
+        let src = fixture(&[("new.txt.hbs", "{{name}}")]);
+        let dst = tempfile::tempdir().unwrap();
+        Bicycle::default()
+            .process_with_policy(src.path(), dst.path(), OverwritePolicy::Never, insert_name)
+            .unwrap();
+        assert_eq!(read(&dst, "new.txt"), "shinji");
+    }
+
+    #[test]
+    fn if_changed() {
+        let src = fixture(&[("changed.txt.hbs", "{{name}}"), ("same.txt.hbs", "same")]);
+        let dst = fixture(&[("changed.txt", "old"), ("same.txt", "same")]);
+        let old = fs::metadata(dst.path().join("same.txt"))
+            .and_then(|metadata| metadata.modified())
+            .unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        let mut bike = Bicycle::default();
+        bike.set_overwrite_policy(OverwritePolicy::IfChanged);
+        bike.process(src.path(), dst.path(), insert_name).unwrap();
+        assert_eq!(read(&dst, "changed.txt"), "shinji");
+        assert_eq!(read(&dst, "same.txt"), "same");
+        let new = fs::metadata(dst.path().join("same.txt"))
+            .and_then(|metadata| metadata.modified())
+            .unwrap();
+        assert_eq!(old, new);
+    }
+}
+
+mod transactional {
+    use super::*;
+    use bicycle::{no_transform, traverse, Action};
+    use std::fs;
+
+    fn actions(src: &std::path::Path, dst: &std::path::Path) -> Vec<Action> {
+        let mut actions = Vec::from(traverse(src, dst, no_transform, Some("hbs")).unwrap());
+        // Make sure the failing action comes last.
+        actions.sort_by_key(|action| action.dst().ends_with("blocked.txt"));
+        actions
+    }
+
+    #[test]
+    fn rolls_back() {
+        let src = fixture(&[
+            ("existing.txt.hbs", "{{name}}"),
+            ("new.txt", "new"),
+            ("sub/deeper/new.txt.hbs", "{{name}}"),
+            ("blocked.txt.hbs", "{{name}}"),
+        ]);
+        // Writing to a directory fails.
+        let dst = fixture(&[("existing.txt", "old"), ("blocked.txt/keep.txt", "keep")]);
+        let before = tree(dst.path());
+        match Bicycle::default()
+            .process_actions_transactional(actions(src.path(), dst.path()).iter(), insert_name)
+        {
+            Err(ProcessingError::TemplateWriteFailed { dst: failed, .. }) => {
+                assert_eq!(failed, dst.path().join("blocked.txt"))
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(tree(dst.path()), before);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restores_symlinks() {
+        use std::{os::unix::fs::symlink, path::Path};
+
+        let src = fixture(&[
+            ("linked.txt.hbs", "{{name}}"),
+            ("dangling.txt", "new"),
+            ("blocked.txt.hbs", "{{name}}"),
+        ]);
+        let dst = fixture(&[("real.txt", "old"), ("blocked.txt/keep.txt", "keep")]);
+        symlink("real.txt", dst.path().join("linked.txt")).unwrap();
+        symlink("missing.txt", dst.path().join("dangling.txt")).unwrap();
+        assert!(Bicycle::default()
+            .process_actions_transactional(actions(src.path(), dst.path()).iter(), insert_name)
+            .is_err());
+        assert_eq!(
+            fs::read_link(dst.path().join("linked.txt")).unwrap(),
+            Path::new("real.txt")
+        );
+        assert_eq!(
+            fs::read_link(dst.path().join("dangling.txt")).unwrap(),
+            Path::new("missing.txt")
+        );
+        assert_eq!(
+            fs::read_to_string(dst.path().join("real.txt")).unwrap(),
+            "old"
+        );
+        assert!(!dst.path().join("missing.txt").exists());
+    }
+
+    #[test]
+    fn commits() {
+        let src = fixture(&[("existing.txt.hbs", "{{name}}"), ("sub/new.txt", "new")]);
+        let dst = fixture(&[("existing.txt", "old"), ("stale/old.txt", "old")]);
+        let bike = Bicycle::default();
+        let actions = bike
+            .sync_actions(src.path(), dst.path(), insert_name)
+            .unwrap();
+        bike.process_actions_transactional(actions.iter(), insert_name)
+            .unwrap();
+        let tree = tree(dst.path());
+        assert_eq!(
+            tree.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["existing.txt", "sub", "sub/new.txt"]
+        );
+        assert_eq!(tree["existing.txt"].as_deref(), Some("shinji"));
+    }
+
+    #[test]
+    fn restores_deletions() {
+        let src = fixture(&[("missing.txt", "")]);
+        let dst = fixture(&[("existing.txt", "old"), ("stale/old.txt", "old")]);
+        let before = tree(dst.path());
+        let bike = Bicycle::default();
+        let mut actions = Vec::from(
+            bike.sync_actions(src.path(), dst.path(), insert_name)
+                .unwrap(),
+        );
+        // Delete first, so that there's something to restore once copying
+        // the missing file fails.
+        actions.sort_by_key(|action| !action.tag().delete());
+        fs::remove_file(src.path().join("missing.txt")).unwrap();
+        assert!(matches!(
+            bike.process_actions_transactional(actions.iter(), insert_name),
+            Err(ProcessingError::FileCopyFailed { .. })
+        ));
+        assert_eq!(tree(dst.path()), before);
+    }
+}
+
+#[cfg(unix)]
+mod symlinks {
+    use super::*;
+    use bicycle::{FileDiff, OverwritePolicy};
+    use std::{fs, os::unix::fs::symlink, path::Path};
+
+    fn linked() -> tempfile::TempDir {
+        let src = fixture(&[("real/file.txt", "contents")]);
+        symlink("real/file.txt", src.path().join("file-link.txt")).unwrap();
+        symlink("real", src.path().join("dir-link")).unwrap();
+        src
+    }
+
+    #[test]
+    fn followed_by_default() {
+        let src = linked();
+        let dst = tempfile::tempdir().unwrap();
+        Bicycle::default()
+            .process(src.path(), dst.path(), insert_name)
+            .unwrap();
+        for path in ["file-link.txt", "dir-link", "dir-link/file.txt"] {
+            let metadata = fs::symlink_metadata(dst.path().join(path)).unwrap();
+            assert!(!metadata.file_type().is_symlink(), "{} is a symlink", path);
+        }
+        let tree = tree(dst.path());
+        assert_eq!(tree["file-link.txt"].as_deref(), Some("contents"));
+        assert_eq!(tree["dir-link/file.txt"].as_deref(), Some("contents"));
+    }
+
+    #[test]
+    fn preserved() {
+        let src = linked();
+        // Existing files are replaced, just like when copying.
+        let dst = fixture(&[("file-link.txt", "old")]);
+        let mut bike = Bicycle::default();
+        bike.set_preserve_symlinks(true);
+        bike.process(src.path(), dst.path(), insert_name).unwrap();
+        assert_eq!(
+            fs::read_link(dst.path().join("file-link.txt")).unwrap(),
+            Path::new("real/file.txt")
+        );
+        assert_eq!(
+            fs::read_link(dst.path().join("dir-link")).unwrap(),
+            Path::new("real")
+        );
+        assert_eq!(
+            fs::read_to_string(dst.path().join("file-link.txt")).unwrap(),
+            "contents"
+        );
+        // The linked directory isn't traversed.
+        let planned = bike
+            .process_dry_run(src.path(), "out", insert_name)
+            .unwrap();
+        let links = planned
+            .iter()
+            .filter(|planned| planned.dst().starts_with("out/dir-link"))
+            .map(|planned| (planned.tag(), planned.dst().to_owned()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            links,
+            [(Tag::Symlink, Path::new("out/dir-link").to_owned())]
+        );
+    }
+
+    #[test]
+    fn rendered_targets() {
+        let src = fixture(&[("{{name}}/file.txt", "contents")]);
+        symlink("{{name}}/file.txt", src.path().join("relative.txt")).unwrap();
+        symlink(
+            src.path().join("{{name}}/file.txt"),
+            src.path().join("absolute.txt"),
+        )
+        .unwrap();
+        symlink("/opt/{{name}}", src.path().join("outside")).unwrap();
+        let dst = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_preserve_symlinks(true);
+        bike.process(src.path(), dst.path(), insert_name).unwrap();
+        assert_eq!(
+            fs::read_link(dst.path().join("relative.txt")).unwrap(),
+            Path::new("shinji/file.txt")
+        );
+        assert_eq!(
+            fs::read_link(dst.path().join("absolute.txt")).unwrap(),
+            dst.path().join("shinji/file.txt")
+        );
+        assert_eq!(
+            fs::read_link(dst.path().join("outside")).unwrap(),
+            Path::new("/opt/shinji")
+        );
+        for link in ["relative.txt", "absolute.txt"] {
+            assert_eq!(
+                fs::read_to_string(dst.path().join(link)).unwrap(),
+                "contents"
+            );
+        }
+        // Rendered targets are what the existing links are compared against.
+        bike.set_overwrite_policy(OverwritePolicy::IfChanged);
+        assert!(bike
+            .process_dry_run(src.path(), dst.path(), insert_name)
+            .unwrap()
+            .iter()
+            .all(|planned| !planned.tag().symlink()));
+        assert!(bike
+            .diff(src.path(), dst.path(), insert_name)
+            .unwrap()
+            .iter()
+            .all(|diff| matches!(diff, FileDiff::Unchanged(_))));
+    }
+}
+
+#[test]
+fn dry_run() {
+    let src = fixture(&[
+        ("hello.txt.hbs", "{{name}}"),
+        ("nested/static.txt", "static"),
+    ]);
+    let dst = tempfile::tempdir().unwrap();
+    let out = dst.path().join("out");
+    let bike = Bicycle::default();
+    let mut planned = bike
+        .process_dry_run(src.path(), &out, insert_name)
+        .unwrap()
+        .into_iter()
+        .map(|planned| (planned.tag(), planned.dst().to_owned()))
+        .collect::<Vec<_>>();
+    planned.sort_by(|a, b| a.1.cmp(&b.1));
+    assert_eq!(
+        planned,
+        vec![
+            (Tag::CreateDirectory, out.clone()),
+            (Tag::WriteTemplate, out.join("hello.txt")),
+            (Tag::CreateDirectory, out.join("nested")),
+            (Tag::CopyFile, out.join("nested/static.txt")),
+        ]
+    );
+    assert!(!out.exists());
+
+    std::fs::write(src.path().join("broken.txt.hbs"), "{{#if name}}").unwrap();
+    match bike.process_dry_run(src.path(), &out, insert_name) {
+        Err(ProcessingError::TemplateRenderFailed { .. }) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(!out.exists());
+}
+
+#[test]
+fn path_sanitizer() {
+    let src = fixture(&[("{{name}}/{{name}}.rs.hbs", "// {{name}}")]);
+    let dst = tempfile::tempdir().unwrap();
+    let mut bike = Bicycle::default();
+    bike.set_path_sanitizer(Some(&bicycle::sanitize_path_segment));
+    bike.process(src.path(), dst.path(), |map| map.insert("name", "a/.."))
+        .unwrap();
+    assert_eq!(tree(dst.path())["a_../a_...rs"].as_deref(), Some("// a/.."));
+}
+
+mod subtree {
+    use super::common::{fixture, tree};
+    use bicycle::{Bicycle, ProcessingError};
+
+    fn sample() -> tempfile::TempDir {
+        fixture(&[
+            ("_data.json", r#"{ "org": "nerv" }"#),
+            ("README.md", "root"),
+            ("services/{{name}}/main.rs.hbs", "// {{org}}/{{name}}"),
+            ("services/{{name}}/Cargo.toml", "static"),
+            ("services/other/lib.rs", "other"),
+            ("tools/build.sh", "build"),
+        ])
+    }
+
+    #[test]
+    fn keeps_full_path() {
+        let src = sample();
+        let dst = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        bike.set_directory_data_name(Some("_data.json"));
+        bike.process_subtree(src.path(), "services/{{name}}", dst.path(), |map| {
+            map.insert("name", "magi")
+        })
+        .unwrap();
+        let tree = tree(dst.path());
+        assert_eq!(
+            tree.keys().map(String::as_str).collect::<Vec<_>>(),
+            [
+                "services",
+                "services/magi",
+                "services/magi/Cargo.toml",
+                "services/magi/main.rs",
+            ]
+        );
+        assert_eq!(
+            tree["services/magi/main.rs"].as_deref(),
+            Some("// nerv/magi")
+        );
+    }
+
+    #[test]
+    fn not_relative() {
+        let src = sample();
+        let dst = tempfile::tempdir().unwrap();
+        for subtree in ["../services", "services/../../elsewhere"] {
+            match Bicycle::default().process_subtree(src.path(), subtree, dst.path(), |_| ()) {
+                Err(ProcessingError::SubtreeNotRelative { subtree: actual }) => {
+                    assert_eq!(actual, std::path::Path::new(subtree))
+                }
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+        assert!(tree(dst.path()).is_empty());
+    }
+}
+
+mod parallel {
+    use super::common::{fixture, tree};
+    use bicycle::{no_transform, traverse, Action, Bicycle, ProcessingError, Tag};
+
+    fn many(count: usize) -> tempfile::TempDir {
+        let files = (0..count)
+            .map(|i| {
+                (
+                    format!("dir{}/sub{}/file{}.txt.hbs", i % 7, i % 3, i),
+                    format!("{} {{{{name}}}}", i),
+                )
+            })
+            .collect::<Vec<_>>();
+        fixture(
+            &files
+                .iter()
+                .map(|(path, contents)| (path.as_str(), contents.as_str()))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn max_concurrency() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let src = many(300);
+        let dst = tempfile::tempdir().unwrap();
+        let actions =
+            Vec::from(traverse(src.path(), dst.path(), no_transform, Some("hbs")).unwrap());
+        for (max, expected) in [(Some(2), 2), (Some(0), 1)] {
+            let (running, most) = (AtomicUsize::new(0), AtomicUsize::new(0));
+            let mut bike = Bicycle::default();
+            bike.set_max_concurrency(max);
+            // Data is inserted while rendering, so this sees every worker
+            // that's rendering a template.
+            bike.process_actions_par(
+                &actions,
+                |map| {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    most.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(1));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    super::insert_name(map);
+                },
+                |_| (),
+            )
+            .unwrap();
+            assert_eq!(most.into_inner(), expected, "with {:?}", max);
+        }
+    }
+
+    #[test]
+    fn ordered_progress() {
+        let src = many(40);
+        let dst = tempfile::tempdir().unwrap();
+        let actions =
+            Vec::from(traverse(src.path(), dst.path(), no_transform, Some("hbs")).unwrap());
+        let mut bike = Bicycle::default();
+        bike.set_max_concurrency(Some(4));
+        let mut reported = Vec::new();
+        bike.process_actions_par(&actions, super::insert_name, |action| {
+            reported.push(action.dst().to_owned())
+        })
+        .unwrap();
+        assert_eq!(
+            reported,
+            actions
+                .iter()
+                .map(|action| action.dst().to_owned())
+                .collect::<Vec<_>>()
+        );
+        let tree = tree(dst.path());
+        for i in 0..40 {
+            assert_eq!(
+                tree[&format!("dir{}/sub{}/file{}.txt", i % 7, i % 3, i)],
+                Some(format!("{} shinji", i))
+            );
+        }
+    }
+
+    #[test]
+    fn matches_sequential() {
+        let src = many(300);
+        let (par, seq) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let bike = Bicycle::default();
+        let actions =
+            Vec::from(traverse(src.path(), par.path(), no_transform, Some("hbs")).unwrap());
+        bike.process_actions_parallel(&actions, super::insert_name)
+            .unwrap();
+        bike.process(src.path(), seq.path(), super::insert_name)
+            .unwrap();
+        let tree = tree(par.path());
+        assert_eq!(tree.values().filter(|file| file.is_some()).count(), 300);
+        assert_eq!(tree, super::tree(seq.path()));
+    }
+
+    #[test]
+    fn appends_stay_in_order() {
+        let src = fixture(&[
+            ("a/log.txt.hbs", "a\n"),
+            ("b/log.txt.hbs", "b\n"),
+            ("c/log.txt.hbs", "c\n"),
+        ]);
+        let dst = tempfile::tempdir().unwrap();
+        let actions = ["c", "a", "b"]
+            .iter()
+            .map(|dir| {
+                let template = src.path().join(dir).join("log.txt.hbs");
+                Action::new(template, dst.path(), no_transform, Tag::AppendTemplate).unwrap()
+            })
+            .collect::<Vec<_>>();
+        Bicycle::default()
+            .process_actions_par(&actions, |_| (), |_| ())
+            .unwrap();
+        assert_eq!(tree(dst.path())["log.txt"].as_deref(), Some("c\na\nb\n"));
+    }
+
+    #[test]
+    fn earliest_error() {
+        let src = fixture(&[
+            ("a.txt.hbs", "{{name}}"),
+            ("b.txt.hbs", "{{missing_b}}"),
+            ("c.txt.hbs", "{{name}}"),
+            ("d.txt.hbs", "{{missing_d}}"),
+        ]);
+        let dst = tempfile::tempdir().unwrap();
+        let mut actions =
+            Vec::from(traverse(src.path(), dst.path(), no_transform, Some("hbs")).unwrap());
+        actions.sort_by(|a, b| a.src().cmp(b.src()));
+        let mut bike = Bicycle::default();
+        bike.set_max_concurrency(Some(1));
+        let mut reported = Vec::new();
+        let err = bike
+            .process_actions_par(&actions, super::insert_name, |action| {
+                reported.push(action.src().to_owned())
+            })
+            .unwrap_err();
+        match err {
+            ProcessingError::TemplateRenderFailed { src: failed, .. } => {
+                assert_eq!(failed, src.path().join("b.txt.hbs"))
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert_eq!(
+            reported,
+            [src.path().to_owned(), src.path().join("a.txt.hbs")]
+        );
+    }
+}
@@ -0,0 +1,606 @@
+use bicycle::{Bicycle, JsonMap};
+
+#[test]
+fn compiled_matches_render() {
+    let bike = Bicycle::default();
+    let template = "{{greeting}}, {{name}}!";
+    let compiled = bike.compile(template).unwrap();
+    for name in &["Shinji", "Asuka", "Rei"] {
+        let insert_data = |map: &mut bicycle::JsonMap| {
+            map.insert("greeting", "Hello");
+            map.insert("name", name);
+        };
+        assert_eq!(
+            compiled.render(insert_data).unwrap(),
+            bike.render(template, insert_data).unwrap(),
+        );
+    }
+}
+
+#[test]
+fn named_matches_render() {
+    let mut bike = Bicycle::default();
+    let template = "{{#each items}}{{@index}}: {{this}} {{/each}}for {{name}}";
+    bike.register_template("list", template).unwrap();
+    for i in 0..1000 {
+        let insert_data = |map: &mut JsonMap| {
+            map.insert("items", vec![i, i * 2, i * 3]);
+            map.insert("name", format!("pilot {}", i));
+        };
+        assert_eq!(
+            bike.render_named("list", insert_data).unwrap(),
+            bike.render(template, insert_data).unwrap(),
+        );
+    }
+}
+
+#[test]
+fn named_errors() {
+    let mut bike = Bicycle::default();
+    assert!(matches!(
+        bike.register_template("broken", "{{#if}}"),
+        Err(bicycle::RenderingError::TemplateRegistrationFailed { .. })
+    ));
+    assert!(matches!(
+        bike.render_named("missing", |_| ()),
+        Err(bicycle::RenderingError::RenderingFailed(_))
+    ));
+}
+
+#[test]
+fn compile_reports_syntax_errors() {
+    let bike = Bicycle::default();
+    assert!(bike.compile("{{#if}}").is_err());
+}
+
+mod extra_helpers {
+    use bicycle::{handlebars::handlebars_helper, Bicycle, HelperDef};
+    use std::iter;
+
+    handlebars_helper!(shout: |s: str| s.to_uppercase());
+
+    fn shout_helper() -> iter::Once<(&'static str, Box<dyn HelperDef + Send + Sync>)> {
+        iter::once(("shout", Box::new(shout)))
+    }
+
+    #[test]
+    fn available_during_render() {
+        let bike = Bicycle::default();
+        let rendered = bike
+            .render_with_helpers("{{shout name}}", shout_helper(), |map| {
+                map.insert("name", "Shinji");
+            })
+            .unwrap();
+        assert_eq!(rendered, "SHINJI");
+    }
+
+    #[test]
+    fn not_retained_after_render() {
+        let bike = Bicycle::default();
+        bike.render_with_helpers("{{shout name}}", shout_helper(), |map| {
+            map.insert("name", "Shinji");
+        })
+        .unwrap();
+        assert!(bike
+            .render("{{shout name}}", |map| {
+                map.insert("name", "Shinji");
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn not_retained_after_failed_render() {
+        let bike = Bicycle::default();
+        // `name` is missing, so this fails in strict mode.
+        assert!(bike
+            .render_with_helpers("{{shout name}}", shout_helper(), |_| ())
+            .is_err());
+        assert!(bike
+            .render("{{shout name}}", |map| {
+                map.insert("name", "Shinji");
+            })
+            .is_err());
+    }
+}
+
+mod try_new {
+    use bicycle::{Bicycle, EscapeFn, JsonMap};
+    use std::iter;
+
+    #[test]
+    fn registers_partials() {
+        let bike = Bicycle::try_new(
+            EscapeFn::None,
+            iter::empty(),
+            vec![("header", "# {{title}}"), ("footer", "fin")],
+            JsonMap::default(),
+        )
+        .unwrap();
+        let rendered = bike
+            .render("{{> header}} / {{> footer}}", |map| {
+                map.insert("title", "Evangelion");
+            })
+            .unwrap();
+        assert_eq!(rendered, "# Evangelion / fin");
+    }
+
+    #[test]
+    fn reports_invalid_partials() {
+        let result = Bicycle::try_new(
+            EscapeFn::None,
+            iter::empty(),
+            iter::once(("broken", "{{#if}}")),
+            JsonMap::default(),
+        );
+        assert!(result.is_err());
+    }
+}
+
+mod register_partial {
+    use bicycle::{Bicycle, RenderingError};
+    use std::fs;
+
+    #[test]
+    fn after_construction() {
+        let mut bike = Bicycle::default();
+        bike.register_partial("header", "# {{title}}").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let footer = dir.path().join("footer.hbs");
+        fs::write(&footer, "fin").unwrap();
+        bike.register_partial_file("footer", &footer).unwrap();
+        assert_eq!(bike.registered_partials(), ["footer", "header"]);
+        let rendered = bike
+            .render("{{> header}} / {{> footer}}", |map| {
+                map.insert("title", "Evangelion");
+            })
+            .unwrap();
+        assert_eq!(rendered, "# Evangelion / fin");
+    }
+
+    #[test]
+    fn failures() {
+        let mut bike = Bicycle::default();
+        let dir = tempfile::tempdir().unwrap();
+        for result in [
+            bike.register_partial("broken", "{{#if}}"),
+            bike.register_partial_file("missing", dir.path().join("missing.hbs")),
+        ] {
+            assert!(
+                matches!(
+                    result,
+                    Err(RenderingError::PartialRegistrationFailed { .. })
+                ),
+                "unexpected result: {:?}",
+                result
+            );
+        }
+        assert!(bike.registered_partials().is_empty());
+    }
+}
+
+#[test]
+fn boxed_custom_escape_fn() {
+    fn bike(replacement: char) -> Bicycle {
+        Bicycle::new(
+            bicycle::EscapeFn::BoxedCustom(Box::new(move |raw| {
+                raw.replace(' ', &replacement.to_string())
+            })),
+            std::iter::empty(),
+            JsonMap::default(),
+        )
+    }
+    for (replacement, expected) in [('_', "Shinji_Ikari"), ('-', "Shinji-Ikari")] {
+        let rendered = bike(replacement)
+            .render("{{name}}", |map| map.insert("name", "Shinji Ikari"))
+            .unwrap();
+        assert_eq!(rendered, expected);
+    }
+}
+
+#[test]
+fn decorator_via_handlebars_mut() {
+    use bicycle::handlebars::{Context, Decorator, Handlebars, RenderContext, RenderError};
+
+    fn shout_name(
+        _: &Decorator,
+        _: &Handlebars,
+        ctx: &Context,
+        rc: &mut RenderContext,
+    ) -> Result<(), RenderError> {
+        let mut data = ctx.data().clone();
+        let name = data["name"].as_str().unwrap_or_default().to_uppercase();
+        data["name"] = name.into();
+        rc.set_context(Context::wraps(data)?);
+        Ok(())
+    }
+
+    let mut bike = Bicycle::default();
+    bike.handlebars_mut()
+        .register_decorator("shout_name", Box::new(shout_name));
+    let rendered = bike
+        .render("{{*shout_name}}Hello {{name}}!", |map| {
+            map.insert("name", "Shinji")
+        })
+        .unwrap();
+    assert_eq!(rendered, "Hello SHINJI!");
+}
+
+#[test]
+fn prevent_indent() {
+    let mut bike = Bicycle::default();
+    bike.register_partial("item", "- {{name}}\n  ({{role}})\n")
+        .unwrap();
+    let render = |bike: &Bicycle| {
+        bike.render("list:\n  {{> item}}\n", |map| {
+            map.insert("name", "Shinji");
+            map.insert("role", "pilot");
+        })
+        .unwrap()
+    };
+    assert_eq!(render(&bike), "list:\n  - Shinji\n    (pilot)\n");
+    bike.set_prevent_indent(true);
+    assert_eq!(render(&bike), "list:\n  - Shinji\n  (pilot)\n");
+}
+
+mod escape_fn_panics {
+    use bicycle::{Bicycle, EscapeFn, JsonMap, RenderingError};
+    use std::iter;
+
+    fn explosive(raw: &str) -> String {
+        if raw.contains("boom") {
+            panic!("can't escape {}", raw);
+        }
+        raw.to_owned()
+    }
+
+    fn bike(catch: bool) -> Bicycle {
+        let mut bike = Bicycle::new(
+            EscapeFn::Custom(&explosive),
+            iter::empty(),
+            JsonMap::default(),
+        );
+        bike.set_catch_escape_fn_panics(catch);
+        bike
+    }
+
+    #[test]
+    fn caught() {
+        let bike = bike(true);
+        let result = bike.render("{{word}}", |map| map.insert("word", "kaboom"));
+        match result {
+            Err(RenderingError::EscapeFnPanicked { input, message }) => {
+                assert_eq!(input, "kaboom");
+                assert_eq!(message, "can't escape kaboom");
+            }
+            other => panic!("expected a caught panic, but got {:?}", other),
+        }
+        // The instance is still perfectly usable afterward.
+        let rendered = bike
+            .render("{{word}}", |map| map.insert("word", "fine"))
+            .unwrap();
+        assert_eq!(rendered, "fine");
+    }
+
+    #[test]
+    #[should_panic(expected = "can't escape kaboom")]
+    fn not_caught_by_default() {
+        let _ = bike(false).render("{{word}}", |map| map.insert("word", "kaboom"));
+    }
+}
+
+mod referenced_variables {
+    use bicycle::{handlebars::handlebars_helper, Bicycle, EscapeFn, HelperDef, JsonMap};
+    use std::iter;
+
+    handlebars_helper!(shout: |s: str| s.to_uppercase());
+    handlebars_helper!(today: | | "today");
+
+    fn bike() -> Bicycle {
+        Bicycle::try_new(
+            EscapeFn::None,
+            vec![
+                ("shout", Box::new(shout) as Box<dyn HelperDef + Send + Sync>),
+                ("today", Box::new(today)),
+            ],
+            iter::once(("signature", "{{author}}")),
+            JsonMap::default(),
+        )
+        .unwrap()
+    }
+
+    fn variables(template: &str) -> Vec<String> {
+        bike().referenced_variables(template).unwrap()
+    }
+
+    #[test]
+    fn plain() {
+        assert_eq!(variables("{{a}} {{b.c}} {{a}}"), vec!["a", "b.c"]);
+    }
+
+    #[test]
+    fn helpers() {
+        assert_eq!(
+            variables("{{today}} {{shout name}} {{shout (shout title)}} {{eq x 1}}"),
+            vec!["name", "title", "x"],
+        );
+    }
+
+    #[test]
+    fn blocks() {
+        assert_eq!(
+            variables(concat!(
+                "{{#if enabled}}{{on}}{{else}}{{off}}{{/if}}",
+                "{{#each items}}{{label}}{{@index}}{{../heading}}{{@root.footer}}",
+                "{{else}}{{empty}}{{/each}}",
+                "{{#with user}}{{name}}{{/with}}",
+            )),
+            vec!["enabled", "on", "off", "items", "heading", "footer", "empty", "user"],
+        );
+    }
+
+    #[test]
+    fn partials() {
+        assert_eq!(
+            variables("{{> signature}} {{date}}"),
+            vec!["author", "date"]
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(bike().referenced_variables("{{#if}}").is_err());
+    }
+}
+
+mod strict_missing {
+    use bicycle::{handlebars::Handlebars, RenderingError};
+    use serde_json::json;
+
+    fn strict_render(template: &str) -> RenderingError {
+        let mut handlebars = Handlebars::new();
+        handlebars.set_strict_mode(true);
+        handlebars
+            .render_template(template, &json!({ "present": 1 }))
+            .unwrap_err()
+            .into()
+    }
+
+    #[test]
+    fn missing_variable() {
+        let err = strict_render("{{present}} {{absent.field}}");
+        assert!(err.is_strict_missing());
+        assert_eq!(err.strict_missing_path().as_deref(), Some("absent.field"));
+    }
+
+    // `RenderingError::strict_missing` matches on the descriptions handlebars
+    // gives these errors, so this breaks if a handlebars upgrade rewords them.
+    #[test]
+    fn handlebars_wording() {
+        let err = strict_render("{{absent}}");
+        match &err {
+            RenderingError::RenderingFailed(cause) => {
+                assert_eq!(cause.desc, r#"Variable "absent" not found in strict mode."#)
+            }
+            other => panic!("expected RenderingFailed, got {:?}", other),
+        }
+        assert!(err.is_strict_missing());
+        assert_eq!(err.strict_missing_path().as_deref(), Some("absent"));
+
+        let err = strict_render(r#"{{lookup this "absent"}}"#);
+        match &err {
+            RenderingError::RenderingFailed(cause) => {
+                assert_eq!(cause.desc, "Value is missing in strict mode")
+            }
+            other => panic!("expected RenderingFailed, got {:?}", other),
+        }
+        assert!(err.is_strict_missing());
+        assert_eq!(err.strict_missing_path(), None);
+    }
+
+    #[test]
+    fn other_errors() {
+        let err = strict_render("{{#if}}{{/if}}");
+        assert!(!err.is_strict_missing());
+        assert_eq!(err.strict_missing_path(), None);
+    }
+}
+
+mod delimiters {
+    use bicycle::Bicycle;
+
+    fn render(template: &str) -> String {
+        let mut bike = Bicycle::default();
+        bike.set_delimiters(Some(("<%", "%>")));
+        bike.render(template, |map| {
+            map.insert("name", "shinji");
+            map.insert("admin", true);
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn translated() {
+        assert_eq!(render("<%name%> <%#if admin%>admin<%/if%>"), "shinji admin");
+    }
+
+    #[test]
+    fn literal_braces_preserved() {
+        assert_eq!(
+            render("${{ secrets.<%name%> }} {{{raw}}} {{{{x}}}}"),
+            "${{ secrets.shinji }} {{{raw}}} {{{{x}}}}"
+        );
+    }
+
+    #[test]
+    fn escaped_delimiters() {
+        assert_eq!(render(r"\<%name\%> <%name%>"), "<%name%> shinji");
+    }
+
+    #[test]
+    fn literal_backslash_before_braces() {
+        assert_eq!(
+            render(r"\{{ <%name%> }} \\{{x}} a\b"),
+            r"\{{ shinji }} \\{{x}} a\b"
+        );
+    }
+
+    #[test]
+    fn compiled() {
+        let mut bike = Bicycle::default();
+        bike.set_delimiters(Some(("[[", "]]")));
+        let rendered = bike
+            .compile("{{ [[name]] }}")
+            .unwrap()
+            .render(|map| map.insert("name", "asuka"))
+            .unwrap();
+        assert_eq!(rendered, "{{ asuka }}");
+    }
+}
+
+mod registered {
+    use bicycle::{handlebars::handlebars_helper, Bicycle, EscapeFn, HelperDef, JsonMap};
+
+    handlebars_helper!(shout: |s: str| s.to_uppercase());
+
+    #[test]
+    fn names() {
+        let bike = Bicycle::try_new(
+            EscapeFn::None,
+            vec![("shout", Box::new(shout) as Box<dyn HelperDef + Send + Sync>)],
+            vec![("header", "# {{title}}"), ("footer", "bye")],
+            JsonMap::default(),
+        )
+        .unwrap();
+        let helpers = bike.registered_helpers();
+        assert!(helpers.contains(&"shout".to_owned()));
+        assert!(helpers.contains(&"if".to_owned()));
+        assert!(helpers.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(bike.registered_partials(), ["footer", "header"]);
+        assert_eq!(bike.registered_templates(), ["footer", "header"]);
+        let handlebars: &bicycle::handlebars::Handlebars = bike.as_ref();
+        assert!(handlebars.has_template("header"));
+    }
+}
+
+#[cfg(feature = "script_helper")]
+mod script_helper {
+    use bicycle::{Bicycle, RenderingError};
+    use std::fs;
+
+    #[test]
+    fn params_and_hash() {
+        let mut bike = Bicycle::default();
+        bike.register_script_helper("greet", r#"hash["greeting"] + ", " + params[0]"#)
+            .unwrap();
+        let rendered = bike
+            .render(r#"{{greet name greeting="hi"}}"#, |map| {
+                map.insert("name", "shinji")
+            })
+            .unwrap();
+        assert_eq!(rendered, "hi, shinji");
+        assert!(bike.registered_helpers().contains(&"greet".to_owned()));
+    }
+
+    #[test]
+    fn invalid_script() {
+        let mut bike = Bicycle::default();
+        assert!(bike
+            .register_script_helper("broken", "params[0] +")
+            .is_err());
+    }
+
+    #[test]
+    fn sandboxed() {
+        let mut bike = Bicycle::default();
+        bike.register_script_helper("spin", "loop {}").unwrap();
+        assert!(bike.render("{{spin}}", |_| ()).is_err());
+        bike.register_script_helper("import", r#"import "secrets" as s; s::x"#)
+            .unwrap();
+        assert!(bike.render("{{import}}", |_| ()).is_err());
+        assert!(bike
+            .register_script_helper("eval", r#"eval("40 + 2")"#)
+            .and_then(|()| bike.render("{{eval}}", |_| ()))
+            .is_err());
+    }
+
+    #[test]
+    fn from_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("concat.rhai"), r#"params[0] + params[1]"#).unwrap();
+        fs::write(dir.path().join("shout.rhai"), "params[0].to_upper()").unwrap();
+        fs::write(dir.path().join("notes.txt"), "not a script").unwrap();
+        fs::create_dir(dir.path().join("nested.rhai")).unwrap();
+        let mut bike = Bicycle::default();
+        assert_eq!(
+            bike.register_script_helpers_dir(dir.path()).unwrap(),
+            ["concat", "shout"]
+        );
+        bike.register_script_helper_file("again", dir.path().join("concat.rhai"))
+            .unwrap();
+        let rendered = bike
+            .render(
+                r#"{{concat "shinji" "ikari"}} {{shout (again "ev" "a")}}"#,
+                |_| (),
+            )
+            .unwrap();
+        assert_eq!(rendered, "shinjiikari EVA");
+    }
+
+    #[test]
+    fn file_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut bike = Bicycle::default();
+        let missing = dir.path().join("missing.rhai");
+        match bike.register_script_helper_file("missing", &missing) {
+            Err(RenderingError::ScriptHelperReadFailed { path, .. }) => assert_eq!(path, missing),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        fs::write(dir.path().join("broken.rhai"), "params[0] +").unwrap();
+        assert!(bike.register_script_helpers_dir(dir.path()).is_err());
+        // Script errors show up when rendering.
+        fs::write(dir.path().join("broken.rhai"), r#"throw "nope""#).unwrap();
+        bike.register_script_helpers_dir(dir.path()).unwrap();
+        assert!(bike.render("{{broken}}", |_| ()).is_err());
+    }
+}
+
+#[test]
+fn render_to_writer() {
+    let bike = Bicycle::default();
+    let template = "{{#each units}}{{this}} {{/each}}{{name}}";
+    let insert_data = |map: &mut JsonMap| {
+        map.insert("name", "shinji");
+        map.insert("units", [0, 1, 2]);
+    };
+    let mut written = Vec::new();
+    bike.render_to_writer(template, &mut written, insert_data)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(written).unwrap(),
+        bike.render(template, insert_data).unwrap()
+    );
+}
+
+#[test]
+fn strict_mode_off() {
+    let mut bike = Bicycle::default();
+    assert!(bike.render("{{missing}}", |_| ()).is_err());
+    bike.set_strict_mode(false);
+    assert_eq!(bike.render("{{missing}}", |_| ()).unwrap(), "");
+}
+
+#[test]
+fn register_helper() {
+    use bicycle::handlebars::handlebars_helper;
+
+    handlebars_helper!(shout: |s: str| s.to_uppercase());
+
+    let mut bike = Bicycle::default();
+    assert!(bike.render("{{shout name}}", |_| ()).is_err());
+    bike.register_helper("shout", Box::new(shout));
+    assert!(bike.registered_helpers().iter().any(|name| name == "shout"));
+    let rendered = bike
+        .render("{{shout name}}", |map| map.insert("name", "gendo"))
+        .unwrap();
+    assert_eq!(rendered, "GENDO");
+}
@@ -10,3 +10,9 @@ fn bicycle_send() {
 fn bicycle_sync() {
     require_sync::<bicycle::Bicycle>();
 }
+
+#[test]
+fn mem_fs_send_sync() {
+    require_send::<bicycle::MemFs>();
+    require_sync::<bicycle::MemFs>();
+}
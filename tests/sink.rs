@@ -0,0 +1,153 @@
+mod common;
+
+use self::common::fixture;
+use bicycle::{Bicycle, JsonMap, MemorySink, Sink};
+use std::{
+    collections::BTreeMap,
+    io,
+    path::{Path, PathBuf},
+};
+
+fn insert_name(map: &mut JsonMap) {
+    map.insert("name", "asuka");
+}
+
+fn sample() -> tempfile::TempDir {
+    let src = fixture(&[
+        ("README.md", "static"),
+        ("{{name}}/mod.rs.hbs", "// {{name}}"),
+        ("bin/run.sh", "#!/bin/sh"),
+    ]);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt as _;
+        let script = src.path().join("bin/run.sh");
+        std::fs::set_permissions(script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    src
+}
+
+#[derive(Default)]
+struct Recorder {
+    dirs: Vec<PathBuf>,
+    files: BTreeMap<PathBuf, (String, Option<u32>)>,
+}
+
+impl Sink for Recorder {
+    fn create_dir(&mut self, path: &Path) -> io::Result<()> {
+        self.dirs.push(path.to_owned());
+        Ok(())
+    }
+
+    fn write_file(&mut self, path: &Path, contents: &[u8], mode: Option<u32>) -> io::Result<()> {
+        let parent = path.parent().unwrap();
+        assert!(
+            parent.as_os_str().is_empty() || self.dirs.iter().any(|dir| dir == parent),
+            "{:?} was written before its parent directory",
+            path
+        );
+        self.files.insert(
+            path.to_owned(),
+            (String::from_utf8(contents.to_vec()).unwrap(), mode),
+        );
+        Ok(())
+    }
+}
+
+#[test]
+fn process_into() {
+    let src = sample();
+    let mut recorder = Recorder::default();
+    Bicycle::default()
+        .process_into(src.path(), &mut recorder, insert_name)
+        .unwrap();
+    recorder.dirs.sort();
+    assert_eq!(
+        recorder.dirs,
+        [PathBuf::from("asuka"), PathBuf::from("bin")]
+    );
+    assert_eq!(recorder.files[Path::new("README.md")].0, "static");
+    assert_eq!(
+        recorder.files[&Path::new("asuka").join("mod.rs")].0,
+        "// asuka"
+    );
+    #[cfg(unix)]
+    assert_eq!(
+        recorder.files[&Path::new("bin").join("run.sh")].1,
+        Some(0o755)
+    );
+}
+
+#[test]
+fn memory() {
+    let src = sample();
+    let mut sink = MemorySink::default();
+    Bicycle::default()
+        .process_into(src.path(), &mut sink, insert_name)
+        .unwrap();
+    assert_eq!(
+        sink.dirs().iter().collect::<Vec<_>>(),
+        [Path::new("asuka"), Path::new("bin")]
+    );
+    let files = sink.into_files();
+    assert_eq!(files.len(), 3);
+    assert_eq!(files[Path::new("README.md")], b"static");
+    assert_eq!(files[&Path::new("asuka").join("mod.rs")], b"// asuka");
+    assert_eq!(files[&Path::new("bin").join("run.sh")], b"#!/bin/sh");
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn zip() {
+    use std::io::Read as _;
+    let src = sample();
+    let mut sink = bicycle::ZipSink::new(io::Cursor::new(Vec::new()));
+    Bicycle::default()
+        .process_into(src.path(), &mut sink, insert_name)
+        .unwrap();
+    let mut archive = zip::ZipArchive::new(sink.finish().unwrap()).unwrap();
+    assert!(archive.by_name("bin/").unwrap().is_dir());
+    let mut contents = String::new();
+    archive
+        .by_name("asuka/mod.rs")
+        .unwrap()
+        .read_to_string(&mut contents)
+        .unwrap();
+    assert_eq!(contents, "// asuka");
+    #[cfg(unix)]
+    assert_eq!(
+        archive
+            .by_name("bin/run.sh")
+            .unwrap()
+            .unix_mode()
+            .map(|mode| mode & 0o777),
+        Some(0o755)
+    );
+}
+
+#[cfg(feature = "tar")]
+#[test]
+fn tar() {
+    use std::io::Read as _;
+    let src = sample();
+    let mut sink = bicycle::TarSink::new(Vec::new());
+    Bicycle::default()
+        .process_into(src.path(), &mut sink, insert_name)
+        .unwrap();
+    let bytes = sink.finish().unwrap();
+    let mut archive = tar::Archive::new(bytes.as_slice());
+    let mut entries = BTreeMap::new();
+    for entry in archive.entries().unwrap() {
+        let mut entry = entry.unwrap();
+        let mut contents = String::new();
+        entry.read_to_string(&mut contents).unwrap();
+        entries.insert(
+            entry.path().unwrap().to_string_lossy().into_owned(),
+            (contents, entry.header().mode().unwrap()),
+        );
+    }
+    assert_eq!(entries["asuka/mod.rs"].0, "// asuka");
+    assert_eq!(entries["README.md"].0, "static");
+    #[cfg(unix)]
+    assert_eq!(entries["bin/run.sh"].1, 0o755);
+}
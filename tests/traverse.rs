@@ -0,0 +1,452 @@
+use bicycle::{
+    changed_since, no_transform, traverse, traverse_collecting, traverse_filtered, traverse_iter,
+    traverse_with_collisions, Action, Bicycle, CollisionStrategy, Tag, TraversalError,
+    DEFAULT_TEMPLATE_EXT,
+};
+use std::path::{Path, PathBuf};
+
+mod common;
+
+use self::common::fixture;
+
+fn paths(paths: &[&str]) -> Vec<PathBuf> {
+    paths.iter().map(PathBuf::from).collect()
+}
+
+#[test]
+fn directory_destinations() {
+    let src = fixture(&[("a.txt", ""), ("sub/b.txt", "")]);
+    let actions = traverse(src.path(), "out", no_transform, DEFAULT_TEMPLATE_EXT).unwrap();
+    let mut dirs = actions
+        .iter()
+        .filter(|action| action.tag().create_directory())
+        .map(|action| action.dst().to_owned())
+        .collect::<Vec<_>>();
+    dirs.sort();
+    assert_eq!(dirs, paths(&["out", "out/sub"]));
+}
+
+#[test]
+fn transformed_destinations() {
+    let src = fixture(&[("{{name}}/{{name}}.txt.hbs", "")]);
+    let bike = Bicycle::default();
+    let actions = traverse(
+        src.path(),
+        "out",
+        |path| {
+            bike.transform_dst(path, |map| {
+                map.insert("name", "shinji");
+            })
+        },
+        DEFAULT_TEMPLATE_EXT,
+    )
+    .unwrap();
+    let template = actions
+        .iter()
+        .find(|action| action.tag().write_template())
+        .unwrap();
+    assert_eq!(template.dst(), Path::new("out/shinji/shinji.txt"));
+}
+
+#[test]
+fn transformed_destinations_always() {
+    let src = fixture(&[("__name__/main.rs", ""), ("plain/lib.rs", "")]);
+    let bike = Bicycle::default();
+    let actions = traverse(
+        src.path(),
+        "out",
+        |path| {
+            let path = path.to_str().unwrap().replace("__name__", "{{name}}");
+            bike.transform_dst_always(Path::new(&path), |map| {
+                map.insert("name", "shinji");
+            })
+        },
+        DEFAULT_TEMPLATE_EXT,
+    )
+    .unwrap();
+    let mut dsts = actions
+        .iter()
+        .map(|action| action.dst().to_owned())
+        .collect::<Vec<_>>();
+    dsts.sort();
+    assert_eq!(
+        dsts,
+        paths(&[
+            "out",
+            "out/plain",
+            "out/plain/lib.rs",
+            "out/shinji",
+            "out/shinji/main.rs"
+        ])
+    );
+}
+
+#[test]
+fn stripped_template_ext() {
+    let src = fixture(&[
+        (".env.hbs", ""),
+        (".hbs.hbs", ""),
+        ("README.hbs", ""),
+        ("config.toml.hbs", ""),
+        ("archive.tar.gz.hbs", ""),
+        ("nested/.gitignore.hbs", ""),
+        ("copied.toml", ""),
+    ]);
+    let mut dsts = traverse(src.path(), "out", no_transform, DEFAULT_TEMPLATE_EXT)
+        .unwrap()
+        .iter()
+        .filter(|action| !action.tag().create_directory())
+        .map(|action| action.dst().to_owned())
+        .collect::<Vec<_>>();
+    dsts.sort();
+    assert_eq!(
+        dsts,
+        paths(&[
+            "out/.env",
+            "out/.hbs",
+            "out/README",
+            "out/archive.tar.gz",
+            "out/config.toml",
+            "out/copied.toml",
+            "out/nested/.gitignore",
+        ])
+    );
+}
+
+#[test]
+fn traverse_iter_matches_traverse() {
+    let src = fixture(&[
+        ("a.txt", ""),
+        ("sub/b.txt.hbs", ""),
+        ("sub/deeper/c.txt", ""),
+    ]);
+    let summarize = |action: &Action| (action.tag().to_string(), action.dst().to_owned());
+    let lazy = traverse_iter(src.path(), "out", no_transform, DEFAULT_TEMPLATE_EXT)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    // Each directory is discovered before its contents.
+    for (index, action) in lazy.iter().enumerate() {
+        if let Some(parent) = action.dst().parent().filter(|_| index > 0) {
+            assert!(lazy[..index]
+                .iter()
+                .any(|dir| dir.tag().create_directory() && dir.dst() == parent));
+        }
+    }
+    let mut lazy = lazy.iter().map(summarize).collect::<Vec<_>>();
+    let mut eager = traverse(src.path(), "out", no_transform, DEFAULT_TEMPLATE_EXT)
+        .unwrap()
+        .iter()
+        .map(summarize)
+        .collect::<Vec<_>>();
+    lazy.sort();
+    eager.sort();
+    assert_eq!(lazy, eager);
+}
+
+#[test]
+fn filtered() {
+    let src = fixture(&[
+        ("a.txt", ""),
+        (".DS_Store", ""),
+        ("node_modules/dep/index.js", ""),
+        ("sub/b.txt.hbs", ""),
+    ]);
+    let seen = std::cell::RefCell::new(Vec::new());
+    let actions = traverse_filtered(
+        src.path(),
+        "out",
+        no_transform,
+        DEFAULT_TEMPLATE_EXT,
+        |path| {
+            let relative = path.strip_prefix(src.path()).unwrap().to_owned();
+            seen.borrow_mut().push(relative.clone());
+            relative != Path::new("node_modules") && relative != Path::new(".DS_Store")
+        },
+    )
+    .unwrap();
+    let mut dsts = actions
+        .iter()
+        .map(|action| action.dst().to_owned())
+        .collect::<Vec<_>>();
+    dsts.sort();
+    assert_eq!(
+        dsts,
+        paths(&["out", "out/a.txt", "out/sub", "out/sub/b.txt"])
+    );
+    // Pruned directories aren't descended into, so their contents are never
+    // even considered.
+    assert!(!seen
+        .borrow()
+        .iter()
+        .any(|path| path.starts_with("node_modules/dep")));
+}
+
+mod collecting {
+    use super::*;
+
+    fn sorted_dsts(actions: &std::collections::VecDeque<Action>) -> Vec<PathBuf> {
+        let mut dsts = actions
+            .iter()
+            .map(|action| action.dst().to_owned())
+            .collect::<Vec<_>>();
+        dsts.sort();
+        dsts
+    }
+
+    #[test]
+    fn continues_past_transform_errors() {
+        let src = fixture(&[("a/x.txt", ""), ("b/y.txt", ""), ("c/z.txt", "")]);
+        let (actions, errors) = traverse_collecting(
+            src.path(),
+            "out",
+            |path| {
+                if path.starts_with("out/b") {
+                    Err(std::io::Error::other("nope"))
+                } else {
+                    Ok(path.to_owned())
+                }
+            },
+            DEFAULT_TEMPLATE_EXT,
+        );
+        assert_eq!(
+            sorted_dsts(&actions),
+            paths(&["out", "out/a", "out/a/x.txt", "out/c", "out/c/z.txt"])
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            TraversalError::PathTransformFailed { path, .. } if path == Path::new("out/b")
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn continues_past_unreadable_directories() {
+        use std::{fs, os::unix::fs::PermissionsExt as _};
+
+        let src = fixture(&[("a/x.txt", ""), ("b/y.txt", ""), ("c/z.txt", "")]);
+        let locked = src.path().join("b");
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        // Permissions don't stop privileged users, so there'd be no error.
+        let readable = fs::read_dir(&locked).is_ok();
+        let (actions, errors) =
+            traverse_collecting(src.path(), "out", no_transform, DEFAULT_TEMPLATE_EXT);
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+        if readable {
+            return;
+        }
+        assert_eq!(
+            sorted_dsts(&actions),
+            paths(&["out", "out/a", "out/a/x.txt", "out/c", "out/c/z.txt"])
+        );
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            TraversalError::DirectoryReadFailed { path, .. } if path == &locked
+        ));
+    }
+}
+
+#[cfg(feature = "ignore")]
+mod ignore {
+    use super::*;
+    use bicycle::traverse_with_ignore;
+
+    fn traversed(src: &Path, patterns: &[&str]) -> Vec<PathBuf> {
+        let patterns = patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        let mut srcs =
+            traverse_with_ignore(src, "out", no_transform, DEFAULT_TEMPLATE_EXT, &patterns)
+                .unwrap()
+                .iter()
+                .map(|action| action.src().strip_prefix(src).unwrap().to_owned())
+                .collect::<Vec<_>>();
+        srcs.sort();
+        srcs
+    }
+
+    #[test]
+    fn file_patterns() {
+        let src = fixture(&[
+            ("keep.txt", ""),
+            ("debug.log", ""),
+            ("sub/trace.log", ""),
+            ("sub/important.log", ""),
+        ]);
+        assert_eq!(
+            traversed(src.path(), &["*.log", "!important.log"]),
+            paths(&["", "keep.txt", "sub", "sub/important.log"])
+        );
+    }
+
+    #[test]
+    fn directory_patterns() {
+        let src = fixture(&[
+            ("keep.txt", ""),
+            ("target/out.txt", ""),
+            ("sub/target/out.txt", ""),
+            ("sub/target.txt", ""),
+        ]);
+        assert_eq!(
+            traversed(src.path(), &["target/"]),
+            paths(&["", "keep.txt", "sub", "sub/target.txt"])
+        );
+    }
+
+    #[test]
+    fn ignore_file() {
+        let src = fixture(&[
+            (
+                bicycle::IGNORE_FILE_NAME,
+                "# scratch files\n/notes.md\nnode_modules/\n",
+            ),
+            ("notes.md", ""),
+            ("node_modules/dep/index.js", ""),
+            ("sub/notes.md", ""),
+            ("sub/secret.txt", ""),
+        ]);
+        assert_eq!(
+            traversed(src.path(), &["secret.txt"]),
+            paths(&["", "sub", "sub/notes.md"])
+        );
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let src = fixture(&[("keep.txt", "")]);
+        assert!(matches!(
+            traverse_with_ignore(
+                src.path(),
+                "out",
+                no_transform,
+                DEFAULT_TEMPLATE_EXT,
+                &["{unclosed".to_owned()],
+            ),
+            Err(TraversalError::IgnoreParseFailed { .. })
+        ));
+    }
+}
+
+#[test]
+fn changed_since_timestamp() {
+    let src = fixture(&[("old.txt", ""), ("sub/old.txt.hbs", "")]);
+    let since = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+    let touch = |path: &str| {
+        let file = std::fs::File::options()
+            .append(true)
+            .create(true)
+            .open(src.path().join(path))
+            .unwrap();
+        file.set_modified(since + std::time::Duration::from_secs(1))
+            .unwrap();
+    };
+    touch("new.txt");
+    touch("sub/new.txt.hbs");
+    let mut changed = changed_since(src.path(), since, DEFAULT_TEMPLATE_EXT)
+        .unwrap()
+        .into_iter()
+        .map(|(path, tag)| (path, tag.to_string()))
+        .collect::<Vec<_>>();
+    changed.sort();
+    assert_eq!(
+        changed,
+        [
+            (src.path().join("new.txt"), "copy".to_owned()),
+            (
+                src.path().join("sub/new.txt.hbs"),
+                "render template".to_owned()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn describe() {
+    let action = Action::new("foo.rs.hbs", "", no_transform, Tag::WriteTemplate).unwrap();
+    assert_eq!(action.describe(), "render template foo.rs.hbs -> foo.rs");
+    let action = Action::new("README.md", "", no_transform, Tag::CopyFile).unwrap();
+    assert_eq!(action.describe(), "copy README.md -> README.md");
+    let action = Action::new("src", "out", no_transform, Tag::CreateDirectory).unwrap();
+    assert_eq!(action.describe(), "create directory out");
+}
+
+mod collisions {
+    use super::*;
+
+    fn file_dsts(strategy: CollisionStrategy) -> Vec<PathBuf> {
+        let src = fixture(&[("name.rs", ""), ("name.rs.hbs", ""), ("sub/x", "")]);
+        let mut dsts = traverse_with_collisions(
+            src.path(),
+            "out",
+            no_transform,
+            DEFAULT_TEMPLATE_EXT,
+            strategy,
+        )
+        .unwrap()
+        .iter()
+        .filter(|action| !matches!(action.tag(), Tag::CreateDirectory))
+        .map(|action| action.dst().to_owned())
+        .collect::<Vec<_>>();
+        dsts.sort();
+        dsts
+    }
+
+    #[test]
+    fn overwrite() {
+        assert_eq!(
+            file_dsts(CollisionStrategy::Overwrite),
+            paths(&["out/name.rs", "out/name.rs", "out/sub/x"]),
+        );
+    }
+
+    #[test]
+    fn suffix() {
+        assert_eq!(
+            file_dsts(CollisionStrategy::Suffix),
+            paths(&["out/name-2.rs", "out/name.rs", "out/sub/x"]),
+        );
+    }
+
+    #[test]
+    fn suffix_counts_up() {
+        let src = fixture(&[("{{a}}.hbs", ""), ("{{b}}.hbs", ""), ("{{c}}.hbs", "")]);
+        let bike = Bicycle::default();
+        let mut dsts = traverse_with_collisions(
+            src.path(),
+            "out",
+            |path| {
+                bike.transform_dst(path, |map| {
+                    map.insert("a", "same");
+                    map.insert("b", "same");
+                    map.insert("c", "same");
+                })
+            },
+            DEFAULT_TEMPLATE_EXT,
+            CollisionStrategy::Suffix,
+        )
+        .unwrap()
+        .iter()
+        .filter(|action| action.tag().write_template())
+        .map(|action| action.dst().to_owned())
+        .collect::<Vec<_>>();
+        dsts.sort();
+        assert_eq!(dsts, paths(&["out/same", "out/same-2", "out/same-3"]));
+    }
+
+    #[test]
+    fn error() {
+        let src = fixture(&[("name.rs", ""), ("name.rs.hbs", "")]);
+        let result = traverse_with_collisions(
+            src.path(),
+            "out",
+            no_transform,
+            DEFAULT_TEMPLATE_EXT,
+            CollisionStrategy::Error,
+        );
+        match result {
+            Err(TraversalError::DestinationCollision { dst, .. }) => {
+                assert_eq!(dst, Path::new("out/name.rs"))
+            }
+            other => panic!("expected a collision, but got {:?}", other),
+        }
+    }
+}